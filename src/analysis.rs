@@ -0,0 +1,540 @@
+mod completion;
+mod navigation;
+mod outline;
+
+use crate::ast;
+use crate::hir::{self, CharacterIdx};
+use crate::lexer::SyntaxKind;
+use crate::parser::{parse, Edit, Parse};
+use crate::syntax::SyntaxNode;
+use crate::validation::{self, ItemError, ValidationError};
+use arrayvec::ArrayVec;
+use rowan::GreenNode;
+use std::collections::HashMap;
+use std::rc::Rc;
+use text_size::{TextRange, TextSize};
+
+pub(crate) use outline::{OutlineItem, OutlineKind};
+
+// Arena slots for expressions of items that have since been edited away are
+// only reclaimed by starting over, which happens once they outnumber the live
+// ones by this much.
+const MAX_DEAD_EXPRS: usize = 1024;
+
+/// Keeps the analysis of a play up to date as its text changes.
+///
+/// Nothing is computed until it’s asked for. Each item is lowered, validated
+/// and constant-folded on its own, with the results memoised on the item’s
+/// green node, so after an edit only the items whose text changed are
+/// analysed again.
+pub(crate) struct AnalysisDatabase {
+    text: String,
+    title: bool,
+    parse: Option<Parse>,
+    hir: hir::Database,
+    items: HashMap<GreenNode, Rc<ItemAnalysis>>,
+    #[cfg(test)]
+    analysed: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct ItemAnalysis {
+    pub(crate) item: hir::Item,
+    /// The value of each sentence, for statements with a constant value.
+    pub(crate) values: Vec<Option<i64>>,
+    // Relative to the start of the item, as the item may move around.
+    validation_errors: Vec<ItemError>,
+    exprs: usize,
+}
+
+impl AnalysisDatabase {
+    pub(crate) fn new(text: String, title: bool) -> Self {
+        Self {
+            text,
+            title,
+            parse: None,
+            hir: hir::Database::default(),
+            items: HashMap::new(),
+            #[cfg(test)]
+            analysed: 0,
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn set_text(&mut self, text: String) {
+        self.text = text;
+        self.parse = None;
+    }
+
+    pub(crate) fn apply_edit(&mut self, edit: &Edit) {
+        edit.apply(&mut self.text);
+        self.parse = self
+            .parse
+            .take()
+            .map(|parse| parse.reparse(edit, self.title));
+    }
+
+    pub(crate) fn parse(&mut self) -> &Parse {
+        let (text, title) = (&self.text, self.title);
+        self.parse.get_or_insert_with(|| parse(text, title))
+    }
+
+    pub(crate) fn root(&mut self) -> ast::Root {
+        ast::Root::cast(self.parse().syntax_node()).unwrap()
+    }
+
+    /// Every item in the play along with its analysis.
+    pub(crate) fn items(&mut self) -> Vec<(ast::Item, Rc<ItemAnalysis>)> {
+        let ast_items: Vec<_> = self.root().items().collect();
+
+        let dead_exprs =
+            self.hir.expr_count() - self.items.values().map(|a| a.exprs).sum::<usize>();
+        if dead_exprs > MAX_DEAD_EXPRS {
+            self.hir = hir::Database::default();
+            self.items.clear();
+        }
+
+        let mut items = HashMap::with_capacity(ast_items.len());
+        let analyses = ast_items
+            .into_iter()
+            .map(|ast| {
+                let green_node = ast.syntax().green().into_owned();
+                let analysis = match self.items.get(&green_node) {
+                    Some(analysis) => Rc::clone(analysis),
+                    None => self.analyse_item(&ast),
+                };
+
+                items.insert(green_node, Rc::clone(&analysis));
+                (ast, analysis)
+            })
+            .collect();
+
+        self.items = items;
+
+        analyses
+    }
+
+    pub(crate) fn validation_errors(&mut self) -> Vec<ValidationError> {
+        let items = self.items();
+
+        let hir = &self.hir;
+        let mut errors: Vec<_> = items
+            .iter()
+            .flat_map(|(ast, analysis)| {
+                let start = ast.syntax().text_range().start();
+                analysis.validation_errors.iter().map(move |error| {
+                    let mut error = error.resolve(hir);
+                    error.range += start;
+                    error
+                })
+            })
+            .collect();
+
+        errors.extend(validation::validate_flow(
+            items
+                .iter()
+                .map(|(ast, analysis)| (ast.clone(), &analysis.item)),
+            &self.hir,
+        ));
+        errors.sort_by_key(|error| error.range.start());
+
+        errors
+    }
+
+    /// The range and value of the expression of every statement or
+    /// ‘remember’ with a constant value.
+    pub(crate) fn constant_values(&mut self) -> Vec<(TextRange, i64)> {
+        self.items()
+            .iter()
+            .filter_map(|(ast, analysis)| match ast {
+                ast::Item::Dialog(dialog) => Some(
+                    dialog
+                        .sentences()
+                        .zip(analysis.values.iter())
+                        .filter_map(|(sentence, value)| match sentence {
+                            ast::Sentence::Statement(statement) => {
+                                Some((statement.expr()?.syntax().text_range(), (*value)?))
+                            }
+                            ast::Sentence::Push(push) => {
+                                Some((push.expr()?.syntax().text_range(), (*value)?))
+                            }
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// The speaker of every dialog whose listener is clear, and who that is.
+    pub(crate) fn listeners(&mut self) -> Vec<(TextRange, String)> {
+        let mut on_stage = ArrayVec::new();
+        let mut listeners = Vec::new();
+
+        for (ast, analysis) in self.items() {
+            follow_direction(&mut on_stage, &analysis.item);
+
+            if let (
+                ast::Item::Dialog(dialog),
+                hir::Item::Dialog {
+                    character: Some(speaker),
+                    ..
+                },
+            ) = (&ast, &analysis.item)
+            {
+                if let (Some(token), Some(listener)) =
+                    (dialog.character(), listener(&on_stage, *speaker))
+                {
+                    listeners.push((token.text_range(), self.hir[listener].name.clone()));
+                }
+            }
+        }
+
+        listeners
+    }
+
+    /// What there is to know about the text at an offset: the value of the
+    /// innermost expression around it, or who a second person pronoun is
+    /// talking to.
+    pub(crate) fn hover(&mut self, offset: TextSize) -> Option<Hover> {
+        let token = self
+            .parse()
+            .syntax_node()
+            .token_at_offset(offset)
+            .find(|token| token.kind() != SyntaxKind::Whitespace)?;
+
+        let items = self.items();
+        let (position, (dialog, analysis)) = items
+            .iter()
+            .enumerate()
+            .find(|(_, (ast, _))| ast.syntax().text_range().contains_inclusive(offset))?;
+        let (dialog, speaker, sentences) = match (dialog, &analysis.item) {
+            (
+                ast::Item::Dialog(dialog),
+                hir::Item::Dialog {
+                    character,
+                    sentences,
+                },
+            ) => (dialog, character, sentences),
+            _ => return None,
+        };
+
+        if matches!(
+            token.kind(),
+            SyntaxKind::SecondPerson
+                | SyntaxKind::SecondPersonReflexive
+                | SyntaxKind::SecondPersonPossessive
+        ) {
+            let listener = listener(&on_stage(&items[..position]), (*speaker)?)?;
+            return Some(Hover {
+                range: token.text_range(),
+                kind: HoverKind::Listener(self.hir[listener].name.clone()),
+            });
+        }
+
+        let target = token.parent()?.ancestors().find_map(ast::Expr::cast)?;
+        let range = target.syntax().text_range();
+        let (ast, expr) = dialog
+            .sentences()
+            .zip(sentences)
+            .find_map(|(sentence, hir)| match (sentence, hir) {
+                (ast::Sentence::Statement(statement), hir::Sentence::Statement { expr }) => {
+                    statement
+                        .expr()
+                        .filter(|ast| ast.syntax().text_range().contains_range(range))
+                        .map(|ast| (ast, expr))
+                }
+                _ => None,
+            })?;
+
+        let expr = self.find_expr(ast, expr, target.syntax())?;
+        let value = self.hir.constant_value(expr).ok()?;
+
+        Some(Hover {
+            range,
+            kind: HoverKind::Value(value),
+        })
+    }
+
+    /// Follows an expression’s syntax and its lowered form down together to
+    /// the lowered form of `target`.
+    fn find_expr<'a>(
+        &'a self,
+        ast: ast::Expr,
+        expr: &'a hir::Expr,
+        target: &SyntaxNode,
+    ) -> Option<&'a hir::Expr> {
+        if ast.syntax() == target {
+            return Some(expr);
+        }
+
+        match (ast, expr) {
+            (ast::Expr::Bin(bin), hir::Expr::Bin { lhs, rhs, .. }) => bin
+                .lhs()
+                .and_then(|ast| self.find_expr(ast, &self.hir[*lhs], target))
+                .or_else(|| {
+                    bin.rhs()
+                        .and_then(|ast| self.find_expr(ast, &self.hir[*rhs], target))
+                }),
+            (ast::Expr::Unary(unary), hir::Expr::Unary { operand, .. }) => unary
+                .operand()
+                .and_then(|ast| self.find_expr(ast, &self.hir[*operand], target)),
+            _ => None,
+        }
+    }
+
+    fn analyse_item(&mut self, ast: &ast::Item) -> Rc<ItemAnalysis> {
+        #[cfg(test)]
+        {
+            self.analysed += 1;
+        }
+
+        let exprs_before = self.hir.expr_count();
+        let item = self.hir.lower_item(ast.clone());
+        let exprs = self.hir.expr_count() - exprs_before;
+
+        let start = ast.syntax().text_range().start();
+        let validation_errors = validation::validate_item(ast, &item, &self.hir)
+            .into_iter()
+            .map(|mut error| {
+                error.range -= start;
+                error
+            })
+            .collect();
+
+        let values = match &item {
+            hir::Item::Dialog { sentences, .. } => sentences
+                .iter()
+                .map(|sentence| match sentence {
+                    hir::Sentence::Statement { expr } | hir::Sentence::Push { expr } => {
+                        self.hir.constant_value(expr).ok()
+                    }
+                    hir::Sentence::Vocative { .. }
+                    | hir::Sentence::Question { .. }
+                    | hir::Sentence::Conditional { .. }
+                    | hir::Sentence::Goto { .. }
+                    | hir::Sentence::Pop
+                    | hir::Sentence::IntOutput
+                    | hir::Sentence::CharOutput
+                    | hir::Sentence::IntInput
+                    | hir::Sentence::CharInput => None,
+                })
+                .collect(),
+            hir::Item::CharacterDef { .. }
+            | hir::Item::StageDirection { .. }
+            | hir::Item::Act { .. }
+            | hir::Item::Scene { .. } => Vec::new(),
+        };
+
+        Rc::new(ItemAnalysis {
+            item,
+            values,
+            validation_errors,
+            exprs,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Hover {
+    pub(crate) range: TextRange,
+    pub(crate) kind: HoverKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum HoverKind {
+    Value(i64),
+    Listener(String),
+}
+
+/// Who is listening to `speaker`, if it is clear.
+pub(crate) fn listener(on_stage: &[CharacterIdx], speaker: CharacterIdx) -> Option<CharacterIdx> {
+    match on_stage {
+        [a, b] if *a == speaker => Some(*b),
+        [a, b] if *b == speaker => Some(*a),
+        _ => None,
+    }
+}
+
+/// Who is on stage after the given items.
+fn on_stage(items: &[(ast::Item, Rc<ItemAnalysis>)]) -> ArrayVec<CharacterIdx, 2> {
+    let mut on_stage = ArrayVec::new();
+    for (_, analysis) in items {
+        follow_direction(&mut on_stage, &analysis.item);
+    }
+
+    on_stage
+}
+
+/// Moves characters on or off the stage if the item is a stage direction.
+/// Directions that validation would reject are followed as far as they make
+/// sense.
+pub(crate) fn follow_direction(on_stage: &mut ArrayVec<CharacterIdx, 2>, item: &hir::Item) {
+    if let hir::Item::StageDirection {
+        characters,
+        direction,
+    } = item
+    {
+        match direction {
+            hir::Direction::Enter => {
+                for character in characters {
+                    if !on_stage.contains(character) {
+                        let _ = on_stage.try_push(*character);
+                    }
+                }
+            }
+            hir::Direction::Exit => on_stage.retain(|c| !characters.contains(c)),
+            hir::Direction::Exeunt => on_stage.clear(),
+            hir::Direction::Missing => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::ValidationErrorKind;
+    use text_size::TextSize;
+
+    const PLAY: &str = "Title.
+Romeo, a man. Juliet, a woman.
+[Enter Romeo and Juliet]
+Romeo: You are the sum of a cat and a big cat.
+Juliet: You are the quotient between a cat and nothing.
+";
+
+    fn replace(db: &mut AnalysisDatabase, from: &str, to: &str) {
+        let start = db.text().find(from).unwrap() as u32;
+        db.apply_edit(&Edit {
+            delete: TextRange::at(start.into(), TextSize::of(from)),
+            insert: to.to_string(),
+        });
+    }
+
+    #[test]
+    fn analyse_items_on_demand() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+        assert_eq!(db.analysed, 0);
+
+        db.items();
+        assert_eq!(db.analysed, 5);
+
+        db.items();
+        assert_eq!(db.analysed, 5);
+    }
+
+    #[test]
+    fn only_reanalyse_edited_items() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+        db.items();
+
+        replace(&mut db, "a big cat", "a big big cat");
+        db.items();
+        assert_eq!(db.analysed, 6);
+
+        db.set_text(format!("{}[Exeunt]", db.text()));
+        db.items();
+        assert_eq!(db.analysed, 7);
+    }
+
+    #[test]
+    fn shift_cached_validation_errors() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+        let before = db.validation_errors();
+
+        replace(&mut db, "the sum of a cat and a big cat", "a cat");
+        let after = db.validation_errors();
+
+        assert_eq!(db.analysed, 6);
+        assert_eq!(before.len(), 1);
+        assert_eq!(after.len(), 1);
+        assert_eq!(
+            u32::from(before[0].range.start()) - u32::from(after[0].range.start()),
+            25
+        );
+        assert_eq!(after[0].to_string(), validation_errors_of(db.text())[0]);
+    }
+
+    #[test]
+    fn flow_errors_follow_edits() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+        assert_eq!(db.validation_errors().len(), 1);
+
+        replace(&mut db, "[Enter Romeo and Juliet]", "[Enter Romeo]");
+        let errors: Vec<_> = db
+            .validation_errors()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(errors, validation_errors_of(db.text()));
+    }
+
+    #[test]
+    fn cached_errors_name_characters_as_declared() {
+        let mut db = AnalysisDatabase::new(
+            "Title.\nRomeo, a man.\n[Enter Romeo and romeo]\n".to_string(),
+            true,
+        );
+        assert_eq!(
+            db.validation_errors()[0].kind,
+            ValidationErrorKind::RepeatedCharacter("Romeo".to_string())
+        );
+
+        replace(&mut db, "Romeo, a man.", "ROMEO, a man.");
+        let errors = db.validation_errors();
+
+        assert_eq!(db.analysed, 3);
+        assert_eq!(
+            errors[0].kind,
+            ValidationErrorKind::RepeatedCharacter("ROMEO".to_string())
+        );
+        assert_eq!(errors[0].to_string(), validation_errors_of(db.text())[0]);
+    }
+
+    #[test]
+    fn constant_values() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+        let values: Vec<_> = db
+            .constant_values()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+
+        assert_eq!(values, [3]);
+    }
+
+    #[test]
+    fn listeners() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+        let listeners: Vec<_> = db
+            .listeners()
+            .into_iter()
+            .map(|(range, name)| (&PLAY[range], name))
+            .collect();
+
+        assert_eq!(
+            listeners,
+            [
+                ("Romeo", "Juliet".to_string()),
+                ("Juliet", "Romeo".to_string())
+            ]
+        );
+    }
+
+    fn validation_errors_of(text: &str) -> Vec<String> {
+        let root = ast::Root::cast(parse(text, true).syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+
+        validation::validate(&root, &items, &db)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+}
@@ -0,0 +1,210 @@
+use super::{on_stage, AnalysisDatabase};
+use crate::ast;
+use crate::highlight::{tag_of_kind, Tag};
+use crate::hir::{self, CharacterIdx};
+use crate::lexer::SyntaxKind;
+use crate::parser::parse;
+use text_size::TextSize;
+
+// Nothing lexes as this, so the parser always reports what it expected in
+// its place.
+const SENTINEL: &str = "@";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Completion {
+    pub(crate) label: String,
+    pub(crate) tag: Tag,
+}
+
+impl AnalysisDatabase {
+    /// The words that could go at an offset, found by parsing the text up to
+    /// the word being typed and asking the parser what it expected next.
+    pub(crate) fn completions(&mut self, offset: TextSize) -> Vec<Completion> {
+        let text = &self.text[..usize::from(offset)];
+        let word_start = text
+            .rfind(|c: char| !c.is_alphabetic() && c != '\'')
+            .map_or(0, |idx| idx + 1);
+        let typed = &text[word_start..];
+        let capitalise = typed.starts_with(char::is_uppercase);
+
+        let prefix = format!("{}{}", &text[..word_start], SENTINEL);
+        let parse = parse(&prefix, self.title);
+        let sentinel = TextSize::of(&text[..word_start]);
+        let expected: Vec<_> = match parse
+            .errors
+            .iter()
+            .find(|error| error.range().start() == sentinel)
+        {
+            Some(error) => error.expected().collect(),
+            // The text before the cursor is free text, like a comment.
+            None => return Vec::new(),
+        };
+
+        let mut completions = Vec::new();
+        for kind in expected {
+            let tag = match tag_of_kind(kind) {
+                Some(tag) => tag,
+                None => continue,
+            };
+
+            if kind == SyntaxKind::Character {
+                let root = ast::Root::cast(parse.syntax_node()).unwrap();
+                let entering = root.items().last().is_some_and(|item| {
+                    item.syntax().text_range().contains(sentinel)
+                        && matches!(
+                            &item,
+                            ast::Item::StageDirection(direction)
+                                if matches!(direction.direction(), Some(ast::Direction::Enter))
+                        )
+                });
+
+                completions.extend(
+                    self.characters_before(sentinel, entering)
+                        .into_iter()
+                        .map(|label| Completion { label, tag }),
+                );
+                continue;
+            }
+
+            let words = kind
+                .words()
+                .iter()
+                .filter(|word| word.starts_with(char::is_alphabetic));
+            for word in words {
+                let label = if capitalise {
+                    let mut chars = word.chars();
+                    chars.next().unwrap().to_uppercase().chain(chars).collect()
+                } else {
+                    word.to_string()
+                };
+
+                completions.push(Completion { label, tag });
+            }
+        }
+
+        completions.dedup();
+        completions
+    }
+
+    /// The characters who could be named at an offset: those who are off
+    /// stage when someone enters, and otherwise those on stage.
+    fn characters_before(&mut self, offset: TextSize, entering: bool) -> Vec<String> {
+        let items: Vec<_> = self
+            .items()
+            .into_iter()
+            .filter(|(ast, _)| ast.syntax().text_range().end() <= offset)
+            .collect();
+        let on_stage = on_stage(&items);
+
+        let characters: Vec<CharacterIdx> = if entering {
+            items
+                .iter()
+                .filter_map(|(_, analysis)| match analysis.item {
+                    hir::Item::CharacterDef {
+                        character: Some(character),
+                    } if !on_stage.contains(&character) => Some(character),
+                    _ => None,
+                })
+                .collect()
+        } else {
+            on_stage.to_vec()
+        };
+
+        characters
+            .into_iter()
+            .map(|character| self.hir[character].name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+
+    fn check(input: &str, expected: Expect) {
+        let offset = TextSize::of(input);
+        let mut db = AnalysisDatabase::new(format!("{}\nJuliet: You are nothing.\n", input), true);
+
+        // The vocabulary is long, so only the first few words of each tag are
+        // shown.
+        let mut tags: Vec<(&str, Vec<String>)> = Vec::new();
+        for completion in db.completions(offset) {
+            match tags
+                .iter_mut()
+                .find(|(tag, _)| *tag == completion.tag.name())
+            {
+                Some((_, labels)) => labels.push(completion.label),
+                None => tags.push((completion.tag.name(), vec![completion.label])),
+            }
+        }
+
+        let actual: String = tags
+            .iter()
+            .map(|(tag, labels)| {
+                let first: Vec<_> = labels.iter().take(4).map(String::as_str).collect();
+                format!("{} ({}): {}\n", tag, labels.len(), first.join(", "))
+            })
+            .collect();
+
+        expected.assert_eq(&actual);
+    }
+
+    const PLAY: &str = "Title.
+Romeo, a man. Juliet, a woman. Hamlet, a prince.
+[Enter Romeo and Juliet]
+";
+
+    #[test]
+    fn complete_speakers_on_stage() {
+        check(
+            &format!("{}Rom", PLAY),
+            expect![[r#"
+character (2): Romeo, Juliet
+keyword (2): Scene, Act
+"#]],
+        );
+    }
+
+    #[test]
+    fn complete_characters_entering() {
+        check(
+            &format!("{}[Exit Juliet]\n[Enter ", PLAY),
+            expect![[r#"
+character (2): Juliet, Hamlet
+"#]],
+        );
+    }
+
+    #[test]
+    fn complete_nouns_and_adjectives() {
+        check(
+            &format!("{}Romeo: You are a ", PLAY),
+            expect![[r#"
+operator (7): difference, product, quotient, sum
+negative-adjective (32): bad, cowardly, cursed, damned
+neutral-adjective (20): big, black, blue, bluest
+positive-adjective (36): amazing, beautiful, blossoming, bold
+negative-noun (24): hell, bastard, beggar, blister
+neutral-noun (41): animal, aunt, brother, cat
+positive-noun (13): heaven, king, lord, angel
+"#]],
+        );
+    }
+
+    #[test]
+    fn capitalise_at_start_of_sentence() {
+        check(
+            &format!("{}Romeo: You are a cat. Sp", PLAY),
+            expect![[r#"
+keyword (18): Am, Are, Art, Be
+character (2): Romeo, Juliet
+"#]],
+        );
+    }
+
+    #[test]
+    fn nothing_in_comments() {
+        check("Title", expect![[r#""#]]);
+    }
+}
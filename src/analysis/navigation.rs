@@ -0,0 +1,238 @@
+use super::AnalysisDatabase;
+use crate::lexer::{Lexer, SyntaxKind};
+use crate::syntax::{SyntaxElement, SyntaxToken};
+use std::fmt;
+use text_size::{TextRange, TextSize};
+
+impl AnalysisDatabase {
+    /// The character named at an offset, wherever it is mentioned.
+    pub(crate) fn character_at(&mut self, offset: TextSize) -> Option<TextRange> {
+        self.character_token_at(offset)
+            .map(|token| token.text_range())
+    }
+
+    /// Where the character named at an offset is declared. Only the first
+    /// declaration counts if there are several.
+    pub(crate) fn definition(&mut self, offset: TextSize) -> Option<TextRange> {
+        let name = self.character_token_at(offset)?;
+
+        self.mentions(name.text())
+            .into_iter()
+            .find(is_declaration)
+            .map(|token| token.text_range())
+    }
+
+    /// Every mention of the character named at an offset, in order.
+    pub(crate) fn references(
+        &mut self,
+        offset: TextSize,
+        include_declaration: bool,
+    ) -> Vec<TextRange> {
+        let name = match self.character_token_at(offset) {
+            Some(name) => name,
+            None => return Vec::new(),
+        };
+
+        self.mentions(name.text())
+            .into_iter()
+            .filter(|token| include_declaration || !is_declaration(token))
+            .map(|token| token.text_range())
+            .collect()
+    }
+
+    /// The ranges to replace with `new_name` to rename the character at an
+    /// offset. Names have to be ones the lexer knows, and can’t already
+    /// belong to someone else.
+    pub(crate) fn rename(
+        &mut self,
+        offset: TextSize,
+        new_name: &str,
+    ) -> Result<Vec<TextRange>, RenameError> {
+        let name = self
+            .character_token_at(offset)
+            .ok_or(RenameError::NoCharacter)?;
+
+        let mut tokens = Lexer::new(new_name);
+        match (tokens.next(), tokens.next()) {
+            (Some(token), None) if token.kind == SyntaxKind::Character => {}
+            _ => return Err(RenameError::NotACharacter(new_name.to_string())),
+        }
+
+        let is_same = same_character(name.text(), new_name);
+        if !is_same && !self.mentions(new_name).is_empty() {
+            return Err(RenameError::AlreadyInPlay(new_name.to_string()));
+        }
+
+        Ok(self.references(offset, true))
+    }
+
+    fn character_token_at(&mut self, offset: TextSize) -> Option<SyntaxToken> {
+        self.parse()
+            .syntax_node()
+            .token_at_offset(offset)
+            .find(|token| token.kind() == SyntaxKind::Character)
+    }
+
+    fn mentions(&mut self, name: &str) -> Vec<SyntaxToken> {
+        self.parse()
+            .syntax_node()
+            .descendants_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|token| {
+                token.kind() == SyntaxKind::Character && same_character(token.text(), name)
+            })
+            .collect()
+    }
+}
+
+/// Names are matched ignoring case, like the lexer does.
+fn same_character(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+fn is_declaration(token: &SyntaxToken) -> bool {
+    token
+        .parent()
+        .is_some_and(|parent| parent.kind() == SyntaxKind::CharacterDef)
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum RenameError {
+    NoCharacter,
+    NotACharacter(String),
+    AlreadyInPlay(String),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCharacter => write!(f, "only characters can be renamed"),
+            Self::NotACharacter(name) => write!(f, "{} is not a character’s name", name),
+            Self::AlreadyInPlay(name) => write!(f, "{} is already in the play", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAY: &str = "Title.
+Romeo, a man. Juliet, a woman.
+[Enter Romeo and JULIET]
+Romeo: You are a cat.
+juliet: You are nothing.
+";
+
+    fn offset_of(text: &str) -> TextSize {
+        TextSize::from(PLAY.find(text).unwrap() as u32)
+    }
+
+    fn texts(ranges: Vec<TextRange>) -> Vec<String> {
+        ranges
+            .into_iter()
+            .map(|range| format!("{:?} {}", range, &PLAY[range]))
+            .collect()
+    }
+
+    #[test]
+    fn go_to_definition() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+
+        let definition = db.definition(offset_of("juliet:"));
+        assert_eq!(
+            definition,
+            Some(TextRange::at(offset_of("Juliet,"), 6.into()))
+        );
+        assert_eq!(db.definition(offset_of("cat")), None);
+    }
+
+    #[test]
+    fn find_references() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+
+        assert_eq!(
+            texts(db.references(offset_of("JULIET"), true)),
+            ["21..27 Juliet", "55..61 JULIET", "85..91 juliet"]
+        );
+        assert_eq!(
+            texts(db.references(offset_of("JULIET"), false)),
+            ["55..61 JULIET", "85..91 juliet"]
+        );
+    }
+
+    #[test]
+    fn navigate_from_sentences() {
+        let play = "Title.
+Romeo, a man. Juliet, a woman.
+[Enter Romeo and Juliet]
+Romeo: Juliet, you are the sum of Romeo and a cat.
+";
+        let offset_of = |text: &str, nth: usize| {
+            TextSize::from(play.match_indices(text).nth(nth).unwrap().0 as u32)
+        };
+        let mut db = AnalysisDatabase::new(play.to_string(), true);
+
+        // The vocative and the operand resolve like any other mention.
+        assert_eq!(
+            db.definition(offset_of("Juliet,", 1)),
+            Some(TextRange::at(offset_of("Juliet", 0), 6.into()))
+        );
+        assert_eq!(
+            db.definition(offset_of("Romeo and a", 0)),
+            Some(TextRange::at(offset_of("Romeo", 0), 5.into()))
+        );
+        assert_eq!(
+            db.references(offset_of("Romeo and a", 0), false),
+            [
+                TextRange::at(offset_of("Romeo", 1), 5.into()),
+                TextRange::at(offset_of("Romeo", 2), 5.into()),
+                TextRange::at(offset_of("Romeo", 3), 5.into()),
+            ]
+        );
+        assert_eq!(
+            db.references(offset_of("Juliet,", 1), false),
+            [
+                TextRange::at(offset_of("Juliet", 1), 6.into()),
+                TextRange::at(offset_of("Juliet", 2), 6.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_every_mention() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+
+        assert_eq!(
+            texts(db.rename(offset_of("Romeo:"), "Hamlet").unwrap()),
+            ["7..12 Romeo", "45..50 Romeo", "63..68 Romeo"]
+        );
+        assert_eq!(
+            texts(db.rename(offset_of("Romeo:"), "ROMEO").unwrap()).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn refuse_bad_names() {
+        let mut db = AnalysisDatabase::new(PLAY.to_string(), true);
+        let offset = offset_of("Romeo:");
+
+        assert_eq!(
+            db.rename(offset, "Bob"),
+            Err(RenameError::NotACharacter("Bob".to_string()))
+        );
+        assert_eq!(
+            db.rename(offset, "Hamlet Lear"),
+            Err(RenameError::NotACharacter("Hamlet Lear".to_string()))
+        );
+        assert_eq!(
+            db.rename(offset, "juliet"),
+            Err(RenameError::AlreadyInPlay("juliet".to_string()))
+        );
+        assert_eq!(
+            db.rename(offset_of("cat"), "Hamlet"),
+            Err(RenameError::NoCharacter)
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use super::AnalysisDatabase;
+use crate::ast;
+use crate::lexer::SyntaxKind;
+use crate::syntax::SyntaxNode;
+use text_size::{TextRange, TextSize};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct OutlineItem {
+    pub(crate) name: String,
+    pub(crate) kind: OutlineKind,
+    /// Everything the item covers, such as a scene up to the next heading.
+    pub(crate) range: TextRange,
+    /// The part that names the item, such as a scene’s heading.
+    pub(crate) selection_range: TextRange,
+    pub(crate) children: Vec<OutlineItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum OutlineKind {
+    DramatisPersonae,
+    Character,
+    Act,
+    Scene,
+}
+
+impl AnalysisDatabase {
+    /// The dramatis personae followed by the acts, with their scenes inside
+    /// them. Scenes before the first act are kept at the top.
+    pub(crate) fn outline(&mut self) -> Vec<OutlineItem> {
+        let root = self.parse().syntax_node();
+        let mut personae = Vec::new();
+        let mut outline: Vec<OutlineItem> = Vec::new();
+
+        for node in root.children() {
+            let range = trimmed_range(&node);
+
+            match node.kind() {
+                SyntaxKind::CharacterDef => {
+                    let character = ast::Item::cast(node.clone()).and_then(|item| match item {
+                        ast::Item::CharacterDef(def) => def.character(),
+                        _ => None,
+                    });
+                    if let Some(character) = character {
+                        personae.push(OutlineItem {
+                            name: character.text().to_string(),
+                            kind: OutlineKind::Character,
+                            range,
+                            selection_range: character.text_range(),
+                            children: Vec::new(),
+                        });
+                    }
+                }
+                SyntaxKind::ActHeading => outline.push(section(OutlineKind::Act, &node)),
+                SyntaxKind::SceneHeading => {
+                    let scene = section(OutlineKind::Scene, &node);
+                    match outline.last_mut() {
+                        Some(act) if act.kind == OutlineKind::Act => act.children.push(scene),
+                        _ => outline.push(scene),
+                    }
+                }
+                _ => {}
+            }
+
+            // Whatever follows a heading belongs to it until the next one.
+            if let Some(section) = outline.last_mut() {
+                section.range = section.range.cover(range);
+                if let Some(scene) = section.children.last_mut() {
+                    scene.range = scene.range.cover(range);
+                }
+            }
+        }
+
+        if let (Some(first), Some(last)) = (personae.first(), personae.last()) {
+            let personae_item = OutlineItem {
+                name: "Dramatis personae".to_string(),
+                kind: OutlineKind::DramatisPersonae,
+                range: first.range.cover(last.range),
+                selection_range: first.range,
+                children: personae,
+            };
+            outline.insert(0, personae_item);
+        }
+
+        outline
+    }
+}
+
+fn section(kind: OutlineKind, heading: &SyntaxNode) -> OutlineItem {
+    let range = trimmed_range(heading);
+    let name = heading
+        .to_string()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    OutlineItem {
+        name,
+        kind,
+        range,
+        selection_range: range,
+        children: Vec::new(),
+    }
+}
+
+/// A node’s range without the whitespace that ends up at the end of it.
+fn trimmed_range(node: &SyntaxNode) -> TextRange {
+    let text = node.to_string();
+    TextRange::at(node.text_range().start(), TextSize::of(text.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+    use std::fmt::Write;
+
+    fn check(input: &str, expected: Expect) {
+        fn render(s: &mut String, items: &[OutlineItem], indent: usize) {
+            for item in items {
+                writeln!(
+                    s,
+                    "{:indent$}{:?} {:?} {:?}",
+                    "",
+                    item.kind,
+                    item.name,
+                    item.range,
+                    indent = indent,
+                )
+                .unwrap();
+                render(s, &item.children, indent + 2);
+            }
+        }
+
+        let mut db = AnalysisDatabase::new(input.to_string(), true);
+        let mut actual = String::new();
+        render(&mut actual, &db.outline(), 0);
+
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn outline_play() {
+        check(
+            "Title.
+Romeo, a man.
+Juliet, a woman.
+
+Act I: Meetings.
+
+Scene I: The first.
+[Enter Romeo and Juliet]
+Romeo: You are a cat.
+
+Scene II: The
+second.
+[Exeunt]
+
+Act II: Partings.
+[Enter Romeo]
+",
+            expect![[r#"
+DramatisPersonae "Dramatis personae" 7..37
+  Character "Romeo" 7..20
+  Character "Juliet" 21..37
+Act "Act I: Meetings." 39..155
+  Scene "Scene I: The first." 57..123
+  Scene "Scene II: The second." 125..155
+Act "Act II: Partings." 157..188
+"#]],
+        );
+    }
+
+    #[test]
+    fn scenes_without_acts() {
+        check(
+            "Title.\nScene I: Alone.\n[Enter Romeo]\nScene II: Still alone.\n",
+            expect![[r#"
+Scene "Scene I: Alone." 7..36
+Scene "Scene II: Still alone." 37..59
+"#]],
+        );
+    }
+}
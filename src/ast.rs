@@ -1,7 +1,8 @@
 use crate::lexer::SyntaxKind;
+use crate::roman;
 use crate::syntax::{SyntaxElement, SyntaxNode, SyntaxToken};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Root(SyntaxNode);
 
 impl Root {
@@ -17,11 +18,13 @@ impl Root {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum Item {
     CharacterDef(CharacterDef),
     StageDirection(StageDirection),
     Dialog(Dialog),
+    Act(Heading),
+    Scene(Heading),
 }
 
 impl Item {
@@ -30,23 +33,70 @@ impl Item {
             SyntaxKind::CharacterDef => Self::CharacterDef(CharacterDef(node)),
             SyntaxKind::StageDirection => Self::StageDirection(StageDirection(node)),
             SyntaxKind::Dialog => Self::Dialog(Dialog(node)),
+            SyntaxKind::ActHeading => Self::Act(Heading(node)),
+            SyntaxKind::SceneHeading => Self::Scene(Heading(node)),
             _ => return None,
         };
 
         Some(result)
     }
+
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::CharacterDef(CharacterDef(node))
+            | Self::StageDirection(StageDirection(node))
+            | Self::Dialog(Dialog(node))
+            | Self::Act(Heading(node))
+            | Self::Scene(Heading(node)) => node,
+        }
+    }
 }
 
-#[derive(Debug)]
+/// The start of an act or a scene, like ‘Scene II: The attack.’
+#[derive(Debug, Clone)]
+pub(crate) struct Heading(SyntaxNode);
+
+impl Heading {
+    pub(crate) fn number(&self) -> Option<u32> {
+        self.numeral()
+            .and_then(|numeral| roman::parse(numeral.text()))
+    }
+
+    pub(crate) fn numeral(&self) -> Option<SyntaxToken> {
+        numeral(&self.0)
+    }
+}
+
+/// The number of an act or scene, which follows the word ‘act’ or ‘scene’.
+fn numeral(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .skip_while(|token| !matches!(token.kind(), SyntaxKind::Act | SyntaxKind::Scene))
+        .skip(1)
+        .find(|token| token.kind() != SyntaxKind::Whitespace)
+        .filter(|token| {
+            matches!(
+                token.kind(),
+                SyntaxKind::RomanNumeral | SyntaxKind::FirstPerson
+            )
+        })
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct CharacterDef(SyntaxNode);
 
 impl CharacterDef {
-    pub(crate) fn character(&self) -> Option<String> {
-        self.0.first_token().map(|token| token.text().to_string())
+    pub(crate) fn character(&self) -> Option<SyntaxToken> {
+        character(&self.0)
     }
 }
 
-#[derive(Debug)]
+fn character(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.first_token()
+        .filter(|token| token.kind() == SyntaxKind::Character)
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct StageDirection(SyntaxNode);
 
 impl StageDirection {
@@ -70,28 +120,27 @@ impl StageDirection {
         }
     }
 
-    pub(crate) fn characters(&self) -> impl Iterator<Item = String> {
+    pub(crate) fn characters(&self) -> impl Iterator<Item = SyntaxToken> {
         self.0
             .children_with_tokens()
             .filter_map(|element| element.into_token())
             .filter(|token| token.kind() == SyntaxKind::Character)
-            .map(|token| token.text().to_string())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum Direction {
     Enter,
     Exit,
     Exeunt,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Dialog(SyntaxNode);
 
 impl Dialog {
-    pub(crate) fn character(&self) -> Option<String> {
-        self.0.first_token().map(|token| token.text().to_string())
+    pub(crate) fn character(&self) -> Option<SyntaxToken> {
+        character(&self.0)
     }
 
     pub(crate) fn sentences(&self) -> impl Iterator<Item = Sentence> {
@@ -99,27 +148,69 @@ impl Dialog {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum Sentence {
+    Vocative(Vocative),
     Statement(Statement),
+    Query(Query),
+    Conditional(Conditional),
+    Goto(Goto),
+    Push(Push),
+    Pop(Pop),
     IntOutput(IntOutput),
     CharOutput(CharOutput),
+    IntInput(IntInput),
+    CharInput(CharInput),
 }
 
 impl Sentence {
     pub(crate) fn cast(node: SyntaxNode) -> Option<Self> {
         let result = match node.kind() {
+            SyntaxKind::Vocative => Self::Vocative(Vocative(node)),
             SyntaxKind::Statement => Self::Statement(Statement(node)),
+            SyntaxKind::Query => Self::Query(Query(node)),
+            SyntaxKind::Conditional => Self::Conditional(Conditional(node)),
+            SyntaxKind::Goto => Self::Goto(Goto(node)),
+            SyntaxKind::Push => Self::Push(Push(node)),
+            SyntaxKind::Pop => Self::Pop(Pop(node)),
             SyntaxKind::IntOutput => Self::IntOutput(IntOutput(node)),
             SyntaxKind::CharOutput => Self::CharOutput(CharOutput(node)),
+            SyntaxKind::IntInput => Self::IntInput(IntInput(node)),
+            SyntaxKind::CharInput => Self::CharInput(CharInput(node)),
             _ => return None,
         };
 
         Some(result)
     }
+
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Vocative(Vocative(node))
+            | Self::Statement(Statement(node))
+            | Self::Query(Query(node))
+            | Self::Conditional(Conditional(node))
+            | Self::Goto(Goto(node))
+            | Self::Push(Push(node))
+            | Self::Pop(Pop(node))
+            | Self::IntOutput(IntOutput(node))
+            | Self::CharOutput(CharOutput(node))
+            | Self::IntInput(IntInput(node))
+            | Self::CharInput(CharInput(node)) => node,
+        }
+    }
+}
+
+/// A character being addressed by name, as in ‘Romeo, you are a pig.’
+#[derive(Debug, Clone)]
+pub(crate) struct Vocative(SyntaxNode);
+
+impl Vocative {
+    pub(crate) fn character(&self) -> Option<SyntaxToken> {
+        character(&self.0)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Statement(SyntaxNode);
 
 impl Statement {
@@ -128,11 +219,132 @@ impl Statement {
     }
 }
 
-#[derive(Debug)]
+/// A question comparing two values, as in ‘Art thou better than me?’
+#[derive(Debug, Clone)]
+pub(crate) struct Query(SyntaxNode);
+
+impl Query {
+    pub(crate) fn lhs(&self) -> Option<Expr> {
+        self.operands(false)
+    }
+
+    pub(crate) fn rhs(&self) -> Option<Expr> {
+        self.operands(true)
+    }
+
+    /// Whether the question is asked the other way round, as in ‘Art thou
+    /// not better than me?’
+    pub(crate) fn is_negated(&self) -> bool {
+        self.0
+            .children_with_tokens()
+            .any(|element| element.kind() == SyntaxKind::Not)
+    }
+
+    pub(crate) fn comparison(&self) -> Option<Comparison> {
+        let comparison = self
+            .0
+            .children()
+            .find(|node| node.kind() == SyntaxKind::Comparison)?;
+        let mut tokens = comparison
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .map(|token| token.kind())
+            .filter(|kind| *kind != SyntaxKind::Whitespace);
+
+        match (tokens.next()?, tokens.next()) {
+            (SyntaxKind::As, _) => Some(Comparison::Equal),
+            (SyntaxKind::PositiveComparative, _)
+            | (SyntaxKind::More, Some(SyntaxKind::PositiveAdjective)) => Some(Comparison::Greater),
+            (SyntaxKind::NegativeComparative, _)
+            | (SyntaxKind::More, Some(SyntaxKind::NegativeAdjective)) => Some(Comparison::Less),
+            _ => None,
+        }
+    }
+
+    // Operands are told apart by which side of the comparison they are on, so
+    // that a missing lhs doesn’t turn the rhs into the lhs.
+    fn operands(&self, after_comparison: bool) -> Option<Expr> {
+        let mut seen_comparison = false;
+
+        for node in self.0.children() {
+            if node.kind() == SyntaxKind::Comparison {
+                seen_comparison = true;
+            } else if seen_comparison == after_comparison {
+                if let Some(expr) = Expr::cast(node) {
+                    return Some(expr);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Comparison {
+    Greater,
+    Less,
+    Equal,
+}
+
+/// A sentence that only happens if the last question was answered one way,
+/// as in ‘If so, let us return to scene II.’
+#[derive(Debug, Clone)]
+pub(crate) struct Conditional(SyntaxNode);
+
+impl Conditional {
+    /// The answer the sentence happens on, which is yes for ‘if so’.
+    pub(crate) fn answer(&self) -> Option<bool> {
+        self.0
+            .children_with_tokens()
+            .find_map(|element| match element.kind() {
+                SyntaxKind::So => Some(true),
+                SyntaxKind::Not => Some(false),
+                _ => None,
+            })
+    }
+
+    pub(crate) fn sentence(&self) -> Option<Sentence> {
+        self.0.children().find_map(Sentence::cast)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Goto(SyntaxNode);
+
+impl Goto {
+    pub(crate) fn scene(&self) -> Option<u32> {
+        self.numeral()
+            .and_then(|numeral| roman::parse(numeral.text()))
+    }
+
+    pub(crate) fn numeral(&self) -> Option<SyntaxToken> {
+        numeral(&self.0)
+    }
+}
+
+/// ‘Remember’, which puts a value on the listener’s stack.
+#[derive(Debug, Clone)]
+pub(crate) struct Push(SyntaxNode);
+
+impl Push {
+    pub(crate) fn expr(&self) -> Option<Expr> {
+        self.0.children().find_map(Expr::cast)
+    }
+}
+
+/// ‘Recall’, which takes the listener’s value off their stack.
+#[derive(Debug, Clone)]
+pub(crate) struct Pop(SyntaxNode);
+
+#[derive(Debug, Clone)]
 pub(crate) enum Expr {
     Noun(NounExpr),
     Bin(BinExpr),
+    Unary(UnaryExpr),
     Nothing(NothingExpr),
+    Character(CharacterExpr),
+    Pronoun(PronounExpr),
 }
 
 impl Expr {
@@ -140,15 +352,29 @@ impl Expr {
         let result = match node.kind() {
             SyntaxKind::NounExpr => Self::Noun(NounExpr(node)),
             SyntaxKind::BinExpr => Self::Bin(BinExpr(node)),
+            SyntaxKind::UnaryExpr => Self::Unary(UnaryExpr(node)),
             SyntaxKind::NothingExpr => Self::Nothing(NothingExpr(node)),
+            SyntaxKind::CharacterExpr => Self::Character(CharacterExpr(node)),
+            SyntaxKind::PronounExpr => Self::Pronoun(PronounExpr(node)),
             _ => return None,
         };
 
         Some(result)
     }
+
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Noun(NounExpr(node))
+            | Self::Bin(BinExpr(node))
+            | Self::Unary(UnaryExpr(node))
+            | Self::Nothing(NothingExpr(node))
+            | Self::Character(CharacterExpr(node))
+            | Self::Pronoun(PronounExpr(node)) => node,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct NounExpr(SyntaxNode);
 
 impl NounExpr {
@@ -167,7 +393,7 @@ impl NounExpr {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum Adjective {
     Positive,
     Negative,
@@ -185,7 +411,7 @@ impl Adjective {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct BinExpr(SyntaxNode);
 
 impl BinExpr {
@@ -216,18 +442,90 @@ impl BinExpr {
     }
 
     pub(crate) fn lhs(&self) -> Option<Expr> {
-        self.0.children().find_map(Expr::cast)
+        self.operands(false)
     }
 
     pub(crate) fn rhs(&self) -> Option<Expr> {
-        self.0.children().filter_map(Expr::cast).nth(1) // Second expr should be rhs
+        self.operands(true)
+    }
+
+    // Operands are told apart by which side of ‘and’ they are on, so that a
+    // missing lhs doesn’t turn the rhs into the lhs.
+    fn operands(&self, after_and: bool) -> Option<Expr> {
+        let mut seen_and = false;
+
+        for element in self.0.children_with_tokens() {
+            match element {
+                SyntaxElement::Token(token) if token.kind() == SyntaxKind::And => seen_and = true,
+                SyntaxElement::Node(node) if seen_and == after_and => {
+                    if let Some(expr) = Expr::cast(node) {
+                        return Some(expr);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct UnaryExpr(SyntaxNode);
+
+impl UnaryExpr {
+    pub(crate) fn operation(&self) -> Option<UnaryOperation> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|element| element.into_token())
+            .find_map(|token| match token.kind() {
+                SyntaxKind::Twice => Some(UnaryOperation::Twice),
+                SyntaxKind::Square => Some(UnaryOperation::Square),
+                SyntaxKind::Cube => Some(UnaryOperation::Cube),
+                _ => None,
+            })
+    }
+
+    pub(crate) fn operand(&self) -> Option<Expr> {
+        self.0.children().find_map(Expr::cast)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct NothingExpr(SyntaxNode);
 
-#[derive(Debug)]
+/// The value of a character, named outright.
+#[derive(Debug, Clone)]
+pub(crate) struct CharacterExpr(SyntaxNode);
+
+impl CharacterExpr {
+    pub(crate) fn character(&self) -> Option<SyntaxToken> {
+        character(&self.0)
+    }
+}
+
+/// The value of the speaker, as ‘I’ or ‘myself’, or of the listener, as
+/// ‘you’ or ‘thyself’.
+#[derive(Debug, Clone)]
+pub(crate) struct PronounExpr(SyntaxNode);
+
+impl PronounExpr {
+    pub(crate) fn person(&self) -> Option<Person> {
+        match self.0.first_token()?.kind() {
+            SyntaxKind::FirstPerson | SyntaxKind::FirstPersonReflexive => Some(Person::First),
+            SyntaxKind::SecondPerson | SyntaxKind::SecondPersonReflexive => Some(Person::Second),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Person {
+    First,
+    Second,
+}
+
+#[derive(Debug, Clone)]
 pub(crate) enum Noun {
     Positive,
     Negative,
@@ -245,7 +543,7 @@ impl Noun {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum Operation {
     Difference,
     Quotient,
@@ -254,12 +552,25 @@ pub(crate) enum Operation {
     Remainder,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub(crate) enum UnaryOperation {
+    Twice,
+    Square,
+    Cube,
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct IntOutput(SyntaxNode);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct CharOutput(SyntaxNode);
 
+#[derive(Debug, Clone)]
+pub(crate) struct IntInput(SyntaxNode);
+
+#[derive(Debug, Clone)]
+pub(crate) struct CharInput(SyntaxNode);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -277,6 +588,6 @@ mod tests {
             unreachable!()
         };
 
-        assert_eq!(character_def.character().unwrap(), "Romeo")
+        assert_eq!(character_def.character().unwrap().text(), "Romeo")
     }
 }
@@ -0,0 +1,726 @@
+use std::fmt;
+use std::path::{Component, Path};
+
+pub(crate) const USAGE: &str = "\
+Usage: puck [command] [file...]
+
+Commands:
+  run <file>        run a play (the default when only a file is given)
+  check <file>      parse and validate a play without running it
+  tokens <file>     show the tokens of a play
+  tree <file>       show the syntax tree of a play
+  hir <file>        show the lowered form of a play
+  highlight <file>  show how each part of a play is highlighted
+  fmt <file...>     lay plays out canonically, in place, leaving plays with
+                    syntax errors alone
+  emit <lang> <file>
+                    write a play as a program in another language (c, rust,
+                    js, wat, asm)
+  build --native <file>
+                    compile a play into an x86-64 Linux executable
+  encode <number>   write an expression that evaluates to a number
+  generate --print <text>
+                    write a play that says some text
+  from-bf <file>    compile a Brainfuck program into a play
+  obfuscate <file>  rewrite a play in other words that mean the same
+  lsp               start a language server, talking over stdin and stdout
+
+Starts a REPL when no arguments are given. A file named - is read from
+standard input.
+
+Options:
+  --input <file>                   give the play’s input from a file when running it
+  --input-text <text>              give the play’s input as text when running it
+  --message-format <human|json>    show errors as text or as one JSON object per line
+  --check                          with fmt, only report plays that are not laid out canonically
+  --shortest                       with encode, use as few words as possible
+  --print <text>                   with generate, the text the play says
+  --seed <number>                  with obfuscate, choose the words from this seed rather than at random
+  --minify                         with obfuscate, choose the shortest words and leave comments empty
+  --native                         with build, make a native executable with the system’s as and ld
+  --output <file>                  with build, where to put the executable
+  -h, --help                       show this message
+
+Exit status:
+  0  success
+  1  a file could not be read, or with fmt --check, a play is not laid
+     out canonically
+  2  the command line was not understood
+  3  the play has syntax errors
+  4  the play is invalid, for instance a character speaks while alone
+  5  the play failed while running, for instance on input that is not a
+     number";
+
+pub(crate) struct Args {
+    pub(crate) command: Command,
+    pub(crate) message_format: MessageFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum MessageFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Command {
+    Repl,
+    Help,
+    Run(String, ProgramInput),
+    Check(String),
+    Tokens(String),
+    Tree(String),
+    Hir(String),
+    Highlight(String),
+    Fmt {
+        paths: Vec<String>,
+        check: bool,
+    },
+    Lsp,
+    Emit(Target, String),
+    Build {
+        path: String,
+        output: String,
+    },
+    Encode {
+        number: i64,
+        shortest: bool,
+    },
+    Generate {
+        text: String,
+    },
+    FromBf(String),
+    Obfuscate {
+        path: String,
+        seed: u64,
+        minify: bool,
+    },
+}
+
+/// The languages a play can be turned into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Target {
+    C,
+    Rust,
+    Js,
+    Wat,
+    Asm,
+}
+
+impl Target {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "c" => Some(Self::C),
+            "rust" => Some(Self::Rust),
+            "js" => Some(Self::Js),
+            "wat" => Some(Self::Wat),
+            "asm" => Some(Self::Asm),
+            _ => None,
+        }
+    }
+}
+
+/// What a running play reads from.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ProgramInput {
+    Stdin,
+    File(String),
+    Text(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum UsageError {
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue(String, String),
+    MissingFile(String),
+    MissingOption(String, String),
+    UnexpectedArgument(String),
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsageError::UnknownOption(option) => write!(f, "unknown option {}", option),
+            UsageError::MissingValue(option) => write!(f, "{} needs a value", option),
+            UsageError::InvalidValue(option, value) => {
+                write!(f, "{} is not a valid value for {}", value, option)
+            }
+            UsageError::MissingFile(command) => write!(f, "{} needs a file", command),
+            UsageError::MissingOption(command, option) => write!(f, "{} needs {}", command, option),
+            UsageError::UnexpectedArgument(argument) => {
+                write!(f, "unexpected argument {}", argument)
+            }
+        }
+    }
+}
+
+/// Options that only make sense for some commands.
+#[derive(Default)]
+struct CommandOptions {
+    input: Option<(String, ProgramInput)>,
+    check: bool,
+    native: bool,
+    output: Option<String>,
+    shortest: bool,
+    print: Option<String>,
+    seed: Option<u64>,
+    minify: bool,
+}
+
+/// Parses the arguments that follow the program name.
+pub(crate) fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, UsageError> {
+    let mut positional = Vec::new();
+    let mut options = CommandOptions::default();
+    let mut message_format = MessageFormat::Human;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (option, value) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => (option, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+
+        match option {
+            "-h" | "--help" => {
+                return Ok(Args {
+                    command: Command::Help,
+                    message_format,
+                })
+            }
+            "--message-format" => {
+                message_format = match value.or_else(|| args.next()).as_deref() {
+                    Some("human") => MessageFormat::Human,
+                    Some("json") => MessageFormat::Json,
+                    Some(value) => {
+                        return Err(UsageError::InvalidValue(
+                            option.to_string(),
+                            value.to_string(),
+                        ))
+                    }
+                    None => return Err(UsageError::MissingValue(option.to_string())),
+                };
+            }
+            "--input" | "--input-text" => {
+                let value = match value.or_else(|| args.next()) {
+                    Some(value) => value,
+                    None => return Err(UsageError::MissingValue(option.to_string())),
+                };
+                let source = if option == "--input" {
+                    ProgramInput::File(value)
+                } else {
+                    ProgramInput::Text(value)
+                };
+                options.input = Some((option.to_string(), source));
+            }
+            "--check" => options.check = true,
+            "--native" => options.native = true,
+            "--shortest" => options.shortest = true,
+            "--output" => match value.or_else(|| args.next()) {
+                Some(value) => options.output = Some(value),
+                None => return Err(UsageError::MissingValue(option.to_string())),
+            },
+            "--minify" => options.minify = true,
+            "--seed" => match value.or_else(|| args.next()) {
+                Some(value) => match value.parse() {
+                    Ok(seed) => options.seed = Some(seed),
+                    Err(_) => return Err(UsageError::InvalidValue(option.to_string(), value)),
+                },
+                None => return Err(UsageError::MissingValue(option.to_string())),
+            },
+            "--print" => match value.or_else(|| args.next()) {
+                Some(value) => options.print = Some(value),
+                None => return Err(UsageError::MissingValue(option.to_string())),
+            },
+            // A lone dash is left alone, as it conventionally means stdin,
+            // and so are negative numbers.
+            _ if option.starts_with('-') && option != "-" && option.parse::<i64>().is_err() => {
+                return Err(UsageError::UnknownOption(option.to_string()))
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    Ok(Args {
+        command: command(positional, options)?,
+        message_format,
+    })
+}
+
+fn command(positional: Vec<String>, options: CommandOptions) -> Result<Command, UsageError> {
+    let mut positional = positional.into_iter();
+    let name = match positional.next() {
+        Some(name) => name,
+        None => return Ok(Command::Repl),
+    };
+
+    // Formatting is the only command that works on several files at once.
+    if name == "fmt" {
+        if let Some((option, _)) = options.input {
+            return Err(UsageError::UnexpectedArgument(option));
+        }
+
+        let paths: Vec<_> = positional.collect();
+        if paths.is_empty() {
+            return Err(UsageError::MissingFile(name));
+        }
+
+        return Ok(Command::Fmt {
+            paths,
+            check: options.check,
+        });
+    }
+
+    if options.check {
+        return Err(UsageError::UnexpectedArgument("--check".to_string()));
+    }
+
+    // Building is the only command that writes a file of its own.
+    if name == "build" {
+        if let Some((option, _)) = options.input {
+            return Err(UsageError::UnexpectedArgument(option));
+        }
+        if !options.native {
+            return Err(UsageError::MissingOption(name, "--native".to_string()));
+        }
+
+        let path = positional.next().ok_or(UsageError::MissingFile(name))?;
+        if let Some(extra) = positional.next() {
+            return Err(UsageError::UnexpectedArgument(extra));
+        }
+
+        // The executable is named after the play, like a C compiler’s, but
+        // never so that it would write over the play.
+        let output = options.output.unwrap_or_else(|| {
+            let play = Path::new(&path);
+            if path == "-" {
+                "a.out".to_string()
+            } else if play.extension().is_none() {
+                format!("{}.out", path)
+            } else {
+                play.with_extension("").to_string_lossy().into_owned()
+            }
+        });
+        if same_path(&output, &path) {
+            return Err(UsageError::InvalidValue("--output".to_string(), output));
+        }
+
+        return Ok(Command::Build { path, output });
+    }
+
+    // Encoding works on a number rather than a play.
+    if name == "encode" {
+        if let Some((option, _)) = options.input {
+            return Err(UsageError::UnexpectedArgument(option));
+        }
+
+        let number = positional
+            .next()
+            .ok_or_else(|| UsageError::MissingValue(name.clone()))?;
+        let number = number
+            .parse()
+            .map_err(|_| UsageError::InvalidValue(name.clone(), number))?;
+        if let Some(extra) = positional.next() {
+            return Err(UsageError::UnexpectedArgument(extra));
+        }
+
+        return Ok(Command::Encode {
+            number,
+            shortest: options.shortest,
+        });
+    }
+
+    if options.shortest {
+        return Err(UsageError::UnexpectedArgument("--shortest".to_string()));
+    }
+
+    // Generating writes a play rather than reading one.
+    if name == "generate" {
+        if let Some((option, _)) = options.input {
+            return Err(UsageError::UnexpectedArgument(option));
+        }
+        if let Some(extra) = positional.next() {
+            return Err(UsageError::UnexpectedArgument(extra));
+        }
+
+        return match options.print {
+            Some(text) => Ok(Command::Generate { text }),
+            None => Err(UsageError::MissingOption(name, "--print".to_string())),
+        };
+    }
+
+    if options.print.is_some() {
+        return Err(UsageError::UnexpectedArgument("--print".to_string()));
+    }
+
+    // Obfuscating without a seed gives a different play every time.
+    if name == "obfuscate" {
+        if let Some((option, _)) = options.input {
+            return Err(UsageError::UnexpectedArgument(option));
+        }
+
+        let path = positional.next().ok_or(UsageError::MissingFile(name))?;
+        if let Some(extra) = positional.next() {
+            return Err(UsageError::UnexpectedArgument(extra));
+        }
+
+        return Ok(Command::Obfuscate {
+            path,
+            seed: options.seed.unwrap_or_else(|| fastrand::u64(..)),
+            minify: options.minify,
+        });
+    }
+
+    if options.seed.is_some() {
+        return Err(UsageError::UnexpectedArgument("--seed".to_string()));
+    }
+    if options.minify {
+        return Err(UsageError::UnexpectedArgument("--minify".to_string()));
+    }
+    if options.native {
+        return Err(UsageError::UnexpectedArgument("--native".to_string()));
+    }
+    if options.output.is_some() {
+        return Err(UsageError::UnexpectedArgument("--output".to_string()));
+    }
+
+    // The editor sends the plays over the connection instead.
+    if name == "lsp" {
+        if let Some(argument) = positional.next() {
+            return Err(UsageError::UnexpectedArgument(argument));
+        }
+        if let Some((option, _)) = options.input {
+            return Err(UsageError::UnexpectedArgument(option));
+        }
+
+        return Ok(Command::Lsp);
+    }
+
+    // The language comes before the file.
+    let target = if name == "emit" {
+        match positional.next() {
+            Some(target) => match Target::from_name(&target) {
+                Some(target) => Some(target),
+                None => return Err(UsageError::InvalidValue(name, target)),
+            },
+            None => return Err(UsageError::MissingValue(name)),
+        }
+    } else {
+        None
+    };
+
+    let file = positional.next();
+    if let Some(extra) = positional.next() {
+        return Err(UsageError::UnexpectedArgument(extra));
+    }
+
+    let (name, file) = match name.as_str() {
+        "run" | "check" | "tokens" | "tree" | "hir" | "highlight" | "emit" | "from-bf" => {
+            match file {
+                Some(file) => (name, file),
+                None => return Err(UsageError::MissingFile(name)),
+            }
+        }
+        // `puck <file>` predates the subcommands.
+        _ => match file {
+            Some(file) => return Err(UsageError::UnexpectedArgument(file)),
+            None => ("run".to_string(), name),
+        },
+    };
+
+    let command = match name.as_str() {
+        "run" => {
+            let input = options
+                .input
+                .map_or(ProgramInput::Stdin, |(_, input)| input);
+            return Ok(Command::Run(file, input));
+        }
+        "check" => Command::Check(file),
+        "tokens" => Command::Tokens(file),
+        "tree" => Command::Tree(file),
+        "hir" => Command::Hir(file),
+        "emit" => Command::Emit(target.unwrap(), file),
+        "from-bf" => Command::FromBf(file),
+        _ => Command::Highlight(file),
+    };
+
+    match options.input {
+        // Only running a play needs its input.
+        Some((option, _)) => Err(UsageError::UnexpectedArgument(option)),
+        None => Ok(command),
+    }
+}
+
+/// Whether two paths name the same file, as far as can be told without
+/// looking at the file system.
+fn same_path(a: &str, b: &str) -> bool {
+    let components = |path| {
+        Path::new(path)
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect::<Vec<_>>()
+    };
+    components(a) == components(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(args: &[&str], expected: Result<Command, UsageError>) {
+        let args = parse_args(args.iter().map(ToString::to_string));
+        assert_eq!(args.map(|args| args.command), expected);
+    }
+
+    #[test]
+    fn no_arguments_start_repl() {
+        check(&[], Ok(Command::Repl));
+    }
+
+    #[test]
+    fn bare_file_runs() {
+        check(
+            &["hamlet.spl"],
+            Ok(Command::Run("hamlet.spl".to_string(), ProgramInput::Stdin)),
+        );
+    }
+
+    #[test]
+    fn subcommands() {
+        check(&["check", "a.spl"], Ok(Command::Check("a.spl".to_string())));
+        check(&["tree", "a.spl"], Ok(Command::Tree("a.spl".to_string())));
+        check(
+            &["highlight", "a.spl"],
+            Ok(Command::Highlight("a.spl".to_string())),
+        );
+        check(&["lsp"], Ok(Command::Lsp));
+        check(
+            &["lsp", "a.spl"],
+            Err(UsageError::UnexpectedArgument("a.spl".to_string())),
+        );
+    }
+
+    #[test]
+    fn emit() {
+        check(
+            &["emit", "c", "a.spl"],
+            Ok(Command::Emit(Target::C, "a.spl".to_string())),
+        );
+        check(
+            &["emit", "rust", "a.spl"],
+            Ok(Command::Emit(Target::Rust, "a.spl".to_string())),
+        );
+        check(
+            &["emit", "cobol", "a.spl"],
+            Err(UsageError::InvalidValue(
+                "emit".to_string(),
+                "cobol".to_string(),
+            )),
+        );
+        check(&["emit"], Err(UsageError::MissingValue("emit".to_string())));
+        check(
+            &["emit", "c"],
+            Err(UsageError::MissingFile("emit".to_string())),
+        );
+    }
+
+    #[test]
+    fn build() {
+        check(
+            &["build", "--native", "plays/hamlet.spl"],
+            Ok(Command::Build {
+                path: "plays/hamlet.spl".to_string(),
+                output: "plays/hamlet".to_string(),
+            }),
+        );
+        check(
+            &["build", "--native", "-", "--output=hamlet"],
+            Ok(Command::Build {
+                path: "-".to_string(),
+                output: "hamlet".to_string(),
+            }),
+        );
+        check(
+            &["build", "--native", "plays/hamlet"],
+            Ok(Command::Build {
+                path: "plays/hamlet".to_string(),
+                output: "plays/hamlet.out".to_string(),
+            }),
+        );
+        check(
+            &[
+                "build",
+                "--native",
+                "hamlet.spl",
+                "--output",
+                "./hamlet.spl",
+            ],
+            Err(UsageError::InvalidValue(
+                "--output".to_string(),
+                "./hamlet.spl".to_string(),
+            )),
+        );
+        check(
+            &["build", "hamlet.spl"],
+            Err(UsageError::MissingOption(
+                "build".to_string(),
+                "--native".to_string(),
+            )),
+        );
+        check(
+            &["run", "--native", "hamlet.spl"],
+            Err(UsageError::UnexpectedArgument("--native".to_string())),
+        );
+    }
+
+    #[test]
+    fn encode() {
+        check(
+            &["encode", "-72", "--shortest"],
+            Ok(Command::Encode {
+                number: -72,
+                shortest: true,
+            }),
+        );
+        check(
+            &["encode", "twelve"],
+            Err(UsageError::InvalidValue(
+                "encode".to_string(),
+                "twelve".to_string(),
+            )),
+        );
+        check(
+            &["run", "--shortest", "a.spl"],
+            Err(UsageError::UnexpectedArgument("--shortest".to_string())),
+        );
+    }
+
+    #[test]
+    fn generate() {
+        check(
+            &["generate", "--print", "-- Hello!"],
+            Ok(Command::Generate {
+                text: "-- Hello!".to_string(),
+            }),
+        );
+        check(
+            &["generate"],
+            Err(UsageError::MissingOption(
+                "generate".to_string(),
+                "--print".to_string(),
+            )),
+        );
+        check(
+            &["check", "--print=Hi", "a.spl"],
+            Err(UsageError::UnexpectedArgument("--print".to_string())),
+        );
+    }
+
+    #[test]
+    fn from_bf() {
+        check(
+            &["from-bf", "hello.b"],
+            Ok(Command::FromBf("hello.b".to_string())),
+        );
+        check(
+            &["from-bf"],
+            Err(UsageError::MissingFile("from-bf".to_string())),
+        );
+    }
+
+    #[test]
+    fn obfuscate() {
+        check(
+            &["obfuscate", "--seed", "7", "--minify", "a.spl"],
+            Ok(Command::Obfuscate {
+                path: "a.spl".to_string(),
+                seed: 7,
+                minify: true,
+            }),
+        );
+        check(
+            &["obfuscate", "--seed=x", "a.spl"],
+            Err(UsageError::InvalidValue(
+                "--seed".to_string(),
+                "x".to_string(),
+            )),
+        );
+        check(
+            &["run", "--minify", "a.spl"],
+            Err(UsageError::UnexpectedArgument("--minify".to_string())),
+        );
+    }
+
+    #[test]
+    fn program_input() {
+        check(
+            &["run", "-", "--input", "data.txt"],
+            Ok(Command::Run(
+                "-".to_string(),
+                ProgramInput::File("data.txt".to_string()),
+            )),
+        );
+        check(
+            &["run", "--input-text=42", "a.spl"],
+            Ok(Command::Run(
+                "a.spl".to_string(),
+                ProgramInput::Text("42".to_string()),
+            )),
+        );
+    }
+
+    #[test]
+    fn message_format() {
+        let args = parse_args(["check", "--message-format=json", "a.spl"].map(String::from));
+        assert_eq!(args.unwrap().message_format, MessageFormat::Json);
+
+        check(
+            &["check", "--message-format", "xml", "a.spl"],
+            Err(UsageError::InvalidValue(
+                "--message-format".to_string(),
+                "xml".to_string(),
+            )),
+        );
+    }
+
+    #[test]
+    fn fmt() {
+        check(
+            &["fmt", "--check", "a.spl", "b.spl"],
+            Ok(Command::Fmt {
+                paths: vec!["a.spl".to_string(), "b.spl".to_string()],
+                check: true,
+            }),
+        );
+        check(
+            &["run", "--check", "a.spl"],
+            Err(UsageError::UnexpectedArgument("--check".to_string())),
+        );
+    }
+
+    #[test]
+    fn help_anywhere() {
+        check(&["run", "--help"], Ok(Command::Help));
+    }
+
+    #[test]
+    fn usage_errors() {
+        check(&["hir"], Err(UsageError::MissingFile("hir".to_string())));
+        check(
+            &["run", "a.spl", "b.spl"],
+            Err(UsageError::UnexpectedArgument("b.spl".to_string())),
+        );
+        check(
+            &["run", "--fast", "a.spl"],
+            Err(UsageError::UnknownOption("--fast".to_string())),
+        );
+        check(
+            &["run", "a.spl", "--input"],
+            Err(UsageError::MissingValue("--input".to_string())),
+        );
+        check(
+            &["check", "a.spl", "--input-text", "42"],
+            Err(UsageError::UnexpectedArgument("--input-text".to_string())),
+        );
+    }
+}
@@ -0,0 +1,800 @@
+mod asm;
+mod c;
+mod js;
+mod rust;
+mod wat;
+
+use crate::analysis::{follow_direction, listener};
+use crate::cli::Target;
+use crate::{hir, roman};
+use arrayvec::ArrayVec;
+pub(crate) use asm::build;
+use la_arena::ArenaMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Turns a valid play into a program for another language.
+pub(crate) fn emit(target: Target, items: &[hir::Item], db: &hir::Database) -> String {
+    let program = Program::new(items, db);
+
+    match target {
+        Target::C => c::emit(&program),
+        Target::Rust => rust::emit(&program),
+        Target::Js => js::emit(&program),
+        Target::Wat => wat::emit(&program),
+        Target::Asm => asm::emit(&program),
+    }
+}
+
+/// A play with the stage worked out ahead of time, so that every sentence
+/// knows which character it changes or reads. Stage directions and speakers
+/// are kept as comments, to make the output easier to follow. Validation
+/// has made sure that a goto finds the stage as it is at the start of the
+/// scene it goes to, so working out the stage from top to bottom holds
+/// whichever way the program goes.
+pub(crate) struct Program {
+    variables: ArenaMap<hir::CharacterIdx, String>,
+    names: ArenaMap<hir::CharacterIdx, String>,
+    /// The characters in the order they were declared.
+    characters: Vec<hir::CharacterIdx>,
+    pub(crate) steps: Vec<Step>,
+    /// The scenes that gotos lead to.
+    targets: HashSet<usize>,
+}
+
+pub(crate) enum Step {
+    Comment(String),
+    Assign {
+        character: hir::CharacterIdx,
+        expr: Expr,
+    },
+    IntOutput(hir::CharacterIdx),
+    CharOutput(hir::CharacterIdx),
+    IntInput(hir::CharacterIdx),
+    CharInput(hir::CharacterIdx),
+    /// The start of a scene. Scenes are numbered from one in the order they
+    /// come in the play, as their own numbers start again in every act,
+    /// which leaves nought for the start of the play.
+    Scene(usize),
+    Goto(usize),
+    /// Remembers whether `lhs` compares to `rhs` as asked.
+    Question {
+        lhs: Expr,
+        comparison: Comparison,
+        rhs: Expr,
+    },
+    /// A step that only happens if the last question was answered `answer`,
+    /// failing if nothing has been asked.
+    Conditional {
+        answer: bool,
+        step: Box<Step>,
+    },
+    Push {
+        character: hir::CharacterIdx,
+        expr: Expr,
+    },
+    /// Takes the top of a character’s stack as their value, failing if the
+    /// stack is empty.
+    Pop(hir::CharacterIdx),
+}
+
+impl Step {
+    /// The step itself, or the step it guards if it’s conditional.
+    fn inner(&self) -> &Step {
+        match self {
+            Step::Conditional { step, .. } => step.inner(),
+            _ => self,
+        }
+    }
+
+    fn exprs(&self) -> Vec<&Expr> {
+        match self.inner() {
+            Step::Assign { expr, .. } | Step::Push { expr, .. } => vec![expr],
+            Step::Question { lhs, rhs, .. } => vec![lhs, rhs],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// How a question compares two values, with ‘not’ already taken into
+/// account.
+#[derive(Clone, Copy)]
+pub(crate) enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn new(comparison: hir::Comparison, negated: bool) -> Self {
+        match (comparison, negated) {
+            (hir::Comparison::Greater, false) => Comparison::Greater,
+            (hir::Comparison::Greater, true) => Comparison::LessOrEqual,
+            (hir::Comparison::Less, false) => Comparison::Less,
+            (hir::Comparison::Less, true) => Comparison::GreaterOrEqual,
+            (hir::Comparison::Equal, false) => Comparison::Equal,
+            (hir::Comparison::Equal, true) => Comparison::NotEqual,
+            (hir::Comparison::Missing, _) => unreachable!(),
+        }
+    }
+
+    /// The operator C, Rust and JavaScript write the comparison with.
+    pub(crate) fn operator(self) -> &'static str {
+        match self {
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+        }
+    }
+}
+
+/// Noun phrases are folded into numbers, while arithmetic is kept as it was
+/// written. ‘I’ and ‘you’ become the characters they stand for.
+pub(crate) enum Expr {
+    Number(i64),
+    Variable(hir::CharacterIdx),
+    Bin {
+        operation: hir::Operation,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+impl Expr {
+    /// Whether working the expression out reads a character’s value, in
+    /// which case its arithmetic has to be checked as the program runs.
+    /// Arithmetic on numbers alone has already been checked by validation.
+    pub(crate) fn is_checked(&self) -> bool {
+        match self {
+            Expr::Number(_) => false,
+            Expr::Variable(_) => true,
+            Expr::Bin { lhs, rhs, .. } => lhs.is_checked() || rhs.is_checked(),
+        }
+    }
+
+    fn any(&self, matches: &impl Fn(&Expr) -> bool) -> bool {
+        matches(self)
+            || match self {
+                Expr::Number(_) | Expr::Variable(_) => false,
+                Expr::Bin { lhs, rhs, .. } => lhs.any(matches) || rhs.any(matches),
+            }
+    }
+}
+
+impl Program {
+    /// Plays have to have been validated, so that the stage always makes
+    /// sense and no value overflows.
+    pub(crate) fn new(items: &[hir::Item], db: &hir::Database) -> Self {
+        let mut program = Self {
+            variables: ArenaMap::default(),
+            names: ArenaMap::default(),
+            characters: Vec::new(),
+            steps: Vec::new(),
+            targets: HashSet::new(),
+        };
+        let mut on_stage = ArrayVec::new();
+        let scenes = Scenes::new(items);
+        let mut scene = 1;
+        let mut act = 0;
+
+        for item in items {
+            follow_direction(&mut on_stage, item);
+
+            match item {
+                hir::Item::CharacterDef { character } => {
+                    let character = character.unwrap();
+                    program
+                        .variables
+                        .insert(character, variable(&db[character].name));
+                    program.names.insert(character, db[character].name.clone());
+                    program.characters.push(character);
+                }
+                hir::Item::StageDirection {
+                    characters,
+                    direction,
+                } => {
+                    let names: Vec<_> = characters
+                        .iter()
+                        .map(|character| db[*character].name.as_str())
+                        .collect();
+                    let comment = match direction {
+                        hir::Direction::Enter => format!("Enter {}", names.join(" and ")),
+                        hir::Direction::Exit => format!("Exit {}", names.join(" and ")),
+                        hir::Direction::Exeunt => "Exeunt".to_string(),
+                        hir::Direction::Missing => unreachable!(),
+                    };
+                    program.steps.push(Step::Comment(comment));
+                }
+                hir::Item::Act { number } => {
+                    act += 1;
+                    program.steps.push(Step::Comment(format!(
+                        "Act {}",
+                        roman::format(number.unwrap())
+                    )));
+                }
+                hir::Item::Scene { number } => {
+                    program.steps.push(Step::Comment(format!(
+                        "Scene {}",
+                        roman::format(number.unwrap())
+                    )));
+                    program.steps.push(Step::Scene(scene));
+                    scene += 1;
+                }
+                hir::Item::Dialog {
+                    character,
+                    sentences,
+                } => {
+                    let speaker = character.unwrap();
+                    let listener = listener(&on_stage, speaker).unwrap();
+                    program.steps.push(Step::Comment(format!(
+                        "{}, to {}",
+                        db[speaker].name, db[listener].name
+                    )));
+
+                    let conversation = Conversation {
+                        speaker,
+                        listener,
+                        act,
+                        scenes: &scenes,
+                    };
+                    program.steps.extend(
+                        sentences
+                            .iter()
+                            .filter_map(|sentence| lower_sentence(sentence, &conversation, db)),
+                    );
+                }
+            }
+        }
+
+        program.targets = program
+            .steps
+            .iter()
+            .filter_map(|step| match step.inner() {
+                Step::Goto(scene) => Some(*scene),
+                _ => None,
+            })
+            .collect();
+
+        program
+    }
+
+    /// The name of the variable holding a character’s value.
+    pub(crate) fn variable(&self, character: hir::CharacterIdx) -> &str {
+        &self.variables[character]
+    }
+
+    /// A character’s name as it was declared, for messages.
+    pub(crate) fn name(&self, character: hir::CharacterIdx) -> &str {
+        &self.names[character]
+    }
+
+    /// Every character’s variable, in the order they were declared.
+    pub(crate) fn variables(&self) -> impl Iterator<Item = &str> {
+        self.characters
+            .iter()
+            .map(move |character| self.variable(*character))
+    }
+
+    /// Whether any step matches, including those guarded by conditionals.
+    pub(crate) fn uses(&self, matches: impl Fn(&Step) -> bool) -> bool {
+        self.steps
+            .iter()
+            .any(|step| matches(step) || matches(step.inner()))
+    }
+
+    /// Whether any arithmetic on characters’ values does `operation`, so
+    /// that the function checking it has to be included.
+    pub(crate) fn checks(&self, operation: hir::Operation) -> bool {
+        self.steps.iter().flat_map(Step::exprs).any(|expr| {
+            expr.any(&|expr| match expr {
+                Expr::Bin { operation: op, .. } => *op == operation && expr.is_checked(),
+                Expr::Number(_) | Expr::Variable(_) => false,
+            })
+        })
+    }
+
+    /// Every character with a stack, in the order they were declared.
+    pub(crate) fn stacks(&self) -> impl Iterator<Item = hir::CharacterIdx> + '_ {
+        self.characters.iter().copied().filter(move |character| {
+            self.uses(|step| match step {
+                Step::Push { character: c, .. } | Step::Pop(c) => c == character,
+                _ => false,
+            })
+        })
+    }
+
+    /// Whether the answer to the last question has to be kept, which
+    /// conditionals read even if nothing can have been asked.
+    pub(crate) fn asks(&self) -> bool {
+        self.uses(|step| matches!(step, Step::Question { .. } | Step::Conditional { .. }))
+    }
+
+    /// Whether some goto leads to a scene, so that it needs a label.
+    pub(crate) fn is_target(&self, scene: usize) -> bool {
+        self.targets.contains(&scene)
+    }
+
+    /// The steps split up where gotos lead, for languages without goto,
+    /// each with the scene it starts at or nought for the start of the play.
+    pub(crate) fn blocks(&self) -> Vec<(usize, &[Step])> {
+        let mut blocks = Vec::new();
+        let (mut scene, mut start) = (0, 0);
+
+        for (idx, step) in self.steps.iter().enumerate() {
+            match step {
+                // The comment naming the scene comes just before it.
+                Step::Scene(target) if self.is_target(*target) => {
+                    blocks.push((scene, &self.steps[start..idx - 1]));
+                    scene = *target;
+                    start = idx - 1;
+                }
+                _ => {}
+            }
+        }
+        blocks.push((scene, &self.steps[start..]));
+
+        blocks
+    }
+
+    /// Whether any arithmetic is checked as the program runs.
+    pub(crate) fn checks_any(&self) -> bool {
+        OPERATIONS.iter().any(|operation| self.checks(*operation))
+    }
+}
+
+/// Every operation, in the order their checking functions are written out.
+pub(crate) const OPERATIONS: [hir::Operation; 5] = [
+    hir::Operation::Sum,
+    hir::Operation::Difference,
+    hir::Operation::Product,
+    hir::Operation::Quotient,
+    hir::Operation::Remainder,
+];
+
+/// The name of the function that does an operation, failing if the result
+/// doesn’t fit in a value or there is nothing to divide by.
+pub(crate) fn checked_function(operation: hir::Operation) -> &'static str {
+    match operation {
+        hir::Operation::Sum => "checked_add",
+        hir::Operation::Difference => "checked_subtract",
+        hir::Operation::Product => "checked_multiply",
+        hir::Operation::Quotient => "checked_divide",
+        hir::Operation::Remainder => "checked_remainder",
+    }
+}
+
+/// Where the scenes of each act are among the scenes of the play.
+struct Scenes(HashMap<(usize, u32), usize>);
+
+impl Scenes {
+    fn new(items: &[hir::Item]) -> Self {
+        let mut scenes = HashMap::new();
+        let mut act = 0;
+        let mut scene = 1;
+
+        for item in items {
+            match item {
+                hir::Item::Act { .. } => act += 1,
+                hir::Item::Scene { number } => {
+                    scenes.entry((act, number.unwrap())).or_insert(scene);
+                    scene += 1;
+                }
+                _ => {}
+            }
+        }
+
+        Self(scenes)
+    }
+}
+
+/// Who ‘I’ and ‘you’ are, and where a goto can go.
+struct Conversation<'a> {
+    speaker: hir::CharacterIdx,
+    listener: hir::CharacterIdx,
+    act: usize,
+    scenes: &'a Scenes,
+}
+
+fn lower_sentence(
+    sentence: &hir::Sentence,
+    conversation: &Conversation<'_>,
+    db: &hir::Database,
+) -> Option<Step> {
+    let Conversation {
+        speaker, listener, ..
+    } = *conversation;
+    let lower = |expr| lower_expr(expr, speaker, listener, db);
+
+    let step = match sentence {
+        // Validation has made sure that the listener is the one addressed.
+        hir::Sentence::Vocative { .. } => return None,
+        hir::Sentence::Statement { expr } => Step::Assign {
+            character: listener,
+            expr: lower(expr),
+        },
+        hir::Sentence::Question {
+            lhs,
+            comparison,
+            negated,
+            rhs,
+        } => Step::Question {
+            lhs: lower(lhs),
+            comparison: Comparison::new(*comparison, *negated),
+            rhs: lower(rhs),
+        },
+        hir::Sentence::Conditional { answer, sentence } => Step::Conditional {
+            answer: answer.unwrap(),
+            step: Box::new(lower_sentence(
+                sentence.as_ref().unwrap(),
+                conversation,
+                db,
+            )?),
+        },
+        hir::Sentence::Goto { scene } => {
+            Step::Goto(conversation.scenes.0[&(conversation.act, scene.unwrap())])
+        }
+        hir::Sentence::Push { expr } => Step::Push {
+            character: listener,
+            expr: lower(expr),
+        },
+        hir::Sentence::Pop => Step::Pop(listener),
+        hir::Sentence::IntOutput => Step::IntOutput(listener),
+        hir::Sentence::CharOutput => Step::CharOutput(listener),
+        hir::Sentence::IntInput => Step::IntInput(listener),
+        hir::Sentence::CharInput => Step::CharInput(listener),
+    };
+
+    Some(step)
+}
+
+/// Character names are made of letters and single spaces, and none of them
+/// are keywords in the languages we target.
+fn variable(name: &str) -> String {
+    name.to_lowercase().replace(' ', "_")
+}
+
+fn lower_expr(
+    expr: &hir::Expr,
+    speaker: hir::CharacterIdx,
+    listener: hir::CharacterIdx,
+    db: &hir::Database,
+) -> Expr {
+    let lower = |expr| Box::new(lower_expr(expr, speaker, listener, db));
+
+    match expr {
+        hir::Expr::Bin {
+            operation,
+            lhs,
+            rhs,
+        } => Expr::Bin {
+            operation: *operation,
+            lhs: lower(&db[*lhs]),
+            rhs: lower(&db[*rhs]),
+        },
+        hir::Expr::Character(character) => Expr::Variable(*character),
+        hir::Expr::Speaker => Expr::Variable(speaker),
+        hir::Expr::Listener => Expr::Variable(listener),
+        // Squares and cubes of values that are only known as the program
+        // runs are products, so that targets can check them like any other.
+        hir::Expr::Unary { operation, operand }
+            if db.constant_value(expr) == Err(hir::ConstError::NotConstant) =>
+        {
+            let product = |lhs, rhs| Expr::Bin {
+                operation: hir::Operation::Product,
+                lhs,
+                rhs,
+            };
+            let operand = || lower(&db[*operand]);
+
+            match operation {
+                hir::UnaryOperation::Twice => product(Box::new(Expr::Number(2)), operand()),
+                hir::UnaryOperation::Square => product(operand(), operand()),
+                hir::UnaryOperation::Cube => {
+                    product(Box::new(product(operand(), operand())), operand())
+                }
+            }
+        }
+        // Squares and cubes are worked out here, as validation has already
+        // made sure they fit, so no target needs operators of its own for them.
+        _ => Expr::Number(db.constant_value(expr).unwrap()),
+    }
+}
+
+/// How C, Rust and JavaScript, which share the usual operators, differ in
+/// writing expressions.
+pub(crate) struct Infix {
+    /// Follows every number, like the `n` of a JavaScript `BigInt`.
+    pub(crate) suffix: &'static str,
+    /// Follows every call to a function that checks arithmetic, like Rust’s
+    /// `?`.
+    pub(crate) call_suffix: &'static str,
+    /// Turns the name of a variable or function into the target’s style.
+    pub(crate) name: fn(&str) -> String,
+}
+
+/// Writes an expression with the usual operators. Only nested operations need
+/// brackets, while arithmetic on characters’ values is written as calls to
+/// the functions named by `checked_function`.
+pub(crate) fn infix(s: &mut String, expr: &Expr, program: &Program, syntax: &Infix) {
+    match expr {
+        Expr::Number(number) => write!(s, "{}{}", number, syntax.suffix).unwrap(),
+        Expr::Variable(character) => s.push_str(&(syntax.name)(program.variable(*character))),
+        Expr::Bin {
+            operation,
+            lhs,
+            rhs,
+        } if expr.is_checked() => {
+            write!(s, "{}(", (syntax.name)(checked_function(*operation))).unwrap();
+            infix(s, lhs, program, syntax);
+            s.push_str(", ");
+            infix(s, rhs, program, syntax);
+            write!(s, "){}", syntax.call_suffix).unwrap();
+        }
+        Expr::Bin {
+            operation,
+            lhs,
+            rhs,
+        } => {
+            operand(s, lhs, program, syntax);
+            let operator = match operation {
+                hir::Operation::Remainder => "%",
+                hir::Operation::Difference => "-",
+                hir::Operation::Quotient => "/",
+                hir::Operation::Product => "*",
+                hir::Operation::Sum => "+",
+            };
+            write!(s, " {} ", operator).unwrap();
+            operand(s, rhs, program, syntax);
+        }
+    }
+}
+
+fn operand(s: &mut String, expr: &Expr, program: &Program, syntax: &Infix) {
+    match expr {
+        Expr::Bin { .. } if !expr.is_checked() => {
+            s.push('(');
+            infix(s, expr, program, syntax);
+            s.push(')');
+        }
+        _ => infix(s, expr, program, syntax),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::ast;
+    use crate::eval::Evaluator;
+    use crate::parser::parse;
+    use crate::validation::validate;
+    use std::cell::RefCell;
+    use std::io::{self, Write as _};
+    use std::path::Path;
+    use std::process::{self, Command, Stdio};
+    use std::rc::Rc;
+    use std::{env, fs};
+
+    /// Plays that every backend has to run the same way as the evaluator,
+    /// along with the inputs to run them on.
+    pub(crate) const CONFORMANCE: &[(&str, &str, &[&str])] = &[
+        (
+            "arithmetic",
+            "Arithmetic.
+Romeo, a man. Juliet, a woman.
+[Enter Romeo and Juliet]
+Romeo: You are the sum of a big big cat and a pig. Open your heart.
+You are the difference between nothing and the product of a big cat and a big big big cat.
+Open your heart.
+You are the quotient between the difference between nothing and a big big big cat and a big cat.
+Open your heart.
+You are the remainder of the quotient between the difference between nothing and a big big big cat and the sum of a big cat and a cat.
+Open your heart.
+Juliet: You are the remainder of the quotient between a big big big cat and the difference between nothing and the sum of a big cat and a cat.
+Open your heart.
+Romeo: You are the difference between twice the cube of a big pig and the square of a big cat.
+Open your heart.
+",
+            &[""],
+        ),
+        (
+            "characters",
+            "Characters.
+Hamlet, a prince. Lady Macbeth, a schemer. The Ghost, a spirit.
+[Enter Hamlet and Lady Macbeth]
+Hamlet: You are the sum of a big big big big big big cat and the sum of a big cat and a cat.
+Speak your mind!
+Lady Macbeth: You are the product of a big big big big big big big cat and the sum of a big cat and a cat.
+Speak your mind! You are the sum of the product of a big big big big big big big big big big big big cat and the sum of a big big cat and a cat and a big big big big cat. Speak your mind!
+You are nothing. Speak your mind.
+[Exeunt]
+[Enter The Ghost and Hamlet]
+The Ghost: Open your heart.
+You are a big big big big big big big big big big big big big big big big big cat. Speak your mind.
+",
+            &[""],
+        ),
+        (
+            "input",
+            "Input.
+Romeo, a man. Juliet, a woman.
+[Enter Romeo and Juliet]
+Romeo: Listen to your heart. Open your heart.
+Juliet: Open your mind. Open your heart. Open your mind. Open your heart.
+Open your mind. Open your heart.
+",
+            &["-12\nλ!", "  +7  \nab", "3\n\u{1F3AD}", "0\n\u{e9}", "42"],
+        ),
+        (
+            "operands",
+            "Operands.
+Romeo, a man. Juliet, a woman. Hamlet, a prince.
+[Enter Romeo and Juliet]
+Romeo: Listen to your heart. Juliet, you are the sum of yourself and twice thyself.
+Open your heart.
+Juliet: You are the difference between the square of me and the cube of a big cat.
+Open your heart. You are the quotient between Romeo and the remainder of the quotient between me and a big big cat.
+Open your heart.
+[Exit Juliet]
+[Enter Hamlet]
+Hamlet: You are the product of Juliet and the sum of myself and a cat. Open your heart.
+",
+            &["7\n", "-5\n", "9\n"],
+        ),
+        (
+            "control-flow",
+            "Control flow.
+Romeo, a man. Juliet, a woman.
+Act I: Counting down and back up.
+Scene I: The start.
+[Enter Romeo and Juliet]
+Juliet: Listen to your heart. Remember nothing.
+Scene II: Counting down.
+Juliet: Open your heart. Remember yourself. You are the difference between yourself and a cat.
+Are you better than nothing? If so, let us return to scene II.
+Scene III: Counting up.
+Juliet: Recall your past. Are you as good as nothing? If so, let us proceed to scene IV.
+Open your heart. Are you not worse than a big big cat? If not, let us return to scene III.
+Romeo: You are the product of me and a big cat. Open your heart. Let us return to scene III.
+Scene IV: The end.
+Romeo: Open your mind. Speak your mind.
+",
+            &["3\nx", "6\n\u{e9}", "0\n?", "-2\n!"],
+        ),
+    ];
+
+    /// Runs a play with the evaluator, giving back what it printed.
+    pub(crate) fn interpret(play: &str, input: &str) -> String {
+        #[derive(Clone, Default)]
+        struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+        impl io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let (items, db) = lower(play);
+        let root = ast::Root::cast(parse(play, true).syntax_node()).unwrap();
+        let buffer = Buffer::default();
+        Evaluator::with_input(io::Cursor::new(input.to_string()))
+            .with_output(buffer.clone())
+            .eval(&root, &items, &db)
+            .unwrap();
+
+        let output = buffer.0.borrow().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    /// Lowers a play, making sure that it is valid first.
+    pub(crate) fn lower(play: &str) -> (Vec<hir::Item>, hir::Database) {
+        let parse = parse(play, true);
+        assert!(parse.errors.is_empty());
+
+        let root = ast::Root::cast(parse.syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+        assert!(validate(&root, &items, &db).is_empty());
+
+        (items, db)
+    }
+
+    /// Whether a program needed by a test is installed, so that the test can
+    /// be skipped instead of failing where it isn’t.
+    pub(crate) fn installed(program: &str) -> bool {
+        let installed = std::process::Command::new(program)
+            .arg("--version")
+            .output()
+            .is_ok();
+        if !installed {
+            eprintln!("skipping: {} is not installed", program);
+        }
+
+        installed
+    }
+
+    /// Builds every conformance play with `build`, which is given a
+    /// directory to work in and gives back the command that runs the play,
+    /// then checks that the play prints what the evaluator does on each
+    /// input.
+    pub(crate) fn check_conformance(language: &str, build: impl Fn(&Path, &str, &str) -> Command) {
+        let dir = env::temp_dir().join(format!("puck-{}-{}", language, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        for (name, play, inputs) in CONFORMANCE {
+            let mut command = build(&dir, name, play);
+
+            for input in *inputs {
+                let mut child = command
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .unwrap();
+                child
+                    .stdin
+                    .take()
+                    .unwrap()
+                    .write_all(input.as_bytes())
+                    .unwrap();
+                let output = child.wait_with_output().unwrap();
+
+                assert!(output.status.success(), "{} failed on {:?}", name, input);
+                assert_eq!(
+                    String::from_utf8(output.stdout).unwrap(),
+                    interpret(play, input),
+                    "{} on {:?}",
+                    name,
+                    input
+                );
+            }
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn infix_brackets_nested_operations() {
+        let (items, db) = lower(CONFORMANCE[0].1);
+        let program = Program::new(&items, &db);
+
+        let exprs: Vec<_> = program
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::Assign { expr, .. } => {
+                    let mut s = String::new();
+                    let syntax = Infix {
+                        suffix: "",
+                        call_suffix: "",
+                        name: str::to_string,
+                    };
+                    infix(&mut s, expr, &program, &syntax);
+                    Some(s)
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            exprs,
+            [
+                "4 + -1",
+                "0 - (2 * 8)",
+                "(0 - 8) / 2",
+                "(0 - 8) % (2 + 1)",
+                "8 % (0 - (2 + 1))",
+                "-16 - 4"
+            ]
+        );
+    }
+}
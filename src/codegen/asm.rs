@@ -0,0 +1,1029 @@
+use super::{Comparison, Expr, Program, Step};
+use crate::hir;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs, io, process};
+
+/// Writes GNU assembler source for x86-64 Linux, which needs nothing but the
+/// kernel to run: input and output are raw `read` and `write` system calls.
+/// Character values live in `.data`, and expressions are worked out in `rax`,
+/// with the stack holding left-hand sides while right-hand sides are found.
+/// Scenes that gotos lead to are labels, and characters’ stacks are lists
+/// of nodes in memory taken from the kernel with `brk`.
+pub(crate) fn emit(program: &Program) -> String {
+    let mut s = String::new();
+
+    s.push_str("# Generated by puck.\n\n");
+    s.push_str("    .data\n");
+    for variable in program.variables() {
+        writeln!(s, "{}:\n    .quad 0", variable).unwrap();
+    }
+
+    for character in program.stacks() {
+        writeln!(s, "{}_stack:\n    .quad 0", program.variable(character)).unwrap();
+    }
+    // Nothing has been asked until the first question.
+    if program.asks() {
+        s.push_str("answer:\n    .quad -1\n");
+    }
+
+    s.push_str("\n    .text\n    .globl _start\n_start:");
+    let mut skips = 0;
+    for step in &program.steps {
+        match step {
+            Step::Comment(comment) => write!(s, "\n\n    # {}", comment).unwrap(),
+            Step::Scene(scene) if program.is_target(*scene) => {
+                write!(s, "\nscene_{}:", scene).unwrap();
+            }
+            Step::Scene(_) => {}
+            _ => statement(&mut s, step, program, &mut skips),
+        }
+    }
+    s.push_str("\n\n    movq $60, %rax\n    xorq %rdi, %rdi\n    syscall\n");
+
+    let reads = program.uses(|step| matches!(step, Step::IntInput(_) | Step::CharInput(_)));
+    if program.uses(|step| matches!(step, Step::IntOutput(_))) {
+        s.push_str(PUT_INT);
+    }
+    if program.uses(|step| matches!(step, Step::CharOutput(_))) {
+        s.push_str(PUT_CHAR);
+    }
+    if program.uses(|step| matches!(step, Step::IntInput(_))) {
+        s.push_str(GET_INT);
+    }
+    if program.uses(|step| matches!(step, Step::CharInput(_))) {
+        s.push_str(GET_CHAR);
+    }
+    if reads {
+        s.push_str(GET_BYTE);
+    }
+    if program.checks_any() {
+        s.push_str(CHECKS);
+    }
+    if program.uses(|step| matches!(step, Step::Conditional { .. })) {
+        s.push_str(ASKED);
+    }
+    if program.stacks().next().is_some() {
+        s.push_str(STACKS);
+        s.push_str("\n    .section .rodata");
+        for character in program.stacks() {
+            let variable = program.variable(character);
+            write!(
+                s,
+                "\n{0}_recall_message:\n    .ascii \"error: {1} has nothing to recall\\n\"\n    .set {0}_recall_length, . - {0}_recall_message",
+                variable,
+                program.name(character)
+            )
+            .unwrap();
+        }
+        s.push_str("\n    .text\n");
+    }
+    if program.checks_any()
+        || program.uses(|step| {
+            !matches!(
+                step,
+                Step::Comment(_)
+                    | Step::Assign { .. }
+                    | Step::Scene(_)
+                    | Step::Goto(_)
+                    | Step::Question { .. }
+            )
+        })
+    {
+        s.push_str(RUNTIME);
+    }
+
+    s
+}
+
+/// Writes a step that does something, numbering the labels that conditional
+/// steps jump to past them with `skips`.
+fn statement(s: &mut String, step: &Step, program: &Program, skips: &mut usize) {
+    match step {
+        Step::Assign { character, expr } => {
+            self::expr(s, expr, program);
+            write!(s, "\n    movq %rax, {}(%rip)", program.variable(*character)).unwrap();
+        }
+        Step::IntOutput(character) => write!(
+            s,
+            "\n    movq {}(%rip), %rdi\n    call put_int",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::CharOutput(character) => write!(
+            s,
+            "\n    movq {}(%rip), %rdi\n    call put_char",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::IntInput(character) => write!(
+            s,
+            "\n    call get_int\n    movq %rax, {}(%rip)",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::CharInput(character) => write!(
+            s,
+            "\n    call get_char\n    movq %rax, {}(%rip)",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::Goto(scene) => write!(s, "\n    jmp scene_{}", scene).unwrap(),
+        Step::Question {
+            lhs,
+            comparison,
+            rhs,
+        } => {
+            operands(s, lhs, rhs, program);
+            s.push_str("\n    cmpq %rcx, %rax");
+            let instruction = match comparison {
+                Comparison::Greater => "setg",
+                Comparison::GreaterOrEqual => "setge",
+                Comparison::Less => "setl",
+                Comparison::LessOrEqual => "setle",
+                Comparison::Equal => "sete",
+                Comparison::NotEqual => "setne",
+            };
+            write!(
+                s,
+                "\n    {} %al\n    movzbq %al, %rax\n    movq %rax, answer(%rip)",
+                instruction
+            )
+            .unwrap();
+        }
+        Step::Conditional { answer, step } => {
+            let skip = *skips;
+            *skips += 1;
+            let jump = if *answer { "jz" } else { "jnz" };
+            write!(
+                s,
+                "\n    movq answer(%rip), %rax\n    testq %rax, %rax\n    js nothing_asked\n    {} skip_{}",
+                jump, skip
+            )
+            .unwrap();
+            statement(s, step, program, skips);
+            write!(s, "\nskip_{}:", skip).unwrap();
+        }
+        Step::Push { character, expr } => {
+            self::expr(s, expr, program);
+            write!(
+                s,
+                "\n    leaq {}_stack(%rip), %rdi\n    call push",
+                program.variable(*character)
+            )
+            .unwrap();
+        }
+        Step::Pop(character) => write!(
+            s,
+            "\n    leaq {0}_stack(%rip), %rdi\n    leaq {0}_recall_message(%rip), %rsi\n    movq ${0}_recall_length, %rdx\n    call pop\n    movq %rax, {0}(%rip)",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::Comment(_) | Step::Scene(_) => unreachable!(),
+    }
+}
+
+/// Leaves the value of an expression in `rax`, clobbering `rcx` and `rdx`.
+/// Arithmetic on characters’ values fails like the evaluator’s when it
+/// overflows or divides by zero.
+fn expr(s: &mut String, expr: &Expr, program: &Program) {
+    match expr {
+        Expr::Number(number) => load(s, "rax", *number),
+        Expr::Variable(character) => {
+            write!(s, "\n    movq {}(%rip), %rax", program.variable(*character)).unwrap()
+        }
+        Expr::Bin {
+            operation,
+            lhs,
+            rhs,
+        } => {
+            operands(s, lhs, rhs, program);
+
+            let checked = expr.is_checked();
+            if checked
+                && matches!(
+                    operation,
+                    hir::Operation::Quotient | hir::Operation::Remainder
+                )
+            {
+                // Dividing the smallest value by -1 overflows, which is
+                // caught by negating it instead.
+                s.push_str(
+                    "\n    testq %rcx, %rcx\n    jz division_by_zero\n    cmpq $-1, %rcx\n    jne 1f\n    negq %rax\n    jo too_large\n    negq %rax\n1:",
+                );
+            }
+            s.push_str(match operation {
+                hir::Operation::Remainder => "\n    cqto\n    idivq %rcx\n    movq %rdx, %rax",
+                hir::Operation::Difference => "\n    subq %rcx, %rax",
+                hir::Operation::Quotient => "\n    cqto\n    idivq %rcx",
+                hir::Operation::Product => "\n    imulq %rcx, %rax",
+                hir::Operation::Sum => "\n    addq %rcx, %rax",
+            });
+            if checked
+                && matches!(
+                    operation,
+                    hir::Operation::Difference | hir::Operation::Product | hir::Operation::Sum
+                )
+            {
+                s.push_str("\n    jo too_large");
+            }
+        }
+    }
+}
+
+/// Leaves the values of `lhs` in `rax` and `rhs` in `rcx`.
+fn operands(s: &mut String, lhs: &Expr, rhs: &Expr, program: &Program) {
+    match rhs {
+        Expr::Number(number) => {
+            expr(s, lhs, program);
+            load(s, "rcx", *number);
+        }
+        Expr::Variable(character) => {
+            expr(s, lhs, program);
+            write!(s, "\n    movq {}(%rip), %rcx", program.variable(*character)).unwrap();
+        }
+        _ => {
+            expr(s, lhs, program);
+            s.push_str("\n    pushq %rax");
+            expr(s, rhs, program);
+            s.push_str("\n    movq %rax, %rcx\n    popq %rax");
+        }
+    }
+}
+
+fn load(s: &mut String, register: &str, number: i64) {
+    // Only `movabsq` takes a full 64-bit immediate.
+    let instruction = if i32::try_from(number).is_ok() {
+        "movq"
+    } else {
+        "movabsq"
+    };
+    write!(s, "\n    {} ${}, %{}", instruction, number, register).unwrap();
+}
+
+/// Assembles and links what `emit` wrote into an executable at `output`,
+/// with the system’s `as` and `ld`.
+pub(crate) fn build(source: &str, output: &Path) -> Result<(), String> {
+    let dir = env::temp_dir().join(format!("puck-build-{}", process::id()));
+    fs::create_dir_all(&dir).map_err(|error| error.to_string())?;
+    let assembly = dir.join("play.s");
+    let object = dir.join("play.o");
+
+    let result = fs::write(&assembly, source)
+        .map_err(|error| error.to_string())
+        .and_then(|()| {
+            run(Command::new("as")
+                .arg("--64")
+                .arg("-o")
+                .arg(&object)
+                .arg(&assembly))
+        })
+        .and_then(|()| run(Command::new("ld").arg("-o").arg(output).arg(&object)));
+
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} failed with {}", program, status)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            Err(format!("{} is not installed", program))
+        }
+        Err(error) => Err(format!("could not run {}: {}", program, error)),
+    }
+}
+
+/// Prints `rdi` in decimal on its own line. Remainders take the sign of the
+/// number, so negative numbers are printed without negating them, which
+/// would overflow for the smallest one.
+const PUT_INT: &str = r#"
+put_int:
+    movq %rdi, %rax
+    movq %rdi, %r8
+    leaq output_buffer+32(%rip), %rsi
+    decq %rsi
+    movb $10, (%rsi)
+    movq $10, %rcx
+1:
+    cqto
+    idivq %rcx
+    testq %rdx, %rdx
+    jns 2f
+    negq %rdx
+2:
+    addq $48, %rdx
+    decq %rsi
+    movb %dl, (%rsi)
+    testq %rax, %rax
+    jnz 1b
+    testq %r8, %r8
+    jns 3f
+    decq %rsi
+    movb $45, (%rsi)
+3:
+    leaq output_buffer+32(%rip), %rdx
+    subq %rsi, %rdx
+    jmp write
+"#;
+
+/// Prints `rdi` as a UTF-8 encoded character. The bytes
+/// after the first are filled in from the end, six bits at a time.
+const PUT_CHAR: &str = r#"
+put_char:
+    cmpq $0, %rdi
+    jl not_a_character
+    movq $1, %rdx
+    xorq %rcx, %rcx
+    cmpq $0x80, %rdi
+    jl 1f
+    movq $2, %rdx
+    movq $0xC0, %rcx
+    cmpq $0x800, %rdi
+    jl 1f
+    movq $3, %rdx
+    movq $0xE0, %rcx
+    cmpq $0xD800, %rdi
+    jl 1f
+    cmpq $0xE000, %rdi
+    jl not_a_character
+    cmpq $0x10000, %rdi
+    jl 1f
+    movq $4, %rdx
+    movq $0xF0, %rcx
+    cmpq $0x110000, %rdi
+    jge not_a_character
+1:
+    leaq output_buffer(%rip), %rsi
+    movq %rdi, %rax
+    movq %rdx, %r8
+2:
+    decq %r8
+    jz 3f
+    movq %rax, %r9
+    andq $0x3F, %r9
+    orq $0x80, %r9
+    movb %r9b, (%rsi,%r8)
+    shrq $6, %rax
+    jmp 2b
+3:
+    orq %rcx, %rax
+    movb %al, (%rsi)
+    jmp write
+
+not_a_character:
+    leaq not_a_character_message(%rip), %rsi
+    movq $not_a_character_length, %rdx
+    jmp fail
+
+    .section .rodata
+not_a_character_message:
+    .ascii "error: a value is not a character\n"
+    .set not_a_character_length, . - not_a_character_message
+    .text
+"#;
+
+/// Reads a whole line, which has to hold nothing but a number once the
+/// whitespace around it is trimmed. The number is built up negatively, so
+/// that the smallest one can be read without overflowing.
+const GET_INT: &str = r#"
+get_int:
+    pushq %rbx
+    pushq %r12
+    xorq %rbx, %rbx
+    xorq %r12, %r12
+1:
+    call get_byte
+    cmpq $0, %rax
+    jl 2f
+    movq $1, %r12
+    cmpq $4096, %rbx
+    jge not_a_number
+    leaq line_buffer(%rip), %rcx
+    movb %al, (%rcx,%rbx)
+    incq %rbx
+    cmpq $10, %rax
+    jne 1b
+2:
+    testq %r12, %r12
+    jz input_ended
+    leaq line_buffer(%rip), %rdi
+    xorq %rsi, %rsi
+3:
+    cmpq %rbx, %rsi
+    jge 4f
+    movzbq (%rdi,%rsi), %rax
+    cmpq $32, %rax
+    je 31f
+    subq $9, %rax
+    cmpq $4, %rax
+    ja 4f
+31:
+    incq %rsi
+    jmp 3b
+4:
+    cmpq %rsi, %rbx
+    jle 5f
+    movzbq -1(%rdi,%rbx), %rax
+    cmpq $32, %rax
+    je 41f
+    subq $9, %rax
+    cmpq $4, %rax
+    ja 5f
+41:
+    decq %rbx
+    jmp 4b
+5:
+    cmpq %rsi, %rbx
+    je not_a_number
+    xorq %rcx, %rcx
+    movzbq (%rdi,%rsi), %rax
+    cmpq $45, %rax
+    jne 51f
+    movq $1, %rcx
+    incq %rsi
+    jmp 52f
+51:
+    cmpq $43, %rax
+    jne 52f
+    incq %rsi
+52:
+    cmpq %rsi, %rbx
+    je not_a_number
+    xorq %rdx, %rdx
+6:
+    cmpq %rsi, %rbx
+    je 7f
+    movzbq (%rdi,%rsi), %rax
+    subq $48, %rax
+    cmpq $9, %rax
+    ja not_a_number
+    imulq $10, %rdx
+    jo not_a_number
+    subq %rax, %rdx
+    jo not_a_number
+    incq %rsi
+    jmp 6b
+7:
+    testq %rcx, %rcx
+    jnz 8f
+    negq %rdx
+    jo not_a_number
+8:
+    movq %rdx, %rax
+    popq %r12
+    popq %rbx
+    ret
+
+input_ended:
+    leaq input_ended_message(%rip), %rsi
+    movq $input_ended_length, %rdx
+    jmp fail
+
+not_a_number:
+    leaq not_a_number_message(%rip), %rsi
+    movq $not_a_number_length, %rdx
+    jmp fail
+
+    .section .rodata
+input_ended_message:
+    .ascii "error: expected a number as input but the input ended\n"
+    .set input_ended_length, . - input_ended_message
+not_a_number_message:
+    .ascii "error: expected a number as input\n"
+    .set not_a_number_length, . - not_a_number_message
+
+    .bss
+line_buffer:
+    .skip 4096
+    .text
+"#;
+
+/// Reads a single UTF-8 encoded character, giving -1 at the end of the input
+/// and U+FFFD for bytes that aren’t valid UTF-8.
+const GET_CHAR: &str = r#"
+get_char:
+    pushq %rbx
+    pushq %r12
+    pushq %r13
+    pushq %r14
+    pushq %r15
+    call get_byte
+    cmpq $0, %rax
+    jl 9f
+    movq %rax, %rbx
+    movq $1, %r12
+    movq %rbx, %rcx
+    andq $0xE0, %rcx
+    cmpq $0xC0, %rcx
+    jne 1f
+    movq $2, %r12
+    jmp 3f
+1:
+    movq %rbx, %rcx
+    andq $0xF0, %rcx
+    cmpq $0xE0, %rcx
+    jne 2f
+    movq $3, %r12
+    jmp 3f
+2:
+    movq %rbx, %rcx
+    andq $0xF8, %rcx
+    cmpq $0xF0, %rcx
+    jne 3f
+    movq $4, %r12
+3:
+    movq $0x7F, %r13
+    movq %r12, %rcx
+    shrq %cl, %r13
+    andq %rbx, %r13
+    xorq %r14, %r14
+    movq $1, %r15
+4:
+    cmpq %r12, %r15
+    jge 6f
+    call get_byte
+    cmpq $0, %rax
+    jl input_ended_inside_character
+    movq %rax, %rcx
+    andq $0xC0, %rcx
+    cmpq $0x80, %rcx
+    je 5f
+    movq $1, %r14
+5:
+    shlq $6, %r13
+    andq $0x3F, %rax
+    orq %rax, %r13
+    incq %r15
+    jmp 4b
+6:
+    cmpq $1, %r12
+    jne 7f
+    movq %rbx, %rax
+    cmpq $0x80, %rax
+    jl 9f
+    jmp 8f
+7:
+    testq %r14, %r14
+    jnz 8f
+    leaq smallest_in_length(%rip), %rcx
+    cmpq (%rcx,%r12,8), %r13
+    jl 8f
+    cmpq $0x10FFFF, %r13
+    jg 8f
+    movq %r13, %rax
+    cmpq $0xD800, %r13
+    jl 9f
+    cmpq $0xDFFF, %r13
+    jg 9f
+8:
+    movq $0xFFFD, %rax
+9:
+    popq %r15
+    popq %r14
+    popq %r13
+    popq %r12
+    popq %rbx
+    ret
+
+input_ended_inside_character:
+    leaq inside_character_message(%rip), %rsi
+    movq $inside_character_length, %rdx
+    jmp fail
+
+    .section .rodata
+smallest_in_length:
+    .quad 0, 0, 0x80, 0x800, 0x10000
+inside_character_message:
+    .ascii "error: could not read input: the input ended inside a character\n"
+    .set inside_character_length, . - inside_character_message
+    .text
+"#;
+
+/// Gives the next byte of input in `rax`, or -1 at the end of the input,
+/// reading a buffer’s worth at a time.
+const GET_BYTE: &str = r#"
+get_byte:
+    movq input_position(%rip), %rax
+    cmpq input_length(%rip), %rax
+    jl 1f
+    xorq %rax, %rax
+    xorq %rdi, %rdi
+    leaq input_buffer(%rip), %rsi
+    movq $4096, %rdx
+    syscall
+    testq %rax, %rax
+    jle 2f
+    movq %rax, input_length(%rip)
+    xorq %rax, %rax
+1:
+    leaq input_buffer(%rip), %rsi
+    movzbq (%rsi,%rax), %rcx
+    incq %rax
+    movq %rax, input_position(%rip)
+    movq %rcx, %rax
+    ret
+2:
+    movq $-1, %rax
+    ret
+
+    .bss
+input_buffer:
+    .skip 4096
+input_position:
+    .quad 0
+input_length:
+    .quad 0
+    .text
+"#;
+
+/// Where arithmetic on characters’ values goes when it can’t be done.
+const CHECKS: &str = r#"
+too_large:
+    leaq too_large_message(%rip), %rsi
+    movq $too_large_length, %rdx
+    jmp fail
+
+division_by_zero:
+    leaq division_by_zero_message(%rip), %rsi
+    movq $division_by_zero_length, %rdx
+    jmp fail
+
+    .section .rodata
+too_large_message:
+    .ascii "error: value is too large\n"
+    .set too_large_length, . - too_large_message
+division_by_zero_message:
+    .ascii "error: division by zero\n"
+    .set division_by_zero_length, . - division_by_zero_message
+    .text
+"#;
+
+/// Where a conditional step goes when nothing has been asked yet.
+const ASKED: &str = r#"
+nothing_asked:
+    leaq nothing_asked_message(%rip), %rsi
+    movq $nothing_asked_length, %rdx
+    jmp fail
+
+    .section .rodata
+nothing_asked_message:
+    .ascii "error: nothing has been asked yet\n"
+    .set nothing_asked_length, . - nothing_asked_message
+    .text
+"#;
+
+/// Pushes `rax` onto the stack whose top is at `rdi`, and pops it back off,
+/// failing with the message at `rsi` that is `rdx` bytes long if the stack is
+/// empty. Each value is kept in a sixteen-byte node along with the node below
+/// it. Popped nodes are kept in a list of their own to be pushed again, and
+/// new ones come from the end of the heap, which grows 64 KiB at a time. The
+/// first time, `brk` is asked where the heap starts. System calls clobber
+/// `rcx`, so it is loaded again after them.
+const STACKS: &str = r#"
+push:
+    movq free_nodes(%rip), %rcx
+    testq %rcx, %rcx
+    jz 1f
+    movq 8(%rcx), %rdx
+    movq %rdx, free_nodes(%rip)
+    jmp 3f
+1:
+    movq heap_next(%rip), %rcx
+    cmpq heap_end(%rip), %rcx
+    jb 2f
+    pushq %rax
+    pushq %rdi
+    movq heap_end(%rip), %rdi
+    testq %rdi, %rdi
+    jnz 11f
+    movq $12, %rax
+    syscall
+    movq %rax, heap_next(%rip)
+    movq %rax, %rdi
+11:
+    addq $65536, %rdi
+    movq $12, %rax
+    syscall
+    cmpq %rdi, %rax
+    jne out_of_memory
+    movq %rax, heap_end(%rip)
+    popq %rdi
+    popq %rax
+    movq heap_next(%rip), %rcx
+2:
+    leaq 16(%rcx), %rdx
+    movq %rdx, heap_next(%rip)
+3:
+    movq %rax, (%rcx)
+    movq (%rdi), %rdx
+    movq %rdx, 8(%rcx)
+    movq %rcx, (%rdi)
+    ret
+
+pop:
+    movq (%rdi), %rcx
+    testq %rcx, %rcx
+    jz fail
+    movq 8(%rcx), %rdx
+    movq %rdx, (%rdi)
+    movq free_nodes(%rip), %rdx
+    movq %rdx, 8(%rcx)
+    movq %rcx, free_nodes(%rip)
+    movq (%rcx), %rax
+    ret
+
+out_of_memory:
+    leaq out_of_memory_message(%rip), %rsi
+    movq $out_of_memory_length, %rdx
+    jmp fail
+
+    .section .rodata
+out_of_memory_message:
+    .ascii "error: out of memory\n"
+    .set out_of_memory_length, . - out_of_memory_message
+
+    .bss
+free_nodes:
+    .quad 0
+heap_next:
+    .quad 0
+heap_end:
+    .quad 0
+    .text
+"#;
+
+/// Writes `rdx` bytes from `rsi` to stdout, or gives up with them as the
+/// error message, along with the buffer that output is put together in.
+const RUNTIME: &str = r#"
+write:
+    movq $1, %rax
+    movq $1, %rdi
+    syscall
+    ret
+
+fail:
+    movq $1, %rax
+    movq $2, %rdi
+    syscall
+    movq $60, %rax
+    movq $1, %rdi
+    syscall
+
+    .bss
+output_buffer:
+    .skip 32
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::{check_conformance, installed, lower};
+    use expect_test::expect;
+
+    fn emit_play(play: &str) -> String {
+        let (items, db) = lower(play);
+        emit(&Program::new(&items, &db))
+    }
+
+    #[test]
+    fn emit_arithmetic() {
+        expect![[r##"
+# Generated by puck.
+
+    .data
+romeo:
+    .quad 0
+lady_macbeth:
+    .quad 0
+
+    .text
+    .globl _start
+_start:
+
+    # Enter Romeo and Lady Macbeth
+
+    # Romeo, to Lady Macbeth
+    movq $0, %rax
+    pushq %rax
+    movq $2, %rax
+    pushq %rax
+    movq $2, %rax
+    movq $1, %rcx
+    addq %rcx, %rax
+    movq %rax, %rcx
+    popq %rax
+    cqto
+    idivq %rcx
+    movq %rax, %rcx
+    popq %rax
+    subq %rcx, %rax
+    movq %rax, lady_macbeth(%rip)
+    movabsq $-4294967296, %rax
+    movq %rax, lady_macbeth(%rip)
+
+    movq $60, %rax
+    xorq %rdi, %rdi
+    syscall
+"##]]
+        .assert_eq(&emit_play(
+            "Title.
+Romeo, a man. Lady Macbeth, a woman.
+[Enter Romeo and Lady Macbeth]
+Romeo: You are the difference between nothing and the quotient between a big cat and the sum of a big cat and a cat.
+You are a big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big pig.
+",
+        ));
+    }
+
+    #[test]
+    fn emit_control_flow() {
+        expect![[r##"
+# Generated by puck.
+
+    .data
+romeo:
+    .quad 0
+juliet:
+    .quad 0
+juliet_stack:
+    .quad 0
+answer:
+    .quad -1
+
+    .text
+    .globl _start
+_start:
+
+    # Act I
+
+    # Scene I
+
+    # Enter Romeo and Juliet
+
+    # Scene II
+scene_2:
+
+    # Romeo, to Juliet
+    movq romeo(%rip), %rax
+    leaq juliet_stack(%rip), %rdi
+    call push
+    leaq juliet_stack(%rip), %rdi
+    leaq juliet_recall_message(%rip), %rsi
+    movq $juliet_recall_length, %rdx
+    call pop
+    movq %rax, juliet(%rip)
+    movq juliet(%rip), %rax
+    movq $0, %rcx
+    cmpq %rcx, %rax
+    setle %al
+    movzbq %al, %rax
+    movq %rax, answer(%rip)
+    movq answer(%rip), %rax
+    testq %rax, %rax
+    js nothing_asked
+    jnz skip_0
+    jmp scene_2
+skip_0:
+
+    movq $60, %rax
+    xorq %rdi, %rdi
+    syscall
+
+nothing_asked:
+    leaq nothing_asked_message(%rip), %rsi
+    movq $nothing_asked_length, %rdx
+    jmp fail
+
+    .section .rodata
+nothing_asked_message:
+    .ascii "error: nothing has been asked yet\n"
+    .set nothing_asked_length, . - nothing_asked_message
+    .text
+
+push:
+    movq free_nodes(%rip), %rcx
+    testq %rcx, %rcx
+    jz 1f
+    movq 8(%rcx), %rdx
+    movq %rdx, free_nodes(%rip)
+    jmp 3f
+1:
+    movq heap_next(%rip), %rcx
+    cmpq heap_end(%rip), %rcx
+    jb 2f
+    pushq %rax
+    pushq %rdi
+    movq heap_end(%rip), %rdi
+    testq %rdi, %rdi
+    jnz 11f
+    movq $12, %rax
+    syscall
+    movq %rax, heap_next(%rip)
+    movq %rax, %rdi
+11:
+    addq $65536, %rdi
+    movq $12, %rax
+    syscall
+    cmpq %rdi, %rax
+    jne out_of_memory
+    movq %rax, heap_end(%rip)
+    popq %rdi
+    popq %rax
+    movq heap_next(%rip), %rcx
+2:
+    leaq 16(%rcx), %rdx
+    movq %rdx, heap_next(%rip)
+3:
+    movq %rax, (%rcx)
+    movq (%rdi), %rdx
+    movq %rdx, 8(%rcx)
+    movq %rcx, (%rdi)
+    ret
+
+pop:
+    movq (%rdi), %rcx
+    testq %rcx, %rcx
+    jz fail
+    movq 8(%rcx), %rdx
+    movq %rdx, (%rdi)
+    movq free_nodes(%rip), %rdx
+    movq %rdx, 8(%rcx)
+    movq %rcx, free_nodes(%rip)
+    movq (%rcx), %rax
+    ret
+
+out_of_memory:
+    leaq out_of_memory_message(%rip), %rsi
+    movq $out_of_memory_length, %rdx
+    jmp fail
+
+    .section .rodata
+out_of_memory_message:
+    .ascii "error: out of memory\n"
+    .set out_of_memory_length, . - out_of_memory_message
+
+    .bss
+free_nodes:
+    .quad 0
+heap_next:
+    .quad 0
+heap_end:
+    .quad 0
+    .text
+
+    .section .rodata
+juliet_recall_message:
+    .ascii "error: Juliet has nothing to recall\n"
+    .set juliet_recall_length, . - juliet_recall_message
+    .text
+
+write:
+    movq $1, %rax
+    movq $1, %rdi
+    syscall
+    ret
+
+fail:
+    movq $1, %rax
+    movq $2, %rdi
+    syscall
+    movq $60, %rax
+    movq $1, %rdi
+    syscall
+
+    .bss
+output_buffer:
+    .skip 32
+"##]]
+        .assert_eq(&emit_play(
+            "Control flow.
+Romeo, a man. Juliet, a woman.
+Act I: A loop. Scene I: The stage. [Enter Romeo and Juliet]
+Scene II: The loop.
+Romeo: Remember me. Recall your past. Are you not better than nothing?
+If not, let us return to scene II.
+",
+        ));
+    }
+
+    #[test]
+    fn executable_output_matches_evaluator() {
+        if !cfg!(all(target_arch = "x86_64", target_os = "linux"))
+            || !installed("as")
+            || !installed("ld")
+        {
+            return;
+        }
+
+        check_conformance("asm", |dir, name, play| {
+            let binary = dir.join(name);
+            build(&emit_play(play), &binary).unwrap();
+            Command::new(binary)
+        });
+    }
+}
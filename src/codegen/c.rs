@@ -0,0 +1,563 @@
+use super::{hir, infix, Infix, Program, Step, OPERATIONS};
+use std::fmt::Write;
+
+/// Writes a C99 program that behaves like the play, only including the I/O
+/// helpers it needs.
+pub(crate) fn emit(program: &Program) -> String {
+    let mut s = String::new();
+
+    s.push_str("/* Generated by puck. */\n\n");
+    if program.uses(|step| matches!(step, Step::IntInput(_))) {
+        s.push_str("#include <ctype.h>\n#include <errno.h>\n");
+    }
+    s.push_str("#include <inttypes.h>\n#include <stdio.h>\n#include <stdlib.h>\n");
+
+    if program.uses(|step| matches!(step, Step::CharOutput(_))) {
+        s.push_str(PUT_CHAR);
+    }
+    if program.uses(|step| matches!(step, Step::IntInput(_))) {
+        s.push_str(GET_INT);
+    }
+    if program.uses(|step| matches!(step, Step::CharInput(_))) {
+        s.push_str(GET_CHAR);
+    }
+    let asks = program.uses(|step| matches!(step, Step::Conditional { .. }));
+    let remembers = program.stacks().next().is_some();
+    if program.checks_any() || asks || remembers {
+        s.push_str(FAIL);
+    }
+    for operation in OPERATIONS {
+        if program.checks(operation) {
+            s.push_str(checked(operation));
+        }
+    }
+    if asks {
+        s.push_str(ASKED);
+    }
+    if remembers {
+        s.push_str(STACK);
+    }
+
+    s.push_str("\nint main(void) {\n");
+    for variable in program.variables() {
+        writeln!(s, "    int64_t {} = 0;", variable).unwrap();
+    }
+    for character in program.stacks() {
+        writeln!(
+            s,
+            "    struct stack {}_stack = {{NULL, 0, 0}};",
+            program.variable(character)
+        )
+        .unwrap();
+    }
+    // Nothing has been asked until the first question.
+    if program.asks() {
+        s.push_str("    int answer = -1;\n    (void)answer;\n");
+    }
+    // Characters whose values are never printed would otherwise be warned
+    // about as unused.
+    let printed: Vec<&str> = program
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            Step::IntOutput(character) | Step::CharOutput(character) => {
+                Some(program.variable(*character))
+            }
+            _ => None,
+        })
+        .collect();
+    for variable in program.variables() {
+        if !printed.contains(&variable) {
+            writeln!(s, "    (void){};", variable).unwrap();
+        }
+    }
+
+    for step in &program.steps {
+        match step {
+            Step::Comment(comment) => writeln!(s, "\n    /* {} */", comment).unwrap(),
+            // Only scenes that are gone to need labels, as C warns about
+            // the rest.
+            Step::Scene(scene) if program.is_target(*scene) => {
+                writeln!(s, "scene_{}:", scene).unwrap();
+            }
+            Step::Scene(_) => {}
+            _ => {
+                s.push_str("    ");
+                statement(&mut s, step, program);
+                s.push('\n');
+            }
+        }
+    }
+
+    s.push_str("\n    return 0;\n}\n");
+    s
+}
+
+/// Writes a step that does something, on lines of its own after the first.
+fn statement(s: &mut String, step: &Step, program: &Program) {
+    match step {
+        Step::Assign { character, expr } => {
+            write!(s, "{} = ", program.variable(*character)).unwrap();
+            infix(s, expr, program, &SYNTAX);
+            s.push(';');
+        }
+        Step::IntOutput(character) => write!(
+            s,
+            "printf(\"%\" PRId64 \"\\n\", {});",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::CharOutput(character) => {
+            write!(s, "put_char({});", program.variable(*character)).unwrap();
+        }
+        Step::IntInput(character) => {
+            write!(s, "{} = get_int();", program.variable(*character)).unwrap();
+        }
+        Step::CharInput(character) => {
+            write!(s, "{} = get_char();", program.variable(*character)).unwrap();
+        }
+        Step::Goto(scene) => write!(s, "goto scene_{};", scene).unwrap(),
+        Step::Question {
+            lhs,
+            comparison,
+            rhs,
+        } => {
+            s.push_str("answer = ");
+            infix(s, lhs, program, &SYNTAX);
+            write!(s, " {} ", comparison.operator()).unwrap();
+            infix(s, rhs, program, &SYNTAX);
+            s.push(';');
+        }
+        Step::Conditional { answer, step } => {
+            let not = if *answer { "" } else { "!" };
+            write!(s, "if ({}asked(answer)) {{\n        ", not).unwrap();
+            statement(s, step, program);
+            s.push_str("\n    }");
+        }
+        Step::Push { character, expr } => {
+            write!(s, "push(&{}_stack, ", program.variable(*character)).unwrap();
+            infix(s, expr, program, &SYNTAX);
+            s.push_str(");");
+        }
+        Step::Pop(character) => {
+            let variable = program.variable(*character);
+            write!(
+                s,
+                "{} = pop(&{}_stack, \"{} has nothing to recall\");",
+                variable,
+                variable,
+                program.name(*character)
+            )
+            .unwrap();
+        }
+        Step::Comment(_) | Step::Scene(_) => unreachable!(),
+    }
+}
+
+const SYNTAX: Infix = Infix {
+    suffix: "",
+    call_suffix: "",
+    name: str::to_string,
+};
+
+/// The function doing an operation on values only known as the program runs,
+/// with the evaluator’s messages for when it can’t be done.
+fn checked(operation: hir::Operation) -> &'static str {
+    match operation {
+        hir::Operation::Sum => ADD,
+        hir::Operation::Difference => SUBTRACT,
+        hir::Operation::Product => MULTIPLY,
+        hir::Operation::Quotient => DIVIDE,
+        hir::Operation::Remainder => REMAINDER,
+    }
+}
+
+const FAIL: &str = r#"
+static void fail(const char *message) {
+    fprintf(stderr, "error: %s\n", message);
+    exit(1);
+}
+"#;
+
+const ADD: &str = r#"
+static int64_t checked_add(int64_t lhs, int64_t rhs) {
+    if ((rhs > 0 && lhs > INT64_MAX - rhs) || (rhs < 0 && lhs < INT64_MIN - rhs)) {
+        fail("value is too large");
+    }
+    return lhs + rhs;
+}
+"#;
+
+const SUBTRACT: &str = r#"
+static int64_t checked_subtract(int64_t lhs, int64_t rhs) {
+    if ((rhs < 0 && lhs > INT64_MAX + rhs) || (rhs > 0 && lhs < INT64_MIN + rhs)) {
+        fail("value is too large");
+    }
+    return lhs - rhs;
+}
+"#;
+
+const MULTIPLY: &str = r#"
+static int64_t checked_multiply(int64_t lhs, int64_t rhs) {
+    if (lhs > 0 ? (rhs > 0 ? lhs > INT64_MAX / rhs : rhs < INT64_MIN / lhs)
+                : (rhs > 0 ? lhs < INT64_MIN / rhs : lhs != 0 && rhs < INT64_MAX / lhs)) {
+        fail("value is too large");
+    }
+    return lhs * rhs;
+}
+"#;
+
+const DIVIDE: &str = r#"
+static int64_t checked_divide(int64_t lhs, int64_t rhs) {
+    if (rhs == 0) {
+        fail("division by zero");
+    }
+    if (lhs == INT64_MIN && rhs == -1) {
+        fail("value is too large");
+    }
+    return lhs / rhs;
+}
+"#;
+
+const REMAINDER: &str = r#"
+static int64_t checked_remainder(int64_t lhs, int64_t rhs) {
+    if (rhs == 0) {
+        fail("division by zero");
+    }
+    if (lhs == INT64_MIN && rhs == -1) {
+        fail("value is too large");
+    }
+    return lhs % rhs;
+}
+"#;
+
+/// Gives back the answer to the last question, which is -1 until one has
+/// been asked.
+const ASKED: &str = r#"
+static int asked(int answer) {
+    if (answer < 0) {
+        fail("nothing has been asked yet");
+    }
+    return answer;
+}
+"#;
+
+/// A character’s stack, which grows as it needs to.
+const STACK: &str = r#"
+struct stack {
+    int64_t *values;
+    size_t length, capacity;
+};
+
+static void push(struct stack *stack, int64_t value) {
+    if (stack->length == stack->capacity) {
+        size_t capacity = stack->capacity == 0 ? 16 : stack->capacity * 2;
+        int64_t *values = realloc(stack->values, capacity * sizeof *values);
+        if (values == NULL) {
+            fail("out of memory");
+        }
+        stack->values = values;
+        stack->capacity = capacity;
+    }
+    stack->values[stack->length++] = value;
+}
+
+static int64_t pop(struct stack *stack, const char *message) {
+    if (stack->length == 0) {
+        fail(message);
+    }
+    return stack->values[--stack->length];
+}
+"#;
+
+/// Prints a value as a UTF-8 encoded character.
+const PUT_CHAR: &str = r#"
+static void put_char(int64_t value) {
+    char bytes[4];
+    size_t length;
+
+    if (value < 0 || value > 0x10FFFF || (value >= 0xD800 && value <= 0xDFFF)) {
+        fprintf(stderr, "error: %" PRId64 " is not a character\n", value);
+        exit(1);
+    }
+
+    if (value < 0x80) {
+        bytes[0] = (char)value;
+        length = 1;
+    } else if (value < 0x800) {
+        bytes[0] = (char)(0xC0 | (value >> 6));
+        bytes[1] = (char)(0x80 | (value & 0x3F));
+        length = 2;
+    } else if (value < 0x10000) {
+        bytes[0] = (char)(0xE0 | (value >> 12));
+        bytes[1] = (char)(0x80 | ((value >> 6) & 0x3F));
+        bytes[2] = (char)(0x80 | (value & 0x3F));
+        length = 3;
+    } else {
+        bytes[0] = (char)(0xF0 | (value >> 18));
+        bytes[1] = (char)(0x80 | ((value >> 12) & 0x3F));
+        bytes[2] = (char)(0x80 | ((value >> 6) & 0x3F));
+        bytes[3] = (char)(0x80 | (value & 0x3F));
+        length = 4;
+    }
+
+    fwrite(bytes, 1, length, stdout);
+}
+"#;
+
+/// Reads a whole line, which has to hold nothing but a number once the
+/// whitespace around it is trimmed.
+const GET_INT: &str = r#"
+static int64_t get_int(void) {
+    size_t length = 0, capacity = 16;
+    char *line = malloc(capacity), *start, *end, *rest;
+    long long value;
+    int c;
+
+    while ((c = getchar()) != EOF) {
+        if (line != NULL && length + 1 == capacity) {
+            char *longer = realloc(line, capacity *= 2);
+            if (longer == NULL) {
+                free(line);
+            }
+            line = longer;
+        }
+        if (line == NULL) {
+            fprintf(stderr, "error: could not read input: out of memory\n");
+            exit(1);
+        }
+        line[length++] = (char)c;
+        if (c == '\n') {
+            break;
+        }
+    }
+    if (length == 0) {
+        fprintf(stderr, "error: expected a number as input but the input ended\n");
+        exit(1);
+    }
+
+    start = line;
+    end = line + length;
+    while (start < end && isspace((unsigned char)*start)) {
+        start++;
+    }
+    while (end > start && isspace((unsigned char)end[-1])) {
+        end--;
+    }
+    *end = '\0';
+
+    errno = 0;
+    value = strtoll(start, &rest, 10);
+    if (start == end || *rest != '\0' || errno != 0) {
+        fprintf(stderr, "error: expected a number as input but found \"%s\"\n", start);
+        exit(1);
+    }
+
+    free(line);
+    return value;
+}
+"#;
+
+/// Reads a single UTF-8 encoded character, giving -1 at the end of the input
+/// and U+FFFD for bytes that aren’t valid UTF-8.
+const GET_CHAR: &str = r#"
+static int64_t get_char(void) {
+    static const int64_t smallest[] = {0, 0, 0x80, 0x800, 0x10000};
+    unsigned char bytes[4];
+    int first = getchar(), length = 1, i;
+    int64_t value;
+
+    if (first == EOF) {
+        return -1;
+    }
+    if ((first & 0xE0) == 0xC0) {
+        length = 2;
+    } else if ((first & 0xF0) == 0xE0) {
+        length = 3;
+    } else if ((first & 0xF8) == 0xF0) {
+        length = 4;
+    }
+
+    bytes[0] = (unsigned char)first;
+    for (i = 1; i < length; i++) {
+        int c = getchar();
+        if (c == EOF) {
+            fprintf(stderr, "error: could not read input: the input ended inside a character\n");
+            exit(1);
+        }
+        bytes[i] = (unsigned char)c;
+    }
+
+    if (length == 1) {
+        return first < 0x80 ? first : 0xFFFD;
+    }
+
+    value = bytes[0] & (0x7F >> length);
+    for (i = 1; i < length; i++) {
+        if ((bytes[i] & 0xC0) != 0x80) {
+            return 0xFFFD;
+        }
+        value = (value << 6) | (bytes[i] & 0x3F);
+    }
+    if (value < smallest[length] || value > 0x10FFFF || (value >= 0xD800 && value <= 0xDFFF)) {
+        return 0xFFFD;
+    }
+
+    return value;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::{check_conformance, installed, lower};
+    use expect_test::expect;
+    use std::fs;
+    use std::process::Command;
+
+    fn emit_play(play: &str) -> String {
+        let (items, db) = lower(play);
+        emit(&Program::new(&items, &db))
+    }
+
+    #[test]
+    fn emit_small_play() {
+        expect![[r#"
+/* Generated by puck. */
+
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+int main(void) {
+    int64_t romeo = 0;
+    int64_t lady_macbeth = 0;
+    (void)romeo;
+
+    /* Enter Romeo and Lady Macbeth */
+
+    /* Romeo, to Lady Macbeth */
+    lady_macbeth = 1 * (4 + -1);
+    printf("%" PRId64 "\n", lady_macbeth);
+
+    return 0;
+}
+"#]]
+        .assert_eq(&emit_play(
+            "Title.
+Romeo, a man. Lady Macbeth, a woman.
+[Enter Romeo and Lady Macbeth]
+Romeo: You are the product of a cat and the sum of a big big cat and a pig. Open your heart.
+",
+        ));
+    }
+
+    #[test]
+    fn emit_control_flow() {
+        expect![[r#"
+/* Generated by puck. */
+
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+static void fail(const char *message) {
+    fprintf(stderr, "error: %s\n", message);
+    exit(1);
+}
+
+static int asked(int answer) {
+    if (answer < 0) {
+        fail("nothing has been asked yet");
+    }
+    return answer;
+}
+
+struct stack {
+    int64_t *values;
+    size_t length, capacity;
+};
+
+static void push(struct stack *stack, int64_t value) {
+    if (stack->length == stack->capacity) {
+        size_t capacity = stack->capacity == 0 ? 16 : stack->capacity * 2;
+        int64_t *values = realloc(stack->values, capacity * sizeof *values);
+        if (values == NULL) {
+            fail("out of memory");
+        }
+        stack->values = values;
+        stack->capacity = capacity;
+    }
+    stack->values[stack->length++] = value;
+}
+
+static int64_t pop(struct stack *stack, const char *message) {
+    if (stack->length == 0) {
+        fail(message);
+    }
+    return stack->values[--stack->length];
+}
+
+int main(void) {
+    int64_t romeo = 0;
+    int64_t juliet = 0;
+    struct stack juliet_stack = {NULL, 0, 0};
+    int answer = -1;
+    (void)answer;
+    (void)romeo;
+    (void)juliet;
+
+    /* Act I */
+
+    /* Scene I */
+
+    /* Enter Romeo and Juliet */
+
+    /* Scene II */
+scene_2:
+
+    /* Romeo, to Juliet */
+    push(&juliet_stack, romeo);
+    juliet = pop(&juliet_stack, "Juliet has nothing to recall");
+    answer = juliet <= 0;
+    if (!asked(answer)) {
+        goto scene_2;
+    }
+
+    return 0;
+}
+"#]]
+        .assert_eq(&emit_play(
+            "Control flow.
+Romeo, a man. Juliet, a woman.
+Act I: A loop. Scene I: The stage. [Enter Romeo and Juliet]
+Scene II: The loop.
+Romeo: Remember me. Recall your past. Are you not better than nothing?
+If not, let us return to scene II.
+",
+        ));
+    }
+
+    #[test]
+    fn compiled_output_matches_evaluator() {
+        if !installed("cc") {
+            return;
+        }
+
+        check_conformance("c", |dir, name, play| {
+            let source = dir.join(format!("{}.c", name));
+            let binary = dir.join(name);
+            fs::write(&source, emit_play(play)).unwrap();
+
+            let status = Command::new("cc")
+                .args(["-std=c99", "-Wall", "-Werror", "-o"])
+                .arg(&binary)
+                .arg(&source)
+                .status()
+                .unwrap();
+            assert!(status.success(), "{} did not compile", name);
+
+            Command::new(binary)
+        });
+    }
+}
@@ -0,0 +1,451 @@
+use super::{hir, infix, Infix, Program, Step, OPERATIONS};
+use std::fmt::Write;
+
+/// Writes an ES module exporting a `run` function, which takes the play’s
+/// input as a string and gives back everything it says. Values are `BigInt`s,
+/// so that they behave like the evaluator’s 64-bit integers.
+pub(crate) fn emit(program: &Program) -> String {
+    let mut s = String::new();
+
+    s.push_str("// Generated by puck.\n\n");
+    s.push_str("/**\n");
+    s.push_str(" * Runs the play on `input`, giving back everything it says.\n");
+    s.push_str(" * @param {string} input\n");
+    s.push_str(" * @returns {string}\n");
+    s.push_str(" */\n");
+    s.push_str("export function run(input = \"\") {\n");
+    s.push_str("  const reader = { input, position: 0 };\n");
+    s.push_str("  let output = \"\";\n");
+    for variable in program.variables() {
+        writeln!(s, "  let {} = 0n;", camel_case(variable)).unwrap();
+    }
+
+    for character in program.stacks() {
+        writeln!(
+            s,
+            "  const {}Stack = [];",
+            camel_case(program.variable(character))
+        )
+        .unwrap();
+    }
+    // Nothing has been asked until the first question.
+    if program.asks() {
+        s.push_str("  let answer = null;\n");
+    }
+
+    // The scenes that gotos lead to become cases of a `switch` on the scene
+    // being played, each falling through to the next.
+    let blocks = program.blocks();
+    if blocks.len() == 1 {
+        steps(&mut s, &program.steps, program, "  ");
+        s.push_str("\n  return output;\n}\n");
+    } else {
+        s.push_str("\n  let scene = 0;\n  while (true) {\n    switch (scene) {\n");
+        for (scene, block) in &blocks {
+            writeln!(s, "      case {}:", scene).unwrap();
+            let mut case = String::new();
+            steps(&mut case, block, program, "        ");
+            s.push_str(case.trim_start_matches('\n'));
+        }
+        s.push_str("\n        return output;\n    }\n  }\n}\n");
+    }
+
+    if program.uses(|step| matches!(step, Step::CharOutput(_))) {
+        s.push_str(PUT_CHAR);
+    }
+    if program.uses(|step| matches!(step, Step::IntInput(_))) {
+        s.push_str(GET_INT);
+    }
+    if program.uses(|step| matches!(step, Step::CharInput(_))) {
+        s.push_str(GET_CHAR);
+    }
+    if program.uses(|step| matches!(step, Step::Conditional { .. })) {
+        s.push_str(ASKED);
+    }
+    if program.stacks().next().is_some() {
+        s.push_str(RECALL);
+    }
+    if program.checks_any() {
+        s.push_str(CHECKED);
+    }
+    for operation in OPERATIONS {
+        if program.checks(operation) {
+            s.push_str(checked(operation));
+        }
+    }
+
+    s
+}
+
+fn steps(s: &mut String, steps: &[Step], program: &Program, indent: &str) {
+    for step in steps {
+        match step {
+            Step::Comment(comment) => writeln!(s, "\n{}// {}", indent, comment).unwrap(),
+            Step::Scene(_) => {}
+            _ => {
+                s.push_str(indent);
+                statement(s, step, program, indent);
+                s.push('\n');
+            }
+        }
+    }
+}
+
+/// Writes a step that does something, indenting any lines after the first.
+fn statement(s: &mut String, step: &Step, program: &Program, indent: &str) {
+    let variable = |character| camel_case(program.variable(character));
+
+    match step {
+        Step::Assign { character, expr } => {
+            write!(s, "{} = ", variable(*character)).unwrap();
+            infix(s, expr, program, &SYNTAX);
+            s.push(';');
+        }
+        Step::IntOutput(character) => {
+            write!(s, "output += `${{{}}}\\n`;", variable(*character)).unwrap();
+        }
+        Step::CharOutput(character) => {
+            write!(s, "output += putChar({});", variable(*character)).unwrap();
+        }
+        Step::IntInput(character) => {
+            write!(s, "{} = getInt(reader);", variable(*character)).unwrap();
+        }
+        Step::CharInput(character) => {
+            write!(s, "{} = getChar(reader);", variable(*character)).unwrap();
+        }
+        Step::Goto(scene) => write!(s, "scene = {};\n{}continue;", scene, indent).unwrap(),
+        Step::Question {
+            lhs,
+            comparison,
+            rhs,
+        } => {
+            let operator = match comparison.operator() {
+                "==" => "===",
+                "!=" => "!==",
+                operator => operator,
+            };
+            s.push_str("answer = ");
+            infix(s, lhs, program, &SYNTAX);
+            write!(s, " {} ", operator).unwrap();
+            infix(s, rhs, program, &SYNTAX);
+            s.push(';');
+        }
+        Step::Conditional { answer, step } => {
+            let not = if *answer { "" } else { "!" };
+            let inner = format!("{}  ", indent);
+            write!(s, "if ({}asked(answer)) {{\n{}", not, inner).unwrap();
+            statement(s, step, program, &inner);
+            write!(s, "\n{}}}", indent).unwrap();
+        }
+        Step::Push { character, expr } => {
+            write!(s, "{}Stack.push(", variable(*character)).unwrap();
+            infix(s, expr, program, &SYNTAX);
+            s.push_str(");");
+        }
+        Step::Pop(character) => write!(
+            s,
+            "{} = recall({}Stack, \"{} has nothing to recall\");",
+            variable(*character),
+            variable(*character),
+            program.name(*character)
+        )
+        .unwrap(),
+        Step::Comment(_) | Step::Scene(_) => unreachable!(),
+    }
+}
+
+const SYNTAX: Infix = Infix {
+    suffix: "n",
+    call_suffix: "",
+    name: camel_case,
+};
+
+/// The function doing an operation on values only known as the program runs,
+/// with the evaluator’s messages for when it can’t be done.
+fn checked(operation: hir::Operation) -> &'static str {
+    match operation {
+        hir::Operation::Sum => ADD,
+        hir::Operation::Difference => SUBTRACT,
+        hir::Operation::Product => MULTIPLY,
+        hir::Operation::Quotient => DIVIDE,
+        hir::Operation::Remainder => REMAINDER,
+    }
+}
+
+const ASKED: &str = r#"
+function asked(answer) {
+  if (answer === null) {
+    throw new Error("nothing has been asked yet");
+  }
+  return answer;
+}
+"#;
+
+const RECALL: &str = r#"
+function recall(stack, message) {
+  if (stack.length === 0) {
+    throw new Error(message);
+  }
+  return stack.pop();
+}
+"#;
+
+/// `BigInt`s never overflow, so results that wouldn’t fit in 64 bits are
+/// caught here instead.
+const CHECKED: &str = r#"
+function checked(value) {
+  if (value !== BigInt.asIntN(64, value)) {
+    throw new Error("value is too large");
+  }
+  return value;
+}
+"#;
+
+const ADD: &str = r#"
+function checkedAdd(lhs, rhs) {
+  return checked(lhs + rhs);
+}
+"#;
+
+const SUBTRACT: &str = r#"
+function checkedSubtract(lhs, rhs) {
+  return checked(lhs - rhs);
+}
+"#;
+
+const MULTIPLY: &str = r#"
+function checkedMultiply(lhs, rhs) {
+  return checked(lhs * rhs);
+}
+"#;
+
+const DIVIDE: &str = r#"
+function checkedDivide(lhs, rhs) {
+  if (rhs === 0n) {
+    throw new Error("division by zero");
+  }
+  return checked(lhs / rhs);
+}
+"#;
+
+const REMAINDER: &str = r#"
+function checkedRemainder(lhs, rhs) {
+  if (rhs === 0n) {
+    throw new Error("division by zero");
+  }
+  // Like dividing, this fails for the smallest value and -1.
+  checked(lhs / rhs);
+  return lhs % rhs;
+}
+"#;
+
+/// JavaScript names its variables like `ladyMacbeth`.
+fn camel_case(variable: &str) -> String {
+    let mut words = variable.split('_');
+    let mut name = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        name.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        name.extend(chars);
+    }
+
+    name
+}
+
+const PUT_CHAR: &str = r#"
+function putChar(value) {
+  if (value < 0n || value > 0x10ffffn || (value >= 0xd800n && value <= 0xdfffn)) {
+    throw new Error(`${value} is not a character`);
+  }
+  return String.fromCodePoint(Number(value));
+}
+"#;
+
+const GET_INT: &str = r#"
+function getInt(reader) {
+  if (reader.position >= reader.input.length) {
+    throw new Error("expected a number as input but the input ended");
+  }
+
+  const newline = reader.input.indexOf("\n", reader.position);
+  const end = newline === -1 ? reader.input.length : newline + 1;
+  const line = reader.input.slice(reader.position, end).trim();
+  reader.position = end;
+
+  const value = /^[+-]?[0-9]+$/.test(line) ? BigInt(line) : null;
+  if (value === null || value !== BigInt.asIntN(64, value)) {
+    throw new Error(`expected a number as input but found ${JSON.stringify(line)}`);
+  }
+  return value;
+}
+"#;
+
+const GET_CHAR: &str = r#"
+function getChar(reader) {
+  const c = reader.input.codePointAt(reader.position);
+  if (c === undefined) {
+    return -1n;
+  }
+
+  reader.position += c > 0xffff ? 2 : 1;
+  return BigInt(c >= 0xd800 && c <= 0xdfff ? 0xfffd : c);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::{check_conformance, installed, lower};
+    use expect_test::expect;
+    use std::fs;
+    use std::process::Command;
+
+    fn emit_play(play: &str) -> String {
+        let (items, db) = lower(play);
+        emit(&Program::new(&items, &db))
+    }
+
+    #[test]
+    fn emit_small_play() {
+        expect![[r#"
+// Generated by puck.
+
+/**
+ * Runs the play on `input`, giving back everything it says.
+ * @param {string} input
+ * @returns {string}
+ */
+export function run(input = "") {
+  const reader = { input, position: 0 };
+  let output = "";
+  let theGhost = 0n;
+  let ladyMacbeth = 0n;
+
+  // Enter The Ghost and Lady Macbeth
+
+  // Lady Macbeth, to The Ghost
+  theGhost = getChar(reader);
+  theGhost = 0n - -2n;
+  output += `${theGhost}\n`;
+
+  return output;
+}
+
+function getChar(reader) {
+  const c = reader.input.codePointAt(reader.position);
+  if (c === undefined) {
+    return -1n;
+  }
+
+  reader.position += c > 0xffff ? 2 : 1;
+  return BigInt(c >= 0xd800 && c <= 0xdfff ? 0xfffd : c);
+}
+"#]]
+        .assert_eq(&emit_play(
+            "Title.
+The Ghost, a spirit. Lady Macbeth, a woman.
+[Enter The Ghost and Lady Macbeth]
+Lady Macbeth: Open your mind. You are the difference between nothing and a big pig.
+Open your heart.
+",
+        ));
+    }
+
+    #[test]
+    fn emit_control_flow() {
+        expect![[r#"
+// Generated by puck.
+
+/**
+ * Runs the play on `input`, giving back everything it says.
+ * @param {string} input
+ * @returns {string}
+ */
+export function run(input = "") {
+  const reader = { input, position: 0 };
+  let output = "";
+  let romeo = 0n;
+  let juliet = 0n;
+  const julietStack = [];
+  let answer = null;
+
+  let scene = 0;
+  while (true) {
+    switch (scene) {
+      case 0:
+        // Act I
+
+        // Scene I
+
+        // Enter Romeo and Juliet
+      case 2:
+        // Scene II
+
+        // Romeo, to Juliet
+        julietStack.push(romeo);
+        juliet = recall(julietStack, "Juliet has nothing to recall");
+        answer = juliet <= 0n;
+        if (!asked(answer)) {
+          scene = 2;
+          continue;
+        }
+
+        return output;
+    }
+  }
+}
+
+function asked(answer) {
+  if (answer === null) {
+    throw new Error("nothing has been asked yet");
+  }
+  return answer;
+}
+
+function recall(stack, message) {
+  if (stack.length === 0) {
+    throw new Error(message);
+  }
+  return stack.pop();
+}
+"#]]
+        .assert_eq(&emit_play(
+            "Control flow.
+Romeo, a man. Juliet, a woman.
+Act I: A loop. Scene I: The stage. [Enter Romeo and Juliet]
+Scene II: The loop.
+Romeo: Remember me. Recall your past. Are you not better than nothing?
+If not, let us return to scene II.
+",
+        ));
+    }
+
+    #[test]
+    fn output_matches_evaluator() {
+        if !installed("node") {
+            return;
+        }
+
+        check_conformance("js", |dir, name, play| {
+            let module = dir.join(format!("{}.mjs", name));
+            let runner = dir.join(format!("{}-runner.mjs", name));
+            fs::write(&module, emit_play(play)).unwrap();
+            fs::write(
+                &runner,
+                format!(
+                    "import {{ readFileSync }} from \"fs\";
+import {{ run }} from \"./{}.mjs\";
+
+process.stdout.write(run(readFileSync(0, \"utf8\")));
+",
+                    name
+                ),
+            )
+            .unwrap();
+
+            let mut command = Command::new("node");
+            command.arg(runner);
+            command
+        });
+    }
+}
@@ -0,0 +1,453 @@
+use super::{hir, infix, Infix, Program, Step, OPERATIONS};
+use std::fmt::Write;
+
+/// Writes a Rust module with a `run` function that behaves like the play,
+/// reading from and writing to whatever it is given. Errors are reported as
+/// `io::Error`s, with the same messages as the evaluator’s.
+pub(crate) fn emit(program: &Program) -> String {
+    let mut s = String::new();
+
+    s.push_str("// Generated by puck.\n\n");
+    s.push_str("use std::io::{self, BufRead, Write};\n\n");
+    s.push_str("/// Runs the play, reading from `input` and writing what it says to `output`.\n");
+    // Characters who only ever speak, or whose values are never read, would
+    // otherwise be warned about, as would steps after a goto.
+    s.push_str("#[allow(unreachable_code, unused_assignments, unused_mut, unused_variables)]\n");
+    s.push_str(
+        "pub fn run<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<()> {\n",
+    );
+    for variable in program.variables() {
+        writeln!(s, "    let mut {}: i64 = 0;", variable).unwrap();
+    }
+    for character in program.stacks() {
+        writeln!(
+            s,
+            "    let mut {}_stack: Vec<i64> = Vec::new();",
+            program.variable(character)
+        )
+        .unwrap();
+    }
+    // Nothing has been asked until the first question.
+    if program.asks() {
+        s.push_str("    let mut answer: Option<bool> = None;\n");
+    }
+
+    // Without goto, the scenes that gotos lead to become arms of a `match`
+    // on the scene being played, which falls through to the next arm.
+    let blocks = program.blocks();
+    if blocks.len() == 1 {
+        steps(&mut s, &program.steps, program, "    ");
+    } else {
+        s.push_str("\n    let mut scene = 0;\n    loop {\n        match scene {\n");
+        for (idx, (scene, block)) in blocks.iter().enumerate() {
+            writeln!(s, "            {} => {{", scene).unwrap();
+            let mut arm = String::new();
+            steps(&mut arm, block, program, "                ");
+            s.push_str(arm.trim_start_matches('\n'));
+            match blocks.get(idx + 1) {
+                Some((next, _)) => writeln!(s, "                scene = {};", next).unwrap(),
+                None => s.push_str("                break;\n"),
+            }
+            s.push_str("            }\n");
+        }
+        s.push_str("            _ => unreachable!(),\n        }\n    }\n");
+    }
+
+    s.push_str("\n    Ok(())\n}\n");
+
+    if program.uses(|step| matches!(step, Step::CharOutput(_))) {
+        s.push_str(PUT_CHAR);
+    }
+    if program.uses(|step| matches!(step, Step::IntInput(_))) {
+        s.push_str(GET_INT);
+    }
+    if program.uses(|step| matches!(step, Step::CharInput(_))) {
+        s.push_str(GET_CHAR);
+    }
+    if program.uses(|step| matches!(step, Step::Conditional { .. })) {
+        s.push_str(ASKED);
+    }
+    if program.stacks().next().is_some() {
+        s.push_str(RECALL);
+    }
+    if program.checks_any() {
+        s.push_str(TOO_LARGE);
+    }
+    for operation in OPERATIONS {
+        if program.checks(operation) {
+            s.push_str(checked(operation));
+        }
+    }
+
+    s
+}
+
+fn steps(s: &mut String, steps: &[Step], program: &Program, indent: &str) {
+    for step in steps {
+        match step {
+            Step::Comment(comment) => writeln!(s, "\n{}// {}", indent, comment).unwrap(),
+            Step::Scene(_) => {}
+            _ => {
+                s.push_str(indent);
+                statement(s, step, program, indent);
+                s.push('\n');
+            }
+        }
+    }
+}
+
+/// Writes a step that does something, indenting any lines after the first.
+fn statement(s: &mut String, step: &Step, program: &Program, indent: &str) {
+    match step {
+        Step::Assign { character, expr } => {
+            write!(s, "{} = ", program.variable(*character)).unwrap();
+            infix(s, expr, program, &SYNTAX);
+            s.push(';');
+        }
+        Step::IntOutput(character) => write!(
+            s,
+            "writeln!(output, \"{{}}\", {})?;",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::CharOutput(character) => write!(
+            s,
+            "put_char(&mut output, {})?;",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::IntInput(character) => write!(
+            s,
+            "{} = get_int(&mut input)?;",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::CharInput(character) => write!(
+            s,
+            "{} = get_char(&mut input)?;",
+            program.variable(*character)
+        )
+        .unwrap(),
+        Step::Goto(scene) => write!(s, "scene = {};\n{}continue;", scene, indent).unwrap(),
+        Step::Question {
+            lhs,
+            comparison,
+            rhs,
+        } => {
+            s.push_str("answer = Some(");
+            infix(s, lhs, program, &SYNTAX);
+            write!(s, " {} ", comparison.operator()).unwrap();
+            infix(s, rhs, program, &SYNTAX);
+            s.push_str(");");
+        }
+        Step::Conditional { answer, step } => {
+            let not = if *answer { "" } else { "!" };
+            let inner = format!("{}    ", indent);
+            write!(s, "if {}asked(answer)? {{\n{}", not, inner).unwrap();
+            statement(s, step, program, &inner);
+            write!(s, "\n{}}}", indent).unwrap();
+        }
+        Step::Push { character, expr } => {
+            write!(s, "{}_stack.push(", program.variable(*character)).unwrap();
+            infix(s, expr, program, &SYNTAX);
+            s.push_str(");");
+        }
+        Step::Pop(character) => {
+            let variable = program.variable(*character);
+            write!(
+                s,
+                "{} = recall(&mut {}_stack, \"{} has nothing to recall\")?;",
+                variable,
+                variable,
+                program.name(*character)
+            )
+            .unwrap();
+        }
+        Step::Comment(_) | Step::Scene(_) => unreachable!(),
+    }
+}
+
+const SYNTAX: Infix = Infix {
+    suffix: "",
+    call_suffix: "?",
+    name: str::to_string,
+};
+
+/// The function doing an operation on values only known as the program runs,
+/// with the evaluator’s messages for when it can’t be done.
+fn checked(operation: hir::Operation) -> &'static str {
+    match operation {
+        hir::Operation::Sum => ADD,
+        hir::Operation::Difference => SUBTRACT,
+        hir::Operation::Product => MULTIPLY,
+        hir::Operation::Quotient => DIVIDE,
+        hir::Operation::Remainder => REMAINDER,
+    }
+}
+
+const ASKED: &str = r#"
+fn asked(answer: Option<bool>) -> io::Result<bool> {
+    answer.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "nothing has been asked yet"))
+}
+"#;
+
+const RECALL: &str = r#"
+fn recall(stack: &mut Vec<i64>, message: &str) -> io::Result<i64> {
+    stack
+        .pop()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, message))
+}
+"#;
+
+const TOO_LARGE: &str = r#"
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "value is too large")
+}
+"#;
+
+const ADD: &str = r#"
+fn checked_add(lhs: i64, rhs: i64) -> io::Result<i64> {
+    lhs.checked_add(rhs).ok_or_else(too_large)
+}
+"#;
+
+const SUBTRACT: &str = r#"
+fn checked_subtract(lhs: i64, rhs: i64) -> io::Result<i64> {
+    lhs.checked_sub(rhs).ok_or_else(too_large)
+}
+"#;
+
+const MULTIPLY: &str = r#"
+fn checked_multiply(lhs: i64, rhs: i64) -> io::Result<i64> {
+    lhs.checked_mul(rhs).ok_or_else(too_large)
+}
+"#;
+
+const DIVIDE: &str = r#"
+fn checked_divide(lhs: i64, rhs: i64) -> io::Result<i64> {
+    if rhs == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "division by zero"));
+    }
+    lhs.checked_div(rhs).ok_or_else(too_large)
+}
+"#;
+
+const REMAINDER: &str = r#"
+fn checked_remainder(lhs: i64, rhs: i64) -> io::Result<i64> {
+    if rhs == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "division by zero"));
+    }
+    lhs.checked_rem(rhs).ok_or_else(too_large)
+}
+"#;
+
+const PUT_CHAR: &str = r#"
+fn put_char(output: &mut impl Write, value: i64) -> io::Result<()> {
+    let c = Some(value)
+        .filter(|value| (0..=0x10FFFF).contains(value))
+        .and_then(|value| char::from_u32(value as u32))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a character", value),
+            )
+        })?;
+
+    write!(output, "{}", c)
+}
+"#;
+
+const GET_INT: &str = r#"
+fn get_int(input: &mut impl BufRead) -> io::Result<i64> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "expected a number as input but the input ended",
+        ));
+    }
+
+    let line = line.trim();
+    line.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("expected a number as input but found {:?}", line),
+        )
+    })
+}
+"#;
+
+const GET_CHAR: &str = r#"
+fn get_char(input: &mut impl BufRead) -> io::Result<i64> {
+    let first = match input.fill_buf()?.first() {
+        Some(first) => *first,
+        None => return Ok(-1),
+    };
+
+    let len = match first.leading_ones() {
+        len @ 2..=4 => len as usize,
+        _ => 1,
+    };
+
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes[..len])?;
+
+    let c = std::str::from_utf8(&bytes[..len])
+        .ok()
+        .and_then(|s| s.chars().next())
+        .unwrap_or(char::REPLACEMENT_CHARACTER);
+
+    Ok(i64::from(u32::from(c)))
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::{check_conformance, installed, lower};
+    use expect_test::expect;
+    use std::fs;
+    use std::process::Command;
+
+    fn emit_play(play: &str) -> String {
+        let (items, db) = lower(play);
+        emit(&Program::new(&items, &db))
+    }
+
+    #[test]
+    fn emit_small_play() {
+        expect![[r#"
+// Generated by puck.
+
+use std::io::{self, BufRead, Write};
+
+/// Runs the play, reading from `input` and writing what it says to `output`.
+#[allow(unreachable_code, unused_assignments, unused_mut, unused_variables)]
+pub fn run<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<()> {
+    let mut romeo: i64 = 0;
+    let mut juliet: i64 = 0;
+
+    // Enter Romeo and Juliet
+
+    // Romeo, to Juliet
+    juliet = 2 - 0;
+    writeln!(output, "{}", juliet)?;
+
+    Ok(())
+}
+"#]]
+        .assert_eq(&emit_play(
+            "Title.
+Romeo, a man. Juliet, a woman.
+[Enter Romeo and Juliet]
+Romeo: You are the difference between a big cat and nothing. Open your heart.
+",
+        ));
+    }
+
+    #[test]
+    fn emit_control_flow() {
+        expect![[r#"
+// Generated by puck.
+
+use std::io::{self, BufRead, Write};
+
+/// Runs the play, reading from `input` and writing what it says to `output`.
+#[allow(unreachable_code, unused_assignments, unused_mut, unused_variables)]
+pub fn run<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<()> {
+    let mut romeo: i64 = 0;
+    let mut juliet: i64 = 0;
+    let mut juliet_stack: Vec<i64> = Vec::new();
+    let mut answer: Option<bool> = None;
+
+    let mut scene = 0;
+    loop {
+        match scene {
+            0 => {
+                // Act I
+
+                // Scene I
+
+                // Enter Romeo and Juliet
+                scene = 2;
+            }
+            2 => {
+                // Scene II
+
+                // Romeo, to Juliet
+                juliet_stack.push(romeo);
+                juliet = recall(&mut juliet_stack, "Juliet has nothing to recall")?;
+                answer = Some(juliet <= 0);
+                if !asked(answer)? {
+                    scene = 2;
+                    continue;
+                }
+                break;
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(())
+}
+
+fn asked(answer: Option<bool>) -> io::Result<bool> {
+    answer.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "nothing has been asked yet"))
+}
+
+fn recall(stack: &mut Vec<i64>, message: &str) -> io::Result<i64> {
+    stack
+        .pop()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, message))
+}
+"#]]
+        .assert_eq(&emit_play(
+            "Control flow.
+Romeo, a man. Juliet, a woman.
+Act I: A loop. Scene I: The stage. [Enter Romeo and Juliet]
+Scene II: The loop.
+Romeo: Remember me. Recall your past. Are you not better than nothing?
+If not, let us return to scene II.
+",
+        ));
+    }
+
+    #[test]
+    fn compiled_output_matches_evaluator() {
+        if !installed("rustc") {
+            return;
+        }
+
+        check_conformance("rust", |dir, name, play| {
+            let crate_dir = dir.join(name);
+            fs::create_dir_all(&crate_dir).unwrap();
+            fs::write(crate_dir.join("play.rs"), emit_play(play)).unwrap();
+            fs::write(
+                crate_dir.join("main.rs"),
+                "mod play;
+
+fn main() {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    if let Err(error) = play::run(stdin.lock(), stdout.lock()) {
+        eprintln!(\"error: {}\", error);
+        std::process::exit(1);
+    }
+}
+",
+            )
+            .unwrap();
+
+            let binary = crate_dir.join("play");
+            let status = Command::new("rustc")
+                .args(["--edition", "2018", "-D", "warnings", "-o"])
+                .arg(&binary)
+                .arg(crate_dir.join("main.rs"))
+                .status()
+                .unwrap();
+            assert!(status.success(), "{} did not compile", name);
+
+            Command::new(binary)
+        });
+    }
+}
@@ -0,0 +1,587 @@
+use super::{checked_function, Comparison, Expr, Program, Step, OPERATIONS};
+use crate::hir;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Writes a WebAssembly text module that exports a `run` function. Character
+/// values live in globals, and input and output go through functions
+/// imported from the `puck` module, which only take and give `i64`s:
+/// `put_int`, `put_char`, `get_int` and `get_char`. Stacks live in linear
+/// memory.
+pub(crate) fn emit(program: &Program) -> String {
+    let mut s = String::new();
+
+    s.push_str(";; Generated by puck.\n(module\n");
+
+    let imports = [
+        (
+            "put_int",
+            "(param i64)",
+            program.uses(|step| matches!(step, Step::IntOutput(_))),
+        ),
+        (
+            "put_char",
+            "(param i64)",
+            program.uses(|step| matches!(step, Step::CharOutput(_))),
+        ),
+        (
+            "get_int",
+            "(result i64)",
+            program.uses(|step| matches!(step, Step::IntInput(_))),
+        ),
+        (
+            "get_char",
+            "(result i64)",
+            program.uses(|step| matches!(step, Step::CharInput(_))),
+        ),
+    ];
+    for (name, signature, used) in &imports {
+        if *used {
+            writeln!(
+                s,
+                "  (import \"puck\" \"{0}\" (func ${0} {1}))",
+                name, signature
+            )
+            .unwrap();
+        }
+    }
+
+    for variable in program.variables() {
+        writeln!(s, "  (global ${} (mut i64) (i64.const 0))", variable).unwrap();
+    }
+    // Nothing has been asked until the first question.
+    if program.asks() {
+        s.push_str("  (global $answer (mut i32) (i32.const -1))\n");
+    }
+
+    // The top of each stack comes first, followed by the values on them.
+    let stacks: Vec<_> = program.stacks().collect();
+    if !stacks.is_empty() {
+        s.push_str("  (memory 1)\n");
+        s.push_str("  (global $free (mut i32) (i32.const 0))\n");
+        writeln!(
+            s,
+            "  (global $heap (mut i32) (i32.const {}))",
+            stacks.len() * 8
+        )
+        .unwrap();
+    }
+
+    if program.uses(|step| matches!(step, Step::Conditional { .. })) {
+        s.push_str(ASKED);
+    }
+    if !stacks.is_empty() {
+        s.push_str(PUSH);
+        s.push_str(POP);
+    }
+
+    for operation in OPERATIONS {
+        if program.checks(operation) {
+            s.push_str(checked(operation));
+        }
+    }
+
+    s.push_str("  (func (export \"run\")");
+    let blocks = program.blocks();
+    let emitter = Emitter {
+        program,
+        blocks: blocks
+            .iter()
+            .enumerate()
+            .map(|(idx, (scene, _))| (*scene, idx))
+            .collect(),
+        stacks,
+    };
+
+    // The scenes that gotos lead to start after the ends of blocks nested
+    // in a loop, which a `br_table` at the top of the loop picks between.
+    // Each block is named after the scene that follows it.
+    if blocks.len() == 1 {
+        emitter.steps(&mut s, &program.steps);
+    } else {
+        s.push_str("\n    (local $scene i32)\n    loop $dispatch");
+        for (scene, _) in blocks.iter().rev() {
+            write!(s, "\n    block $scene_{}", scene).unwrap();
+        }
+        s.push_str("\n    (br_table");
+        for (scene, _) in &blocks {
+            write!(s, " $scene_{}", scene).unwrap();
+        }
+        s.push_str(" (local.get $scene))");
+        for (scene, block) in &blocks {
+            write!(s, "\n    end $scene_{}", scene).unwrap();
+            emitter.steps(&mut s, block);
+        }
+        s.push_str("\n    end $dispatch");
+    }
+    // A comment would swallow brackets that follow it on the same line.
+    s.push_str("\n  )\n)\n");
+
+    s
+}
+
+/// What writing a step needs to know besides the program.
+struct Emitter<'a> {
+    program: &'a Program,
+    /// Where each scene that gotos lead to is among the blocks.
+    blocks: HashMap<usize, usize>,
+    /// The characters with stacks, whose tops are at eight times their
+    /// position here.
+    stacks: Vec<hir::CharacterIdx>,
+}
+
+impl Emitter<'_> {
+    fn steps(&self, s: &mut String, steps: &[Step]) {
+        for step in steps {
+            match step {
+                Step::Comment(comment) => write!(s, "\n\n    ;; {}", comment).unwrap(),
+                Step::Scene(_) => {}
+                _ => {
+                    s.push_str("\n    ");
+                    self.statement(s, step);
+                }
+            }
+        }
+    }
+
+    fn statement(&self, s: &mut String, step: &Step) {
+        let program = self.program;
+
+        match step {
+            Step::Assign { character, expr } => {
+                write!(s, "(global.set ${} ", program.variable(*character)).unwrap();
+                folded(s, expr, program);
+                s.push(')');
+            }
+            Step::IntOutput(character) => write!(
+                s,
+                "(call $put_int (global.get ${}))",
+                program.variable(*character)
+            )
+            .unwrap(),
+            Step::CharOutput(character) => write!(
+                s,
+                "(call $put_char (global.get ${}))",
+                program.variable(*character)
+            )
+            .unwrap(),
+            Step::IntInput(character) => write!(
+                s,
+                "(global.set ${} (call $get_int))",
+                program.variable(*character)
+            )
+            .unwrap(),
+            Step::CharInput(character) => write!(
+                s,
+                "(global.set ${} (call $get_char))",
+                program.variable(*character)
+            )
+            .unwrap(),
+            Step::Goto(scene) => write!(
+                s,
+                "(local.set $scene (i32.const {})) (br $dispatch)",
+                self.blocks[scene]
+            )
+            .unwrap(),
+            Step::Question {
+                lhs,
+                comparison,
+                rhs,
+            } => {
+                let instruction = match comparison {
+                    Comparison::Greater => "i64.gt_s",
+                    Comparison::GreaterOrEqual => "i64.ge_s",
+                    Comparison::Less => "i64.lt_s",
+                    Comparison::LessOrEqual => "i64.le_s",
+                    Comparison::Equal => "i64.eq",
+                    Comparison::NotEqual => "i64.ne",
+                };
+                write!(s, "(global.set $answer ({} ", instruction).unwrap();
+                folded(s, lhs, program);
+                s.push(' ');
+                folded(s, rhs, program);
+                s.push_str("))");
+            }
+            Step::Conditional { answer, step } => {
+                if *answer {
+                    s.push_str("(if (call $asked) (then ");
+                } else {
+                    s.push_str("(if (i32.eqz (call $asked)) (then ");
+                }
+                self.statement(s, step);
+                s.push_str("))");
+            }
+            Step::Push { character, expr } => {
+                write!(s, "(call $push (i32.const {}) ", self.stack(*character)).unwrap();
+                folded(s, expr, program);
+                s.push(')');
+            }
+            Step::Pop(character) => write!(
+                s,
+                "(global.set ${} (call $pop (i32.const {})))",
+                program.variable(*character),
+                self.stack(*character)
+            )
+            .unwrap(),
+            Step::Comment(_) | Step::Scene(_) => unreachable!(),
+        }
+    }
+
+    /// The address of the top of a character’s stack.
+    fn stack(&self, character: hir::CharacterIdx) -> usize {
+        let position = self.stacks.iter().position(|c| *c == character).unwrap();
+        position * 8
+    }
+}
+
+/// Gives back the answer to the last question, trapping if nothing has been
+/// asked.
+const ASKED: &str = "  (func $asked (result i32)
+    (if (i32.lt_s (global.get $answer) (i32.const 0))
+      (then unreachable))
+    (global.get $answer))
+";
+
+/// Values on stacks are kept in sixteen-byte nodes, holding the value and
+/// the node below it, with nought standing for the bottom of a stack.
+/// Popped nodes are kept in a list of their own, to be pushed again before
+/// more memory is used.
+const PUSH: &str = "  (func $push (param $stack i32) (param $value i64)
+    (local $node i32)
+    (local.set $node (global.get $free))
+    (if (i32.eqz (local.get $node))
+      (then
+        (local.set $node (global.get $heap))
+        (global.set $heap (i32.add (local.get $node) (i32.const 16)))
+        (if (i32.gt_u (global.get $heap) (i32.shl (memory.size) (i32.const 16)))
+          (then
+            (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1))
+              (then unreachable)))))
+      (else
+        (global.set $free (i32.load offset=8 (local.get $node)))))
+    (i64.store (local.get $node) (local.get $value))
+    (i32.store offset=8 (local.get $node) (i32.load (local.get $stack)))
+    (i32.store (local.get $stack) (local.get $node)))
+";
+
+/// Traps if the stack is empty.
+const POP: &str = "  (func $pop (param $stack i32) (result i64)
+    (local $node i32)
+    (local.set $node (i32.load (local.get $stack)))
+    (if (i32.eqz (local.get $node))
+      (then unreachable))
+    (i32.store (local.get $stack) (i32.load offset=8 (local.get $node)))
+    (i32.store offset=8 (local.get $node) (global.get $free))
+    (global.set $free (local.get $node))
+    (i64.load (local.get $node)))
+";
+
+/// The function doing an operation on values only known as the program runs,
+/// trapping when it can’t be done. Dividing by zero or the smallest value by
+/// -1 already traps, so quotients need no function of their own.
+fn checked(operation: hir::Operation) -> &'static str {
+    match operation {
+        hir::Operation::Sum => ADD,
+        hir::Operation::Difference => SUBTRACT,
+        hir::Operation::Product => MULTIPLY,
+        hir::Operation::Quotient => "",
+        hir::Operation::Remainder => REMAINDER,
+    }
+}
+
+/// The sum overflowed if it has a different sign from both operands.
+const ADD: &str = "  (func $checked_add (param $lhs i64) (param $rhs i64) (result i64)
+    (local $result i64)
+    (local.set $result (i64.add (local.get $lhs) (local.get $rhs)))
+    (if (i64.lt_s
+          (i64.and
+            (i64.xor (local.get $lhs) (local.get $result))
+            (i64.xor (local.get $rhs) (local.get $result)))
+          (i64.const 0))
+      (then unreachable))
+    (local.get $result))
+";
+
+/// The difference overflowed if the operands have different signs and it
+/// doesn’t have the sign of the first.
+const SUBTRACT: &str = "  (func $checked_subtract (param $lhs i64) (param $rhs i64) (result i64)
+    (local $result i64)
+    (local.set $result (i64.sub (local.get $lhs) (local.get $rhs)))
+    (if (i64.lt_s
+          (i64.and
+            (i64.xor (local.get $lhs) (local.get $rhs))
+            (i64.xor (local.get $lhs) (local.get $result)))
+          (i64.const 0))
+      (then unreachable))
+    (local.get $result))
+";
+
+/// The product overflowed if dividing it by one operand doesn’t give back
+/// the other, where the division itself traps for -1 times the smallest value.
+const MULTIPLY: &str = "  (func $checked_multiply (param $lhs i64) (param $rhs i64) (result i64)
+    (local $result i64)
+    (local.set $result (i64.mul (local.get $lhs) (local.get $rhs)))
+    (if (i64.ne (local.get $lhs) (i64.const 0))
+      (then
+        (if (i64.ne (i64.div_s (local.get $result) (local.get $lhs)) (local.get $rhs))
+          (then unreachable))))
+    (local.get $result))
+";
+
+/// The remainder of the smallest value and -1 is 0, but the evaluator
+/// counts it as too large, which dividing first catches.
+const REMAINDER: &str = "  (func $checked_remainder (param $lhs i64) (param $rhs i64) (result i64)
+    (drop (i64.div_s (local.get $lhs) (local.get $rhs)))
+    (i64.rem_s (local.get $lhs) (local.get $rhs)))
+";
+
+/// Writes an expression as nested folded instructions.
+fn folded(s: &mut String, expr: &Expr, program: &Program) {
+    match expr {
+        Expr::Number(number) => write!(s, "(i64.const {})", number).unwrap(),
+        Expr::Variable(character) => {
+            write!(s, "(global.get ${})", program.variable(*character)).unwrap()
+        }
+        Expr::Bin {
+            operation,
+            lhs,
+            rhs,
+        } => {
+            let instruction = match operation {
+                hir::Operation::Quotient => "i64.div_s".to_string(),
+                _ if expr.is_checked() => format!("call ${}", checked_function(*operation)),
+                hir::Operation::Remainder => "i64.rem_s".to_string(),
+                hir::Operation::Difference => "i64.sub".to_string(),
+                hir::Operation::Product => "i64.mul".to_string(),
+                hir::Operation::Sum => "i64.add".to_string(),
+            };
+            write!(s, "({} ", instruction).unwrap();
+            folded(s, lhs, program);
+            s.push(' ');
+            folded(s, rhs, program);
+            s.push(')');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::{check_conformance, installed, lower, CONFORMANCE};
+    use expect_test::{expect, Expect};
+    use std::fs;
+    use std::process::Command;
+
+    fn emit_play(play: &str) -> String {
+        let (items, db) = lower(play);
+        emit(&Program::new(&items, &db))
+    }
+
+    fn check(play: &str, expected: Expect) {
+        let actual = emit_play(play);
+        wat::parse_str(&actual).unwrap();
+        expected.assert_eq(&actual);
+    }
+
+    /// Runs a compiled module under node, with imports that behave like the
+    /// evaluator on valid input.
+    const RUNNER: &str = r#"import { readFileSync } from "fs";
+
+const input = readFileSync(0, "utf8");
+let position = 0;
+let output = "";
+
+const puck = {
+  put_int: (value) => {
+    output += `${value}\n`;
+  },
+  put_char: (value) => {
+    output += String.fromCodePoint(Number(value));
+  },
+  get_int: () => {
+    const newline = input.indexOf("\n", position);
+    const end = newline === -1 ? input.length : newline + 1;
+    const line = input.slice(position, end).trim();
+    position = end;
+    return BigInt(line);
+  },
+  get_char: () => {
+    const c = input.codePointAt(position);
+    if (c === undefined) {
+      return -1n;
+    }
+    position += c > 0xffff ? 2 : 1;
+    return BigInt(c);
+  },
+};
+
+const { instance } = await WebAssembly.instantiate(readFileSync(process.argv[2]), { puck });
+instance.exports.run();
+process.stdout.write(output);
+"#;
+
+    #[test]
+    fn emit_arithmetic() {
+        check(
+            CONFORMANCE[0].1,
+            expect![[r#"
+;; Generated by puck.
+(module
+  (import "puck" "put_int" (func $put_int (param i64)))
+  (global $romeo (mut i64) (i64.const 0))
+  (global $juliet (mut i64) (i64.const 0))
+  (func (export "run")
+
+    ;; Enter Romeo and Juliet
+
+    ;; Romeo, to Juliet
+    (global.set $juliet (i64.add (i64.const 4) (i64.const -1)))
+    (call $put_int (global.get $juliet))
+    (global.set $juliet (i64.sub (i64.const 0) (i64.mul (i64.const 2) (i64.const 8))))
+    (call $put_int (global.get $juliet))
+    (global.set $juliet (i64.div_s (i64.sub (i64.const 0) (i64.const 8)) (i64.const 2)))
+    (call $put_int (global.get $juliet))
+    (global.set $juliet (i64.rem_s (i64.sub (i64.const 0) (i64.const 8)) (i64.add (i64.const 2) (i64.const 1))))
+    (call $put_int (global.get $juliet))
+
+    ;; Juliet, to Romeo
+    (global.set $romeo (i64.rem_s (i64.const 8) (i64.sub (i64.const 0) (i64.add (i64.const 2) (i64.const 1)))))
+    (call $put_int (global.get $romeo))
+
+    ;; Romeo, to Juliet
+    (global.set $juliet (i64.sub (i64.const -16) (i64.const 4)))
+    (call $put_int (global.get $juliet))
+  )
+)
+"#]],
+        );
+    }
+
+    #[test]
+    fn emit_input_and_output() {
+        check(
+            "Title.
+Lady Macbeth, a schemer. The Ghost, a spirit.
+[Enter Lady Macbeth and The Ghost]
+Lady Macbeth: Listen to your heart. Open your mind. Speak your mind.
+[Exit The Ghost]
+",
+            expect![[r#"
+;; Generated by puck.
+(module
+  (import "puck" "put_char" (func $put_char (param i64)))
+  (import "puck" "get_int" (func $get_int (result i64)))
+  (import "puck" "get_char" (func $get_char (result i64)))
+  (global $lady_macbeth (mut i64) (i64.const 0))
+  (global $the_ghost (mut i64) (i64.const 0))
+  (func (export "run")
+
+    ;; Enter Lady Macbeth and The Ghost
+
+    ;; Lady Macbeth, to The Ghost
+    (global.set $the_ghost (call $get_int))
+    (global.set $the_ghost (call $get_char))
+    (call $put_char (global.get $the_ghost))
+
+    ;; Exit The Ghost
+  )
+)
+"#]],
+        );
+    }
+
+    #[test]
+    fn emit_control_flow() {
+        check(
+            "Control flow.
+Romeo, a man. Juliet, a woman.
+Act I: A loop. Scene I: The stage. [Enter Romeo and Juliet]
+Scene II: The loop.
+Romeo: Remember me. Recall your past. Are you not better than nothing?
+If not, let us return to scene II.
+",
+            expect![[r#"
+;; Generated by puck.
+(module
+  (global $romeo (mut i64) (i64.const 0))
+  (global $juliet (mut i64) (i64.const 0))
+  (global $answer (mut i32) (i32.const -1))
+  (memory 1)
+  (global $free (mut i32) (i32.const 0))
+  (global $heap (mut i32) (i32.const 8))
+  (func $asked (result i32)
+    (if (i32.lt_s (global.get $answer) (i32.const 0))
+      (then unreachable))
+    (global.get $answer))
+  (func $push (param $stack i32) (param $value i64)
+    (local $node i32)
+    (local.set $node (global.get $free))
+    (if (i32.eqz (local.get $node))
+      (then
+        (local.set $node (global.get $heap))
+        (global.set $heap (i32.add (local.get $node) (i32.const 16)))
+        (if (i32.gt_u (global.get $heap) (i32.shl (memory.size) (i32.const 16)))
+          (then
+            (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1))
+              (then unreachable)))))
+      (else
+        (global.set $free (i32.load offset=8 (local.get $node)))))
+    (i64.store (local.get $node) (local.get $value))
+    (i32.store offset=8 (local.get $node) (i32.load (local.get $stack)))
+    (i32.store (local.get $stack) (local.get $node)))
+  (func $pop (param $stack i32) (result i64)
+    (local $node i32)
+    (local.set $node (i32.load (local.get $stack)))
+    (if (i32.eqz (local.get $node))
+      (then unreachable))
+    (i32.store (local.get $stack) (i32.load offset=8 (local.get $node)))
+    (i32.store offset=8 (local.get $node) (global.get $free))
+    (global.set $free (local.get $node))
+    (i64.load (local.get $node)))
+  (func (export "run")
+    (local $scene i32)
+    loop $dispatch
+    block $scene_2
+    block $scene_0
+    (br_table $scene_0 $scene_2 (local.get $scene))
+    end $scene_0
+
+    ;; Act I
+
+    ;; Scene I
+
+    ;; Enter Romeo and Juliet
+    end $scene_2
+
+    ;; Scene II
+
+    ;; Romeo, to Juliet
+    (call $push (i32.const 0) (global.get $romeo))
+    (global.set $juliet (call $pop (i32.const 0)))
+    (global.set $answer (i64.le_s (global.get $juliet) (i64.const 0)))
+    (if (i32.eqz (call $asked)) (then (local.set $scene (i32.const 1)) (br $dispatch)))
+    end $dispatch
+  )
+)
+"#]],
+        );
+    }
+
+    #[test]
+    fn output_matches_evaluator() {
+        if !installed("node") {
+            return;
+        }
+
+        check_conformance("wat", |dir, name, play| {
+            let module = dir.join(format!("{}.wasm", name));
+            let runner = dir.join("runner.mjs");
+            fs::write(&module, wat::parse_str(emit_play(play)).unwrap()).unwrap();
+            fs::write(&runner, RUNNER).unwrap();
+
+            let mut command = Command::new("node");
+            command.arg(runner).arg(module);
+            command
+        });
+    }
+}
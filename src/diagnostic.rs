@@ -0,0 +1,186 @@
+use crate::eval::EvalError;
+use crate::from_bf::BfError;
+use crate::lexer::SyntaxKind;
+use crate::line_index::LineIndex;
+use crate::parser::ParseError;
+use crate::validation::ValidationError;
+use serde_json::json;
+use text_size::TextRange;
+
+/// An error from any stage of running a play, in a form that can be shown
+/// either to people or to other programs.
+#[derive(Debug)]
+pub(crate) struct Diagnostic {
+    pub(crate) stage: Stage,
+    pub(crate) code: &'static str,
+    pub(crate) message: String,
+    /// Only errors that stop a play before it starts running don’t point at
+    /// any particular part of it.
+    pub(crate) range: Option<TextRange>,
+    pub(crate) expected: Vec<SyntaxKind>,
+    pub(crate) found: Option<SyntaxKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Stage {
+    Parse,
+    Validation,
+    Runtime,
+}
+
+impl Diagnostic {
+    pub(crate) fn from_parse_error(error: &ParseError) -> Self {
+        Self {
+            stage: Stage::Parse,
+            code: "unexpected-token",
+            message: error.message(),
+            range: Some(error.range()),
+            expected: error.expected().collect(),
+            found: error.found(),
+        }
+    }
+
+    pub(crate) fn from_validation_error(error: &ValidationError) -> Self {
+        Self {
+            stage: Stage::Validation,
+            code: error.kind.code(),
+            message: error.kind.to_string(),
+            range: Some(error.range),
+            expected: Vec::new(),
+            found: None,
+        }
+    }
+
+    /// Brackets that don’t match are a syntax error, and a program with too
+    /// many loops is one that can’t be written down.
+    pub(crate) fn from_bf_error(error: &BfError) -> Self {
+        Self {
+            stage: Stage::Parse,
+            code: error.kind.code(),
+            message: error.kind.to_string(),
+            range: Some(error.range),
+            expected: Vec::new(),
+            found: None,
+        }
+    }
+
+    pub(crate) fn from_eval_error(error: &EvalError) -> Self {
+        Self {
+            stage: Stage::Runtime,
+            code: error.code(),
+            message: error.to_string(),
+            range: error.range,
+            expected: Vec::new(),
+            found: None,
+        }
+    }
+
+    pub(crate) fn human(&self, path: &str, line_index: &LineIndex) -> String {
+        match self.range {
+            Some(range) => {
+                let position = line_index.line_col(range.start());
+                format!(
+                    "{}:{}:{}: error: {}",
+                    path,
+                    position.line + 1,
+                    position.col + 1,
+                    self.message,
+                )
+            }
+            None => format!("{}: error: {}", path, self.message),
+        }
+    }
+
+    /// Lines and columns start from one, like in the human-readable form, while
+    /// byte offsets start from zero.
+    pub(crate) fn json(&self, path: &str, line_index: &LineIndex) -> serde_json::Value {
+        let span = self.range.map(|range| {
+            let start = line_index.line_col(range.start());
+            let end = line_index.line_col(range.end());
+
+            json!({
+                "byte_start": u32::from(range.start()),
+                "byte_end": u32::from(range.end()),
+                "line_start": start.line + 1,
+                "column_start": start.col + 1,
+                "line_end": end.line + 1,
+                "column_end": end.col + 1,
+            })
+        });
+
+        let kinds = |kinds: &[SyntaxKind]| -> Vec<String> {
+            kinds.iter().map(|kind| format!("{:?}", kind)).collect()
+        };
+
+        json!({
+            "severity": "error",
+            "stage": match self.stage {
+                Stage::Parse => "parse",
+                Stage::Validation => "validation",
+                Stage::Runtime => "runtime",
+            },
+            "code": self.code,
+            "message": self.message,
+            "file": path,
+            "span": span,
+            "expected": kinds(&self.expected),
+            "found": self.found.map(|found| format!("{:?}", found)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::{EvalErrorKind, Evaluator};
+    use crate::parser::parse;
+    use crate::{ast, hir};
+    use expect_test::expect;
+    use std::io;
+
+    #[test]
+    fn parse_error_as_json() {
+        let input = "Romeo, a man.\nRomeo: You are a.";
+        let parse = parse(input, false);
+        let diagnostic = Diagnostic::from_parse_error(&parse.errors[0]);
+
+        expect![[r#"{"code":"unexpected-token","expected":["Difference","Product","Quotient","Sum","Remainder","Cube","Square","NegativeAdjective","NeutralAdjective","PositiveAdjective","NegativeNoun","NeutralNoun","PositiveNoun"],"file":"play.spl","found":null,"message":"expected ‘difference’, ‘product’, ‘quotient’, ‘sum’, ‘remainder’, ‘cube’, ‘square’, adjective, adjective, adjective, noun, noun or noun","severity":"error","span":{"byte_end":30,"byte_start":29,"column_end":17,"column_start":16,"line_end":2,"line_start":2},"stage":"parse"}"#]]
+        .assert_eq(
+            &diagnostic
+                .json("play.spl", &LineIndex::new(input))
+                .to_string(),
+        );
+    }
+
+    #[test]
+    fn runtime_error_as_json() {
+        let input = "Romeo, a man.\nJuliet, a woman.\n[Enter Romeo and Juliet]\nRomeo: Listen to your heart.";
+        let root = ast::Root::cast(parse(input, false).syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+        let error = Evaluator::with_input(io::empty())
+            .eval(&root, &items, &db)
+            .unwrap_err();
+        let diagnostic = Diagnostic::from_eval_error(&error);
+
+        expect![[r#"{"code":"expected-number","expected":[],"file":"play.spl","found":null,"message":"expected a number as input but the input ended","severity":"error","span":{"byte_end":84,"byte_start":63,"column_end":29,"column_start":8,"line_end":4,"line_start":4},"stage":"runtime"}"#]]
+        .assert_eq(
+            &diagnostic
+                .json("play.spl", &LineIndex::new(input))
+                .to_string(),
+        );
+    }
+
+    #[test]
+    fn runtime_error_as_human() {
+        let diagnostic = Diagnostic::from_eval_error(&EvalError {
+            kind: EvalErrorKind::ExpectedNumber(None),
+            range: None,
+        });
+
+        assert_eq!(
+            diagnostic.human("play.spl", &LineIndex::new("")),
+            "play.spl: error: expected a number as input but the input ended"
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use crate::lexer::SyntaxKind;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// Above this, the noun would overflow.
+const MAX_EXPONENT: u32 = 62;
+
+/// The factors tried when looking for a product.
+const SMALL_FACTORS: std::ops::RangeInclusive<i64> = 2..=16;
+
+/// Writes an expression that evaluates to `number`. By default the number is
+/// a sum of powers of two, one for each bit, which is predictable. With
+/// `shortest`, sums and differences of nearby powers of two, products with
+/// small factors, and doubles, squares and cubes are searched for the
+/// expression with the fewest words.
+pub(crate) fn encode(number: i64, shortest: bool) -> String {
+    let term = if shortest {
+        let mut search = Search::default();
+        search.cost(number);
+        search.term(number)
+    } else {
+        binary(number)
+    };
+
+    let mut s = String::new();
+    write_term(&mut s, &term);
+    s
+}
+
+enum Term {
+    Nothing,
+    /// A noun with as many adjectives as the exponent.
+    Power {
+        exponent: u32,
+        negative: bool,
+    },
+    Bin(Operation, Box<Term>, Box<Term>),
+    Unary(UnaryOperation, Box<Term>),
+}
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Sum,
+    Difference,
+    Product,
+}
+
+#[derive(Clone, Copy)]
+enum UnaryOperation {
+    Twice,
+    Square,
+    Cube,
+}
+
+fn binary(number: i64) -> Term {
+    if number == i64::MIN {
+        return Term::Bin(
+            Operation::Sum,
+            Box::new(power(-(1 << MAX_EXPONENT)).unwrap()),
+            Box::new(power(-(1 << MAX_EXPONENT)).unwrap()),
+        );
+    }
+
+    if let Some(power) = power(number) {
+        return power;
+    }
+    if number == 0 {
+        return Term::Nothing;
+    }
+
+    let highest = number.signum() << (63 - number.unsigned_abs().leading_zeros());
+    Term::Bin(
+        Operation::Sum,
+        Box::new(power(highest).unwrap()),
+        Box::new(binary(number - highest)),
+    )
+}
+
+/// A power of two, or its negation, as a single noun.
+fn power(number: i64) -> Option<Term> {
+    let magnitude = number.unsigned_abs();
+    let exponent = magnitude.trailing_zeros();
+    if number == 0 || !magnitude.is_power_of_two() || exponent > MAX_EXPONENT {
+        return None;
+    }
+
+    Some(Term::Power {
+        exponent,
+        negative: number < 0,
+    })
+}
+
+/// Finds the fewest words for every number it comes across, remembering
+/// how each was reached.
+#[derive(Default)]
+struct Search {
+    best: HashMap<i64, (usize, Choice)>,
+}
+
+#[derive(Clone, Copy)]
+enum Choice {
+    Leaf,
+    Bin(Operation, i64, i64),
+    Unary(UnaryOperation, i64),
+}
+
+impl Search {
+    fn cost(&mut self, number: i64) -> usize {
+        if let Some((cost, _)) = self.best.get(&number) {
+            return *cost;
+        }
+
+        let best = match (number, power(number)) {
+            (0, _) => (1, Choice::Leaf),
+            // An article, the adjectives and the noun.
+            (_, Some(_)) => (
+                number.unsigned_abs().trailing_zeros() as usize + 2,
+                Choice::Leaf,
+            ),
+            _ => {
+                let mut candidates = Vec::new();
+
+                let sign = number.signum();
+                let floor = 63 - number.unsigned_abs().leading_zeros();
+                // Only the smallest number lies beyond the biggest noun, and
+                // it is the sum of two of them.
+                let below = sign << floor.min(MAX_EXPONENT);
+                candidates.push(Choice::Bin(Operation::Sum, below, number - below));
+                if floor < MAX_EXPONENT {
+                    let above = sign << (floor + 1);
+                    candidates.push(Choice::Bin(Operation::Difference, above, above - number));
+                }
+                // Every operand is smaller than the number, so the search
+                // always ends.
+                for factor in SMALL_FACTORS {
+                    if number % factor == 0 && (factor as u64) < number.unsigned_abs() {
+                        candidates.push(Choice::Bin(Operation::Product, factor, number / factor));
+                    }
+                }
+                if number % 2 == 0 {
+                    candidates.push(Choice::Unary(UnaryOperation::Twice, number / 2));
+                }
+                if let Some(root) = root(number, 2) {
+                    candidates.push(Choice::Unary(UnaryOperation::Square, root));
+                }
+                if let Some(root) = root(number, 3) {
+                    candidates.push(Choice::Unary(UnaryOperation::Cube, root));
+                }
+
+                candidates
+                    .into_iter()
+                    .map(|choice| match choice {
+                        // ‘The sum of’, ‘and’ and the operands.
+                        Choice::Bin(_, lhs, rhs) => (4 + self.cost(lhs) + self.cost(rhs), choice),
+                        // ‘Twice’, or ‘the square of’ and ‘the cube of’.
+                        Choice::Unary(UnaryOperation::Twice, operand) => {
+                            (1 + self.cost(operand), choice)
+                        }
+                        Choice::Unary(_, operand) => (3 + self.cost(operand), choice),
+                        Choice::Leaf => unreachable!(),
+                    })
+                    .min_by_key(|(cost, _)| *cost)
+                    .unwrap()
+            }
+        };
+
+        self.best.insert(number, best);
+        best.0
+    }
+
+    fn term(&self, number: i64) -> Term {
+        match self.best[&number].1 {
+            Choice::Leaf => power(number).unwrap_or(Term::Nothing),
+            Choice::Bin(operation, lhs, rhs) => Term::Bin(
+                operation,
+                Box::new(self.term(lhs)),
+                Box::new(self.term(rhs)),
+            ),
+            Choice::Unary(operation, operand) => {
+                Term::Unary(operation, Box::new(self.term(operand)))
+            }
+        }
+    }
+}
+
+/// The number whose square or cube is `number`, if there is one that is
+/// smaller than it, so that the search still ends.
+fn root(number: i64, exponent: u32) -> Option<i64> {
+    // Squares are never negative, so their roots are taken as positive.
+    let estimate = (number.unsigned_abs() as f64).powf(1.0 / f64::from(exponent)) as i64;
+    let sign = if exponent % 2 == 1 {
+        number.signum()
+    } else {
+        1
+    };
+    (estimate - 1..=estimate + 1)
+        .map(|root| sign * root)
+        .find(|root| root.abs() > 1 && root.checked_pow(exponent) == Some(number))
+}
+
+fn write_term(s: &mut String, term: &Term) {
+    match term {
+        Term::Nothing => s.push_str(shortest_word(SyntaxKind::Nothing)),
+        Term::Power { exponent, negative } => {
+            s.push_str(shortest_word(SyntaxKind::Article));
+            for _ in 0..*exponent {
+                write!(s, " {}", shortest_word(SyntaxKind::NeutralAdjective)).unwrap();
+            }
+            let noun = if *negative {
+                SyntaxKind::NegativeNoun
+            } else {
+                SyntaxKind::PositiveNoun
+            };
+            write!(s, " {}", shortest_word(noun)).unwrap();
+        }
+        Term::Bin(operation, lhs, rhs) => {
+            s.push_str(match operation {
+                Operation::Sum => "the sum of ",
+                Operation::Difference => "the difference between ",
+                Operation::Product => "the product of ",
+            });
+            write_term(s, lhs);
+            s.push_str(" and ");
+            write_term(s, rhs);
+        }
+        Term::Unary(operation, operand) => {
+            s.push_str(match operation {
+                UnaryOperation::Twice => "twice ",
+                UnaryOperation::Square => "the square of ",
+                UnaryOperation::Cube => "the cube of ",
+            });
+            write_term(s, operand);
+        }
+    }
+}
+
+/// The shortest of the words that lex as a kind, or the first of them if
+/// several are as short.
+fn shortest_word(kind: SyntaxKind) -> &'static str {
+    kind.words()
+        .iter()
+        .copied()
+        .filter(|word| word.chars().all(char::is_alphabetic))
+        .min_by_key(|word| word.len())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::Evaluator;
+    use crate::parser::parse;
+    use crate::{ast, hir};
+    use expect_test::{expect, Expect};
+    use proptest::prelude::*;
+
+    fn check(number: i64, shortest: bool, expected: Expect) {
+        expected.assert_eq(&encode(number, shortest));
+    }
+
+    /// What the evaluator makes of an expression, told to Juliet.
+    fn evaluate(expr: &str) -> i64 {
+        let parse = parse(
+            &format!(
+                "Romeo, a man. Juliet, a woman. [Enter Romeo and Juliet] Romeo: You are {}.",
+                expr
+            ),
+            false,
+        );
+        assert!(parse.errors.is_empty(), "{}", expr);
+        let root = ast::Root::cast(parse.syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+
+        let mut evaluator = Evaluator::default();
+        evaluator.eval(&root, &items, &db).unwrap();
+        let juliet = db.find_character("Juliet").unwrap();
+        let value = evaluator
+            .values()
+            .find(|(character, _)| *character == juliet)
+            .map(|(_, value)| value)
+            .unwrap();
+        value
+    }
+
+    fn words(expr: &str) -> usize {
+        expr.split_whitespace().count()
+    }
+
+    #[test]
+    fn encode_small_numbers() {
+        check(0, false, expect![[r#"zero"#]]);
+        check(1, false, expect![["a joy"]]);
+        check(-4, false, expect![["a big big hog"]]);
+        check(
+            72,
+            false,
+            expect![["the sum of a big big big big big big joy and a big big big joy"]],
+        );
+    }
+
+    #[test]
+    fn encode_shortest() {
+        check(
+            7,
+            true,
+            expect![["the difference between a big big big joy and a joy"]],
+        );
+        check(
+            -243,
+            true,
+            expect![[
+                r#"the product of the sum of a big big big joy and a joy and the cube of the sum of a big hog and a hog"#
+            ]],
+        );
+        check(
+            1_000_000,
+            true,
+            expect![[r#"the square of the cube of twice the sum of a big big joy and a joy"#]],
+        );
+    }
+
+    #[test]
+    fn encode_extremes() {
+        for number in [i64::MIN, i64::MIN + 1, i64::MAX] {
+            for shortest in [false, true] {
+                let expr = encode(number, shortest);
+                assert_eq!(evaluate(&expr), number, "{}", expr);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn evaluates_to_number(number: i64) {
+            prop_assert_eq!(evaluate(&encode(number, false)), number);
+        }
+
+        #[test]
+        fn shortest_is_no_longer(number in -100_000_i64..100_000) {
+            prop_assert!(words(&encode(number, true)) <= words(&encode(number, false)));
+        }
+    }
+
+    proptest! {
+        // Searching across the whole range is slow without optimisations.
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn shortest_evaluates_to_number(number: i64) {
+            prop_assert_eq!(evaluate(&encode(number, true)), number);
+        }
+    }
+}
@@ -1,88 +1,359 @@
-use crate::hir;
+use crate::{ast, hir, roman};
 use arrayvec::ArrayVec;
+use la_arena::ArenaMap;
 use std::collections::HashMap;
-use std::convert::TryInto;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use text_size::TextRange;
 
 #[derive(Debug, Default)]
 pub(crate) struct Evaluator {
-    states: HashMap<String, CharacterState>,
-    on_stage: ArrayVec<String, 2>,
+    states: ArenaMap<hir::CharacterIdx, CharacterState>,
+    on_stage: ArrayVec<hir::CharacterIdx, 2>,
+    /// The answer to the last question, which conditionals go by.
+    answer: Option<bool>,
+    input: Input,
+    output: Output,
+}
+
+/// Where ‘Listen to your heart’ and ‘Open your mind’ read from.
+struct Input(Box<dyn BufRead>);
+
+impl Default for Input {
+    fn default() -> Self {
+        Self(Box::new(io::BufReader::new(io::stdin())))
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Input")
+    }
+}
+
+/// Where ‘Open your heart’ and ‘Speak your mind’ write to.
+struct Output(Box<dyn Write>);
+
+impl Default for Output {
+    fn default() -> Self {
+        Self(Box::new(io::stdout()))
+    }
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Output")
+    }
 }
 
 impl Evaluator {
-    pub(crate) fn eval(&mut self, items: &[hir::Item], db: hir::Database) {
-        for item in items {
-            match item {
-                hir::Item::CharacterDef { character } => {
+    pub(crate) fn with_input(input: impl BufRead + 'static) -> Self {
+        Self {
+            input: Input(Box::new(input)),
+            ..Self::default()
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_output(self, output: impl Write + 'static) -> Self {
+        Self {
+            output: Output(Box::new(output)),
+            ..self
+        }
+    }
+
+    /// Runs the items of a play, which are lowered from the items of `root`
+    /// so that errors can point at the part of the play that went wrong.
+    pub(crate) fn eval(
+        &mut self,
+        root: &ast::Root,
+        items: &[hir::Item],
+        db: &hir::Database,
+    ) -> Result<(), EvalError> {
+        if hir::has_missing(items, db) {
+            return Err(EvalError {
+                kind: EvalErrorKind::Incomplete,
+                range: None,
+            });
+        }
+
+        let asts: Vec<_> = root.items().collect();
+        let scenes = Scenes::new(items);
+
+        let mut idx = 0;
+        while let Some((ast, item)) = asts.get(idx).zip(items.get(idx)) {
+            idx += 1;
+
+            match (ast.clone(), item) {
+                (ast::Item::CharacterDef(_), hir::Item::CharacterDef { character }) => {
                     self.states
-                        .insert(character.clone(), CharacterState::default());
+                        .insert(character.unwrap(), CharacterState::default());
                 }
-                hir::Item::StageDirection {
-                    characters,
-                    direction,
-                } => match direction {
-                    hir::Direction::Exeunt => {
+                (
+                    ast::Item::StageDirection(stage_direction),
+                    hir::Item::StageDirection {
+                        characters,
+                        direction,
+                    },
+                ) => {
+                    if let hir::Direction::Exeunt = direction {
                         for state in self.states.values_mut() {
                             state.on_stage = false;
                         }
+                        self.on_stage.clear();
+                        continue;
                     }
-                    hir::Direction::Enter => {
-                        for character in characters {
-                            let state = self.states.get_mut(character).unwrap();
-                            assert!(!state.on_stage);
-                            state.on_stage = true;
-                            self.on_stage.push(character.clone());
-                        }
-                    }
-                    hir::Direction::Exit => {
-                        for character in characters {
-                            let state = self.states.get_mut(character).unwrap();
-                            assert!(state.on_stage);
-                            state.on_stage = false;
-                            let idx = self.on_stage.iter().position(|c| c == character).unwrap();
-                            self.on_stage.remove(idx);
-                        }
+
+                    let tokens = stage_direction.characters();
+                    for (token, character) in tokens.zip(characters) {
+                        self.direct(*character, direction, db)
+                            .map_err(|kind| kind.at(token.text_range()))?;
                     }
-                },
-                hir::Item::Dialog {
-                    character,
-                    sentences,
-                } => {
-                    assert_eq!(self.on_stage.len(), 2);
-                    let speaker_idx = self.on_stage.iter().position(|c| c == character).unwrap();
-                    let _speaker = self.on_stage[speaker_idx].clone();
-                    let listener = match speaker_idx {
-                        0 => self.on_stage[1].clone(),
-                        1 => self.on_stage[0].clone(),
-                        _ => unreachable!(),
-                    };
-
-                    for sentence in sentences {
-                        match sentence {
-                            hir::Sentence::Statement { expr } => {
-                                self.states.get_mut(&listener).unwrap().value =
-                                    self.eval_expr(expr, &db);
-                            }
-                            hir::Sentence::IntOutput => {
-                                println!("{}", self.states[&listener].value);
-                            }
-                            hir::Sentence::CharOutput => {
-                                println!(
-                                    "{}",
-                                    char::from_u32(
-                                        self.states[&listener].value.try_into().unwrap()
-                                    )
-                                    .unwrap()
-                                );
-                            }
+                }
+                (
+                    ast::Item::Dialog(dialog),
+                    hir::Item::Dialog {
+                        character,
+                        sentences,
+                    },
+                ) => {
+                    // Only a play with syntax errors has a dialog without a
+                    // speaker, and those never get this far.
+                    let speaker = character.unwrap();
+                    let listener = self
+                        .listener(speaker, db)
+                        .map_err(|kind| kind.at(dialog.character().unwrap().text_range()))?;
+
+                    let conversation = Conversation { speaker, listener };
+                    for (ast, sentence) in dialog.sentences().zip(sentences) {
+                        let range = ast.syntax().text_range();
+                        let scene = self
+                            .eval_sentence(sentence, conversation, db)
+                            .map_err(|kind| kind.at(range))?;
+
+                        // The rest of the dialog is skipped when the play
+                        // carries on from another scene.
+                        if let Some(scene) = scene {
+                            idx = scenes
+                                .find(idx - 1, scene)
+                                .ok_or_else(|| EvalErrorKind::UnknownScene(scene).at(range))?;
+                            break;
                         }
                     }
                 }
+                (ast::Item::Act(_), hir::Item::Act { .. })
+                | (ast::Item::Scene(_), hir::Item::Scene { .. }) => {}
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves a character on or off the stage.
+    fn direct(
+        &mut self,
+        character: hir::CharacterIdx,
+        direction: &hir::Direction,
+        db: &hir::Database,
+    ) -> Result<(), EvalErrorKind> {
+        let name = || db[character].name.clone();
+        let state = self
+            .states
+            .get_mut(character)
+            .ok_or_else(|| EvalErrorKind::UndeclaredCharacter(name()))?;
+
+        match direction {
+            hir::Direction::Enter => {
+                if state.on_stage {
+                    return Err(EvalErrorKind::AlreadyOnStage(name()));
+                }
+                if self.on_stage.try_push(character).is_err() {
+                    return Err(EvalErrorKind::CrowdedStage(name()));
+                }
+                state.on_stage = true;
+            }
+            hir::Direction::Exit => {
+                let idx = self
+                    .on_stage
+                    .iter()
+                    .position(|c| *c == character)
+                    .ok_or_else(|| EvalErrorKind::NotOnStage(name()))?;
+                state.on_stage = false;
+                self.on_stage.remove(idx);
+            }
+            hir::Direction::Exeunt | hir::Direction::Missing => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    /// Finds who a character on stage is talking to.
+    fn listener(
+        &self,
+        speaker: hir::CharacterIdx,
+        db: &hir::Database,
+    ) -> Result<hir::CharacterIdx, EvalErrorKind> {
+        let name = || db[speaker].name.clone();
+        let speaker_idx = self
+            .on_stage
+            .iter()
+            .position(|c| *c == speaker)
+            .ok_or_else(|| EvalErrorKind::NotOnStage(name()))?;
+
+        match (speaker_idx, self.on_stage.len()) {
+            (0, 2) => Ok(self.on_stage[1]),
+            (1, 2) => Ok(self.on_stage[0]),
+            _ => Err(EvalErrorKind::NoListener(name())),
+        }
+    }
+
+    /// Performs a sentence, giving the scene to carry on from if it is a goto
+    /// that happens.
+    fn eval_sentence(
+        &mut self,
+        sentence: &hir::Sentence,
+        conversation: Conversation,
+        db: &hir::Database,
+    ) -> Result<Option<u32>, EvalErrorKind> {
+        let listener = conversation.listener;
+
+        match sentence {
+            hir::Sentence::Vocative { character } => {
+                if *character != listener {
+                    return Err(EvalErrorKind::NotListening(db[*character].name.clone()));
+                }
+            }
+            hir::Sentence::Statement { expr } => {
+                self.states[listener].value = self.eval_expr(expr, conversation, db)?;
+            }
+            hir::Sentence::Question {
+                lhs,
+                comparison,
+                negated,
+                rhs,
+            } => {
+                let lhs = self.eval_expr(lhs, conversation, db)?;
+                let rhs = self.eval_expr(rhs, conversation, db)?;
+                let answer = match comparison {
+                    hir::Comparison::Greater => lhs > rhs,
+                    hir::Comparison::Less => lhs < rhs,
+                    hir::Comparison::Equal => lhs == rhs,
+                    hir::Comparison::Missing => unreachable!(),
+                };
+                self.answer = Some(answer != *negated);
+            }
+            hir::Sentence::Conditional { answer, sentence } => {
+                if self.answer.ok_or(EvalErrorKind::NoQuestion)? == answer.unwrap() {
+                    return self.eval_sentence(sentence.as_ref().unwrap(), conversation, db);
+                }
+            }
+            hir::Sentence::Goto { scene } => return Ok(*scene),
+            hir::Sentence::Push { expr } => {
+                let value = self.eval_expr(expr, conversation, db)?;
+                self.states[listener].stack.push(value);
+            }
+            hir::Sentence::Pop => {
+                let state = &mut self.states[listener];
+                state.value = state
+                    .stack
+                    .pop()
+                    .ok_or_else(|| EvalErrorKind::EmptyStack(db[listener].name.clone()))?;
+            }
+            hir::Sentence::IntOutput => {
+                writeln!(self.output.0, "{}", self.states[listener].value)
+                    .map_err(EvalErrorKind::Output)?;
+            }
+            hir::Sentence::CharOutput => {
+                let value = self.states[listener].value;
+                let c = u32::try_from(value)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(EvalErrorKind::NotACharacter(value))?;
+                write!(self.output.0, "{}", c).map_err(EvalErrorKind::Output)?;
+            }
+            hir::Sentence::IntInput => {
+                self.states[listener].value = self.read_int()?;
+            }
+            hir::Sentence::CharInput => {
+                self.states[listener].value = self.read_char()?;
             }
         }
+
+        Ok(None)
+    }
+
+    pub(crate) fn on_stage(&self) -> &[hir::CharacterIdx] {
+        &self.on_stage
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = (hir::CharacterIdx, i64)> + '_ {
+        self.states
+            .iter()
+            .map(|(character, state)| (character, state.value))
+    }
+
+    /// Reads a whole line, which has to hold nothing but a number.
+    fn read_int(&mut self) -> Result<i64, EvalErrorKind> {
+        let mut line = String::new();
+        if self
+            .input
+            .0
+            .read_line(&mut line)
+            .map_err(EvalErrorKind::Input)?
+            == 0
+        {
+            return Err(EvalErrorKind::ExpectedNumber(None));
+        }
+
+        let line = line.trim();
+        line.parse()
+            .map_err(|_| EvalErrorKind::ExpectedNumber(Some(line.to_string())))
+    }
+
+    /// Reads a single UTF-8 encoded character, giving -1 at the end of the
+    /// input.
+    fn read_char(&mut self) -> Result<i64, EvalErrorKind> {
+        let first = match self
+            .input
+            .0
+            .fill_buf()
+            .map_err(EvalErrorKind::Input)?
+            .first()
+        {
+            Some(first) => *first,
+            None => return Ok(-1),
+        };
+
+        let len = match first.leading_ones() {
+            0 => 1,
+            len @ 2..=4 => len as usize,
+            _ => 1,
+        };
+
+        let mut bytes = [0; 4];
+        self.input
+            .0
+            .read_exact(&mut bytes[..len])
+            .map_err(EvalErrorKind::Input)?;
+
+        let c = std::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+
+        Ok(i64::from(u32::from(c)))
     }
 
-    fn eval_expr(&mut self, expr: &hir::Expr, db: &hir::Database) -> i64 {
+    /// Works out the value of an expression, failing rather than panicking on
+    /// arithmetic that doesn’t fit in a value.
+    fn eval_expr(
+        &self,
+        expr: &hir::Expr,
+        conversation: Conversation,
+        db: &hir::Database,
+    ) -> Result<i64, EvalErrorKind> {
         match expr {
             hir::Expr::Noun { adjectives, noun } => {
                 let value = match noun {
@@ -90,31 +361,513 @@ impl Evaluator {
                     hir::Noun::Negative => -1,
                 };
 
-                value * 2_i64.pow(adjectives.len() as u32)
+                2_i64
+                    .checked_pow(adjectives.len() as u32)
+                    .map(|power| value * power)
+                    .ok_or(EvalErrorKind::Overflow)
             }
             hir::Expr::Bin {
                 operation,
                 lhs,
                 rhs,
             } => {
-                let lhs = self.eval_expr(&db[*lhs], db);
-                let rhs = self.eval_expr(&db[*rhs], db);
+                let lhs = self.eval_expr(&db[*lhs], conversation, db)?;
+                let rhs = self.eval_expr(&db[*rhs], conversation, db)?;
+
+                if rhs == 0
+                    && matches!(
+                        operation,
+                        hir::Operation::Quotient | hir::Operation::Remainder
+                    )
+                {
+                    return Err(EvalErrorKind::DivisionByZero);
+                }
 
                 match operation {
-                    hir::Operation::Remainder => lhs % rhs,
-                    hir::Operation::Difference => lhs - rhs,
-                    hir::Operation::Quotient => lhs / rhs,
-                    hir::Operation::Product => lhs * rhs,
-                    hir::Operation::Sum => lhs + rhs,
+                    hir::Operation::Remainder => lhs.checked_rem(rhs),
+                    hir::Operation::Difference => lhs.checked_sub(rhs),
+                    hir::Operation::Quotient => lhs.checked_div(rhs),
+                    hir::Operation::Product => lhs.checked_mul(rhs),
+                    hir::Operation::Sum => lhs.checked_add(rhs),
                 }
+                .ok_or(EvalErrorKind::Overflow)
+            }
+            hir::Expr::Unary { operation, operand } => {
+                let operand = self.eval_expr(&db[*operand], conversation, db)?;
+
+                match operation {
+                    hir::UnaryOperation::Twice => operand.checked_mul(2),
+                    hir::UnaryOperation::Square => operand.checked_mul(operand),
+                    hir::UnaryOperation::Cube => operand.checked_pow(3),
+                }
+                .ok_or(EvalErrorKind::Overflow)
+            }
+            hir::Expr::Nothing => Ok(0),
+            hir::Expr::Character(character) => self
+                .states
+                .get(*character)
+                .map(|state| state.value)
+                .ok_or_else(|| EvalErrorKind::UndeclaredCharacter(db[*character].name.clone())),
+            hir::Expr::Speaker => Ok(self.states[conversation.speaker].value),
+            hir::Expr::Listener => Ok(self.states[conversation.listener].value),
+            hir::Expr::Missing => unreachable!(),
+        }
+    }
+}
+
+/// An error that stops a play, along with the part of the play that caused
+/// it where there is one.
+#[derive(Debug)]
+pub(crate) struct EvalError {
+    pub(crate) kind: EvalErrorKind,
+    pub(crate) range: Option<TextRange>,
+}
+
+impl EvalError {
+    pub(crate) fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum EvalErrorKind {
+    Incomplete,
+    ExpectedNumber(Option<String>),
+    Input(io::Error),
+    Output(io::Error),
+    UndeclaredCharacter(String),
+    AlreadyOnStage(String),
+    NotOnStage(String),
+    CrowdedStage(String),
+    NoListener(String),
+    NotListening(String),
+    NotACharacter(i64),
+    DivisionByZero,
+    Overflow,
+    UnknownScene(u32),
+    NoQuestion,
+    EmptyStack(String),
+}
+
+impl EvalErrorKind {
+    fn at(self, range: TextRange) -> EvalError {
+        EvalError {
+            kind: self,
+            range: Some(range),
+        }
+    }
+
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Self::Incomplete => "incomplete",
+            Self::ExpectedNumber(_) => "expected-number",
+            Self::Input(_) => "input",
+            Self::Output(_) => "output",
+            Self::UndeclaredCharacter(_) => "undeclared-character",
+            Self::AlreadyOnStage(_) => "already-on-stage",
+            Self::NotOnStage(_) => "not-on-stage",
+            Self::CrowdedStage(_) => "crowded-stage",
+            Self::NoListener(_) => "no-listener",
+            Self::NotListening(_) => "not-listening",
+            Self::NotACharacter(_) => "not-a-character",
+            Self::DivisionByZero => "division-by-zero",
+            Self::Overflow => "overflow",
+            Self::UnknownScene(_) => "unknown-scene",
+            Self::NoQuestion => "no-question",
+            Self::EmptyStack(_) => "empty-stack",
+        }
+    }
+}
+
+impl fmt::Display for EvalErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Incomplete => write!(f, "cannot run a play that contains syntax errors"),
+            Self::ExpectedNumber(Some(found)) => {
+                write!(f, "expected a number as input but found {:?}", found)
+            }
+            Self::ExpectedNumber(None) => {
+                write!(f, "expected a number as input but the input ended")
+            }
+            Self::Input(error) => write!(f, "could not read input: {}", error),
+            Self::Output(error) => write!(f, "could not write output: {}", error),
+            Self::UndeclaredCharacter(name) => {
+                write!(f, "{} is not in the dramatis personae", name)
             }
-            hir::Expr::Nothing => 0,
+            Self::AlreadyOnStage(name) => write!(f, "{} is already on stage", name),
+            Self::NotOnStage(name) => write!(f, "{} is not on stage", name),
+            Self::CrowdedStage(name) => write!(
+                f,
+                "{} cannot enter as two characters are already on stage",
+                name
+            ),
+            Self::NoListener(name) => write!(f, "{} has no one on stage to talk to", name),
+            Self::NotListening(name) => write!(f, "{} is not the one being spoken to", name),
+            Self::NotACharacter(value) => write!(f, "{} is not a character", value),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::Overflow => write!(f, "value is too large"),
+            Self::UnknownScene(scene) => {
+                write!(f, "there is no scene {} in this act", roman::format(*scene))
+            }
+            Self::NoQuestion => write!(f, "nothing has been asked yet"),
+            Self::EmptyStack(name) => write!(f, "{} has nothing to recall", name),
         }
     }
 }
 
+/// Who is talking to whom, which decides who ‘I’ and ‘you’ are.
+#[derive(Debug, Clone, Copy)]
+struct Conversation {
+    speaker: hir::CharacterIdx,
+    listener: hir::CharacterIdx,
+}
+
 #[derive(Debug, Default)]
 struct CharacterState {
     value: i64,
     on_stage: bool,
+    /// What the character has been told to remember, most recent last.
+    stack: Vec<i64>,
+}
+
+/// Where each scene of a play starts, so that gotos can find them.
+struct Scenes {
+    /// The act each item is in, counting from the start of the play.
+    acts: Vec<usize>,
+    starts: HashMap<(usize, u32), usize>,
+}
+
+impl Scenes {
+    fn new(items: &[hir::Item]) -> Self {
+        let mut acts = Vec::with_capacity(items.len());
+        let mut starts = HashMap::new();
+        let mut act = 0;
+
+        for (idx, item) in items.iter().enumerate() {
+            match item {
+                hir::Item::Act { .. } => act += 1,
+                hir::Item::Scene {
+                    number: Some(number),
+                } => {
+                    starts.entry((act, *number)).or_insert(idx);
+                }
+                _ => {}
+            }
+            acts.push(act);
+        }
+
+        Self { acts, starts }
+    }
+
+    /// Finds where a scene in the same act as the item at `idx` starts.
+    fn find(&self, idx: usize, scene: u32) -> Option<usize> {
+        self.starts.get(&(self.acts[idx], scene)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast;
+    use crate::parser::parse;
+
+    #[test]
+    fn refuse_to_run_incomplete_program() {
+        let parse = parse(
+            "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet] Romeo: You are a.",
+            false,
+        );
+        let root = ast::Root::cast(parse.syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+
+        assert!(matches!(
+            Evaluator::default().eval(&root, &items, &db),
+            Err(EvalError {
+                kind: EvalErrorKind::Incomplete,
+                range: None
+            })
+        ));
+    }
+
+    fn eval_with_input(input: &str, program_input: &str) -> Result<Vec<i64>, EvalErrorKind> {
+        let parse = parse(input, false);
+        let root = ast::Root::cast(parse.syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+
+        let mut evaluator = Evaluator::with_input(io::Cursor::new(program_input.to_string()));
+        evaluator
+            .eval(&root, &items, &db)
+            .map_err(|error| error.kind)?;
+
+        Ok(evaluator.values().map(|(_, value)| value).collect())
+    }
+
+    #[test]
+    fn errors_point_at_what_went_wrong() {
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo] [Enter Juliet and Romeo]";
+        let root = ast::Root::cast(parse(play, false).syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+
+        let error = Evaluator::default().eval(&root, &items, &db).unwrap_err();
+        assert!(matches!(error.kind, EvalErrorKind::AlreadyOnStage(_)));
+        assert_eq!(&play[error.range.unwrap()], "Romeo");
+        assert_eq!(u32::from(error.range.unwrap().start()), 63);
+    }
+
+    #[test]
+    fn read_input() {
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: Listen to your heart.
+Juliet: Open your mind.";
+
+        assert_eq!(eval_with_input(play, "-12\nλ").unwrap(), [955, -12]);
+        assert_eq!(eval_with_input(play, "3\n").unwrap(), [-1, 3]);
+    }
+
+    #[test]
+    fn reject_input_that_is_not_a_number() {
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: Listen to your heart.";
+
+        assert!(matches!(
+            eval_with_input(play, "twelve\n"),
+            Err(EvalErrorKind::ExpectedNumber(Some(found))) if found == "twelve"
+        ));
+        assert!(matches!(
+            eval_with_input(play, ""),
+            Err(EvalErrorKind::ExpectedNumber(None))
+        ));
+    }
+
+    #[test]
+    fn reject_value_that_is_not_a_character() {
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: You are a pig. Speak your mind.";
+        assert!(matches!(
+            eval_with_input(play, ""),
+            Err(EvalErrorKind::NotACharacter(-1))
+        ));
+
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: Listen to your heart. Speak your mind.";
+        assert!(matches!(
+            eval_with_input(play, "-5\n"),
+            Err(EvalErrorKind::NotACharacter(-5))
+        ));
+        assert!(matches!(
+            eval_with_input(play, "55296\n"),
+            Err(EvalErrorKind::NotACharacter(55296))
+        ));
+    }
+
+    #[test]
+    fn reject_arithmetic_that_cannot_be_done() {
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: You are the quotient between a cat and nothing.";
+        assert!(matches!(
+            eval_with_input(play, ""),
+            Err(EvalErrorKind::DivisionByZero)
+        ));
+
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: You are the remainder of the quotient between a cat and nothing.";
+        assert!(matches!(
+            eval_with_input(play, ""),
+            Err(EvalErrorKind::DivisionByZero)
+        ));
+
+        let play = format!(
+            "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: You are the product of a {}cat and a big big cat.",
+            "big ".repeat(62)
+        );
+        assert!(matches!(
+            eval_with_input(&play, ""),
+            Err(EvalErrorKind::Overflow)
+        ));
+
+        let play = format!(
+            "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: You are a {}cat.",
+            "big ".repeat(63)
+        );
+        assert!(matches!(
+            eval_with_input(&play, ""),
+            Err(EvalErrorKind::Overflow)
+        ));
+
+        let play = format!(
+            "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: You are the square of a {}cat.",
+            "big ".repeat(32)
+        );
+        assert!(matches!(
+            eval_with_input(&play, ""),
+            Err(EvalErrorKind::Overflow)
+        ));
+    }
+
+    #[test]
+    fn use_characters_values() {
+        let play = "Romeo, a test. Juliet, a test. Hamlet, a test.
+[Enter Romeo and Juliet]
+Romeo: Listen to your heart. Juliet, you are the product of yourself and a big cat.
+Juliet: You are the sum of me and Hamlet.
+[Exeunt]
+[Enter Hamlet and Romeo]
+Hamlet: You are the difference between Juliet and the square of thyself.";
+
+        assert_eq!(
+            eval_with_input(
+                play, "3
+"
+            )
+            .unwrap(),
+            [-30, 6, 0]
+        );
+    }
+
+    #[test]
+    fn reject_vocative_to_someone_else() {
+        let play = "Romeo, a test. Juliet, a test. Hamlet, a test.
+[Enter Romeo and Juliet]
+Romeo: Hamlet, you are a cat.";
+
+        assert!(matches!(
+            eval_with_input(play, ""),
+            Err(EvalErrorKind::NotListening(name)) if name == "Hamlet"
+        ));
+    }
+
+    #[test]
+    fn reject_arithmetic_on_values_that_cannot_be_done() {
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: Listen to your heart. You are the quotient between a cat and yourself.";
+        assert!(matches!(
+            eval_with_input(play, "0\n"),
+            Err(EvalErrorKind::DivisionByZero)
+        ));
+        assert!(matches!(
+            eval_with_input(play, "-1\n"),
+            Ok(values) if values == [0, -1]
+        ));
+
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: Listen to your heart. You are the cube of thyself.";
+        assert!(matches!(
+            eval_with_input(play, "3000000\n"),
+            Err(EvalErrorKind::Overflow)
+        ));
+    }
+
+    #[test]
+    fn loop_back_to_scene() {
+        let play = "Romeo, a test. Juliet, a test.
+Act I: Sums.
+Scene I: A number.
+[Enter Romeo and Juliet]
+Romeo: Listen to your heart.
+Scene II: The loop.
+Juliet: You are the sum of yourself and me.
+Romeo: You are the difference between thyself and a cat. Are you better than nothing? If so, let us return to scene II. You are a cat.";
+        assert!(matches!(
+            eval_with_input(play, "4\n"),
+            Ok(values) if values == [10, 1]
+        ));
+    }
+
+    #[test]
+    fn answer_questions() {
+        let answer = |question: &str| {
+            let play = format!(
+                "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: You are a big cat. {} If so, you are nothing. If not, you are a cat.",
+                question
+            );
+            eval_with_input(&play, "").unwrap()[1] == 0
+        };
+
+        assert!(answer("Are you better than a cat?"));
+        assert!(!answer("Are you worse than a cat?"));
+        assert!(answer("Are you more fair than nothing?"));
+        assert!(!answer("Are you more foul than nothing?"));
+        assert!(answer("Are you as good as the sum of a cat and a cat?"));
+        assert!(!answer("Are you not as good as twice a cat?"));
+        assert!(answer("Am I as good as nothing?"));
+    }
+
+    #[test]
+    fn remember_and_recall() {
+        let play = "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Juliet: Remember a cat. Remember a big cat. Recall your past.
+Romeo: You are me.
+Juliet: Recall your past.";
+        assert!(matches!(
+            eval_with_input(play, ""),
+            Ok(values) if values == [1, 2]
+        ));
+
+        let play = format!("{} Recall your past.", play);
+        assert_eq!(
+            eval_with_input(&play, "").unwrap_err().to_string(),
+            "Romeo has nothing to recall"
+        );
+    }
+
+    #[test]
+    fn reject_control_flow_that_cannot_be_followed() {
+        let check = |play: &str, expected: &str| {
+            let error = eval_with_input(play, "").unwrap_err();
+            assert_eq!(error.to_string(), expected);
+        };
+
+        check(
+            "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: If so, you are a cat.",
+            "nothing has been asked yet",
+        );
+        check(
+            "Romeo, a test. Juliet, a test.
+Act I: First. Scene I: The first. [Enter Romeo and Juliet]
+Act II: Second. Scene II: The second.
+Romeo: Let us return to scene I.",
+            "there is no scene I in this act",
+        );
+    }
+
+    #[test]
+    fn reject_invalid_stage_directions() {
+        let check = |play: &str, expected: &str| {
+            let error = eval_with_input(play, "").unwrap_err();
+            assert_eq!(error.to_string(), expected);
+        };
+
+        check(
+            "Romeo, a test. [Enter Romeo] [Enter Romeo]",
+            "Romeo is already on stage",
+        );
+        check("Romeo, a test. [Exit Romeo]", "Romeo is not on stage");
+        check(
+            "Romeo, a test. Juliet, a test. Hamlet, a test. [Enter Romeo and Juliet] [Enter Hamlet]",
+            "Hamlet cannot enter as two characters are already on stage",
+        );
+        check(
+            "Romeo, a test. [Enter Romeo] Romeo: You are nothing.",
+            "Romeo has no one on stage to talk to",
+        );
+        check(
+            "Romeo, a test. Juliet, a test. [Enter Romeo] Juliet: You are nothing.",
+            "Juliet is not on stage",
+        );
+        check("[Enter Romeo]", "Romeo is not in the dramatis personae");
+    }
 }
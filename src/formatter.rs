@@ -0,0 +1,285 @@
+use crate::lexer::SyntaxKind;
+use crate::syntax::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+const INDENT: &str = "    ";
+
+/// Lays a play out canonically: the title and each heading followed by a
+/// blank line, the dramatis personae one per line, and every sentence after
+/// the first in a speech on its own indented line.
+///
+/// Only whitespace is ever changed, and inside comments not even that. Text
+/// between items that failed to parse is kept exactly as it was written.
+pub(crate) fn format(root: &SyntaxNode) -> String {
+    let mut out = String::new();
+    let mut previous = None;
+    let mut blank_line = false;
+
+    let elements: Vec<_> = root.children_with_tokens().collect();
+    let mut idx = 0;
+
+    while idx < elements.len() {
+        let element = &elements[idx];
+        if element.kind() == SyntaxKind::Whitespace {
+            blank_line |= element.to_string().matches('\n').count() >= 2;
+            idx += 1;
+            continue;
+        }
+
+        let kind = ItemKind::of(element);
+        if let Some(previous) = previous {
+            out.push('\n');
+            if separate(previous, kind, blank_line) {
+                out.push('\n');
+            }
+        }
+
+        // Whitespace after the last token of an item ends up inside it.
+        let trailing_whitespace = if element.kind() == SyntaxKind::Error {
+            let end = unparsable_end(&elements, idx);
+            let trailing_whitespace = format_unparsable(&mut out, &elements[idx..end]);
+            idx = end;
+            trailing_whitespace
+        } else {
+            idx += 1;
+            format_item(&mut out, element)
+        };
+
+        previous = Some(kind);
+        blank_line = trailing_whitespace.matches('\n').count() >= 2;
+    }
+
+    if previous.is_some() {
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Finds where a run of text that isn’t part of any item ends. The parser
+/// gives every stray token its own error node, which are taken together,
+/// along with the whitespace between them, so that they can be kept as one.
+fn unparsable_end(elements: &[SyntaxElement], start: usize) -> usize {
+    let mut end = start;
+    for (idx, element) in elements.iter().enumerate().skip(start) {
+        match element.kind() {
+            SyntaxKind::Error => end = idx + 1,
+            SyntaxKind::Whitespace => {}
+            _ => break,
+        }
+    }
+
+    end
+}
+
+fn format_unparsable(out: &mut String, elements: &[SyntaxElement]) -> String {
+    let text: String = elements.iter().map(ToString::to_string).collect();
+    let trimmed = text.trim_end();
+    out.push_str(trimmed);
+
+    text[trimmed.len()..].to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ItemKind {
+    Title,
+    CharacterDef,
+    Heading,
+    Other,
+}
+
+impl ItemKind {
+    fn of(element: &SyntaxElement) -> Self {
+        match element.kind() {
+            SyntaxKind::Comment => Self::Title,
+            SyntaxKind::CharacterDef => Self::CharacterDef,
+            SyntaxKind::ActHeading | SyntaxKind::SceneHeading => Self::Heading,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Whether a blank line goes between two items.
+fn separate(previous: ItemKind, next: ItemKind, blank_line: bool) -> bool {
+    match (previous, next) {
+        (ItemKind::Title | ItemKind::Heading, _) | (_, ItemKind::Heading) => true,
+        (ItemKind::CharacterDef, ItemKind::CharacterDef) => false,
+        (ItemKind::CharacterDef, _) | (_, ItemKind::CharacterDef) => true,
+        // Writers often group a scene’s speeches with blank lines, so those
+        // are kept, though never more than one in a row.
+        _ => blank_line,
+    }
+}
+
+fn format_item(out: &mut String, item: &SyntaxElement) -> String {
+    let tokens: Vec<SyntaxToken> = match item {
+        SyntaxElement::Node(node) => node
+            .descendants_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .collect(),
+        SyntaxElement::Token(token) => vec![token.clone()],
+    };
+
+    let mut previous: Option<&SyntaxToken> = None;
+    let mut whitespace = String::new();
+
+    for token in &tokens {
+        if token.kind() == SyntaxKind::Whitespace {
+            whitespace.push_str(token.text());
+            continue;
+        }
+
+        if let Some(previous) = previous {
+            // Everything a comment says is kept, even its own spacing.
+            if previous.kind() == SyntaxKind::Skip && token.kind() == SyntaxKind::Skip {
+                out.push_str(&whitespace);
+            } else if starts_sentence(token) && is_terminator(previous.kind()) {
+                out.push('\n');
+                out.push_str(INDENT);
+            } else if needs_space(previous.kind(), token.kind(), !whitespace.is_empty()) {
+                out.push(' ');
+            }
+        }
+
+        out.push_str(token.text());
+        previous = Some(token);
+        whitespace.clear();
+    }
+
+    whitespace
+}
+
+/// Whether a token is the first of a sentence, or of unparsable text between
+/// sentences, in a speech.
+fn starts_sentence(token: &SyntaxToken) -> bool {
+    token
+        .ancestors()
+        .find(|node| {
+            node.parent()
+                .is_some_and(|parent| parent.kind() == SyntaxKind::Dialog)
+        })
+        .and_then(|node| node.first_token())
+        .is_some_and(|first| &first == token)
+}
+
+fn is_terminator(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Period | SyntaxKind::Exclamation | SyntaxKind::Question
+    )
+}
+
+fn needs_space(previous: SyntaxKind, next: SyntaxKind, had_whitespace: bool) -> bool {
+    let closes = |kind| {
+        is_terminator(kind)
+            || matches!(
+                kind,
+                SyntaxKind::Comma | SyntaxKind::Colon | SyntaxKind::RBracket
+            )
+    };
+
+    if closes(next) || previous == SyntaxKind::LBracket {
+        false
+    } else {
+        // Words that were written together, like the pieces of a word the
+        // lexer doesn’t know, have to stay together.
+        had_whitespace || closes(previous) || next == SyntaxKind::LBracket
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use expect_test::{expect, Expect};
+
+    fn check(input: &str, expected: Expect) {
+        let formatted = format(&parse(input, true).syntax_node());
+        expected.assert_eq(&formatted);
+
+        let reformatted = format(&parse(&formatted, true).syntax_node());
+        assert_eq!(formatted, reformatted, "formatting is not idempotent");
+    }
+
+    #[test]
+    fn format_play() {
+        check(
+            "The Infamous  Hello World Program.   Romeo, a young man with a remarkable patience.
+Juliet, a likewise young woman of remarkable grace.   Act I: Hamlet's insults and flattery.
+Scene I: The insulting of Romeo.
+[ Enter Romeo and Juliet ]
+Juliet:You are a big cat .Open your heart!Speak your mind!
+Romeo :   You are nothing.
+
+[Exit Juliet]",
+            expect![[r#"
+The Infamous  Hello World Program.
+
+Romeo, a young man with a remarkable patience.
+Juliet, a likewise young woman of remarkable grace.
+
+Act I: Hamlet's insults and flattery.
+
+Scene I: The insulting of Romeo.
+
+[Enter Romeo and Juliet]
+Juliet: You are a big cat.
+    Open your heart!
+    Speak your mind!
+Romeo: You are nothing.
+
+[Exit Juliet]
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_unparsable_text() {
+        check(
+            "Title.\nRomeo: Art  thou. You are a   cat.\nwhat  is this\n",
+            expect![[r#"
+Title.
+
+Romeo: Art thou.
+    You are a cat.
+    what is this
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_unparsable_text_between_items() {
+        check(
+            "Title.\nRomeo, a man.\n[Enter Romeo]\nwhat  is  this\n  oh   no\n\n[Exit  Romeo]\n",
+            expect![[r#"
+Title.
+
+Romeo, a man.
+
+[Enter Romeo]
+what  is  this
+  oh   no
+
+[Exit Romeo]
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_comments_and_joined_words() {
+        check(
+            "Title.\nRomeo,a  man . Romeo: You are a cats.",
+            expect![[r#"
+Title.
+
+Romeo, a  man.
+
+Romeo: You are a cats.
+"#]],
+        );
+    }
+
+    #[test]
+    fn format_empty_play() {
+        check("", expect![[r#""#]]);
+    }
+}
@@ -0,0 +1,436 @@
+use crate::encode::encode;
+use crate::roman;
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+use text_size::{TextRange, TextSize};
+
+/// How many cells the tape has, as in the original Brainfuck.
+const CELLS: i64 = 30_000;
+
+/// The largest number a scene heading can be written with.
+const MAX_SCENE: u32 = 4999;
+
+/// The scene the program proper starts in, after the tape is set up.
+const FIRST_SCENE: u32 = 3;
+
+/// Compiles a Brainfuck program into a play.
+///
+/// Romeo holds the cell under the pointer, and is the one Juliet talks to.
+/// The cells to the right of him are on his stack, and the cells to the left
+/// on Juliet’s, so moving the pointer moves a value from one stack to the
+/// other. Every loop is a scene that starts by asking whether Romeo is
+/// nothing, and ends by going back to that question.
+pub(crate) fn from_bf(program: &str) -> Result<String, BfError> {
+    let instructions = instructions(program)?;
+
+    let mut play = Play::default();
+    play.s.push_str("A Translation from Brainfuck.\n\n");
+    play.s
+        .push_str("Romeo, who holds the cell under the pointer.\n");
+    play.s
+        .push_str("Juliet, who remembers the cells to the left.\n\n");
+    play.s.push_str("Act I: The program.\n\n");
+    play.s
+        .push_str("Scene I: The tape.\n\n[Enter Romeo and Juliet]\n");
+    play.say(Romeo, format!("You are {}.", encode(CELLS, true)));
+    play.scene(2, "Every cell is nothing");
+    play.say(Juliet, "Remember nothing.");
+    play.say(Romeo, "You are the difference between yourself and a cat.");
+    play.say(Romeo, "Are you better than nothing?");
+    play.say(Romeo, "If so, let us return to scene II.");
+    play.scene(FIRST_SCENE, "The program");
+
+    for instruction in instructions {
+        match instruction.op {
+            Op::Right => {
+                play.say(Romeo, "Remember me.");
+                play.say(Juliet, "Recall your past.");
+            }
+            Op::Left => {
+                play.say(Juliet, "Remember yourself.");
+                play.say(Romeo, "Recall your past.");
+                play.say(Juliet, "You are me.");
+            }
+            Op::Add(0) => {}
+            Op::Add(amount) => play.say(
+                Juliet,
+                format!(
+                    "You are the remainder of the quotient between the sum of yourself and {} and {}.",
+                    encode(amount.into(), true),
+                    encode(256, true),
+                ),
+            ),
+            Op::Output => play.say(Juliet, "Speak your mind."),
+            // The end of the input reads as nothing, and anything past a
+            // byte wraps around like adding to a cell does.
+            Op::Input => {
+                play.say(Juliet, "Open your mind.");
+                play.say(Juliet, "Are you worse than nothing?");
+                play.say(Juliet, "If so, you are nothing.");
+                play.say(
+                    Juliet,
+                    format!(
+                        "You are the remainder of the quotient between yourself and {}.",
+                        encode(256, true),
+                    ),
+                );
+            }
+            Op::Open { scene, after } => {
+                play.scene(scene, "A loop");
+                play.say(Juliet, "Are you as good as nothing?");
+                play.say(
+                    Juliet,
+                    format!("If so, let us proceed to scene {}.", roman::format(after)),
+                );
+            }
+            Op::Close { scene, back } => {
+                play.say(
+                    Juliet,
+                    format!("Let us return to scene {}.", roman::format(back)),
+                );
+                play.scene(scene, "After a loop");
+            }
+        }
+    }
+
+    play.speech();
+    play.s.push_str("[Exeunt]\n");
+
+    Ok(play.s)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Speaker {
+    Romeo,
+    Juliet,
+}
+use Speaker::{Juliet, Romeo};
+
+/// A play as it is written, with the sentences of whoever is speaking kept
+/// back until someone else speaks.
+#[derive(Default)]
+struct Play {
+    s: String,
+    speaker: Option<Speaker>,
+    sentences: Vec<String>,
+}
+
+impl Play {
+    fn say(&mut self, speaker: Speaker, sentence: impl Into<String>) {
+        if self.speaker != Some(speaker) {
+            self.speech();
+            self.speaker = Some(speaker);
+        }
+        self.sentences.push(sentence.into());
+    }
+
+    fn scene(&mut self, number: u32, comment: &str) {
+        self.speech();
+        self.speaker = None;
+        write!(
+            self.s,
+            "\nScene {}: {}.\n\n",
+            roman::format(number),
+            comment
+        )
+        .unwrap();
+    }
+
+    fn speech(&mut self) {
+        if let Some(speaker) = self.speaker {
+            if !self.sentences.is_empty() {
+                writeln!(self.s, "{:?}: {}", speaker, self.sentences.join("\n    ")).unwrap();
+                self.sentences.clear();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Right,
+    Left,
+    /// A run of `+` and `-`, as what it adds to the cell modulo 256.
+    Add(u8),
+    Output,
+    Input,
+    /// Starts the scene of a loop, which goes on to the scene after the
+    /// loop when the cell is zero.
+    Open {
+        scene: u32,
+        after: u32,
+    },
+    /// Goes back to the scene of the loop, and starts the scene after it.
+    Close {
+        scene: u32,
+        back: u32,
+    },
+}
+
+struct Instruction {
+    op: Op,
+    range: TextRange,
+}
+
+/// Everything that isn’t one of the eight commands is a comment.
+fn instructions(program: &str) -> Result<Vec<Instruction>, BfError> {
+    let mut instructions: Vec<Instruction> = Vec::new();
+    let mut open = Vec::new();
+    let mut last_scene = FIRST_SCENE;
+
+    for (offset, c) in program.char_indices() {
+        let range = TextRange::at(TextSize::try_from(offset).unwrap(), TextSize::from(1));
+        // Every bracket starts a scene, numbered in the order they come.
+        let mut scene = || {
+            last_scene += 1;
+            if last_scene > MAX_SCENE {
+                return Err(BfError {
+                    kind: BfErrorKind::TooManyLoops,
+                    range,
+                });
+            }
+            Ok(last_scene)
+        };
+        let op = match c {
+            '>' => Op::Right,
+            '<' => Op::Left,
+            '+' | '-' => {
+                let amount = if c == '+' { 1 } else { u8::MAX };
+                if let Some(Instruction {
+                    op: Op::Add(previous),
+                    ..
+                }) = instructions.last_mut()
+                {
+                    *previous = previous.wrapping_add(amount);
+                    continue;
+                }
+                Op::Add(amount)
+            }
+            '.' => Op::Output,
+            ',' => Op::Input,
+            '[' => {
+                open.push(instructions.len());
+                Op::Open {
+                    scene: scene()?,
+                    after: 0,
+                }
+            }
+            ']' => match open.pop() {
+                Some(start) => {
+                    let scene = scene()?;
+                    match &mut instructions[start].op {
+                        Op::Open { scene: back, after } => {
+                            *after = scene;
+                            Op::Close { scene, back: *back }
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                None => {
+                    return Err(BfError {
+                        kind: BfErrorKind::UnmatchedClose,
+                        range,
+                    })
+                }
+            },
+            _ => continue,
+        };
+
+        instructions.push(Instruction { op, range });
+    }
+
+    match open.pop() {
+        Some(start) => Err(BfError {
+            kind: BfErrorKind::UnmatchedOpen,
+            range: instructions[start].range,
+        }),
+        None => Ok(instructions),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BfError {
+    pub(crate) kind: BfErrorKind,
+    pub(crate) range: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum BfErrorKind {
+    UnmatchedOpen,
+    UnmatchedClose,
+    TooManyLoops,
+}
+
+impl fmt::Display for BfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error at {}..{}: {}",
+            u32::from(self.range.start()),
+            u32::from(self.range.end()),
+            self.kind,
+        )
+    }
+}
+
+impl BfErrorKind {
+    /// A stable name for the kind of error, for tools that consume
+    /// diagnostics.
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            BfErrorKind::UnmatchedOpen | BfErrorKind::UnmatchedClose => "unmatched-bracket",
+            BfErrorKind::TooManyLoops => "too-many-loops",
+        }
+    }
+}
+
+impl fmt::Display for BfErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BfErrorKind::UnmatchedOpen => write!(f, "this ‘[’ is never closed"),
+            BfErrorKind::UnmatchedClose => write!(f, "this ‘]’ closes nothing"),
+            BfErrorKind::TooManyLoops => write!(
+                f,
+                "this loop needs a scene numbered past {}, the most a heading can be",
+                roman::format(MAX_SCENE)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast;
+    use crate::codegen::tests::interpret;
+    use crate::eval::Evaluator;
+    use crate::formatter::format;
+    use crate::hir;
+    use crate::parser::parse;
+    use crate::validation::validate;
+    use expect_test::{expect, Expect};
+    use std::io;
+
+    fn check_error(program: &str, expected: Expect) {
+        expected.assert_eq(&from_bf(program).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn compile_loops_and_input() {
+        // Reads characters until the input runs out, saying each one back.
+        let play = from_bf(",[.,]").unwrap();
+        expect![[r#"
+A Translation from Brainfuck.
+
+Romeo, who holds the cell under the pointer.
+Juliet, who remembers the cells to the left.
+
+Act I: The program.
+
+Scene I: The tape.
+
+[Enter Romeo and Juliet]
+Romeo: You are the product of the sum of a big joy and a joy and the square of the square of twice the sum of a big big joy and a joy.
+
+Scene II: Every cell is nothing.
+
+Juliet: Remember nothing.
+Romeo: You are the difference between yourself and a cat.
+    Are you better than nothing?
+    If so, let us return to scene II.
+
+Scene III: The program.
+
+Juliet: Open your mind.
+    Are you worse than nothing?
+    If so, you are nothing.
+    You are the remainder of the quotient between yourself and a big big big big big big big big joy.
+
+Scene IV: A loop.
+
+Juliet: Are you as good as nothing?
+    If so, let us proceed to scene V.
+    Speak your mind.
+    Open your mind.
+    Are you worse than nothing?
+    If so, you are nothing.
+    You are the remainder of the quotient between yourself and a big big big big big big big big joy.
+    Let us return to scene IV.
+
+Scene V: After a loop.
+
+[Exeunt]
+"#]]
+        .assert_eq(&play);
+
+        assert_eq!(interpret(&play, "to be"), "to be");
+        assert_eq!(interpret(&play, ""), "");
+        assert_eq!(format(&parse(&play, true).syntax_node()), play);
+    }
+
+    #[test]
+    fn compile_hello_world() {
+        let play = from_bf(
+            "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.\
+             ------.--------.>>+.>++.",
+        )
+        .unwrap();
+        assert_eq!(interpret(&play, ""), "Hello World!\n");
+
+        let root = ast::Root::cast(parse(&play, true).syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+        assert_eq!(validate(&root, &items, &db), []);
+        assert_eq!(format(&parse(&play, true).syntax_node()), play);
+    }
+
+    #[test]
+    fn compile_digits() {
+        let play = from_bf("++++++++[>++++++<-]>>++++++++++[<.+>-]").unwrap();
+        assert_eq!(interpret(&play, ""), "0123456789");
+    }
+
+    #[test]
+    fn wrap_cells_around() {
+        // Nothing less one is 255, and one more than that is nothing again.
+        let play = from_bf("-.+.").unwrap();
+        assert_eq!(interpret(&play, ""), "\u{ff}\0");
+    }
+
+    #[test]
+    fn compile_nothing() {
+        assert_eq!(interpret(&from_bf("a comment").unwrap(), ""), "");
+    }
+
+    #[test]
+    fn leaving_the_tape() {
+        let play = from_bf(">><<<").unwrap();
+        let root = ast::Root::cast(parse(&play, true).syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+
+        let error = Evaluator::with_input(io::empty())
+            .eval(&root, &items, &db)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Juliet has nothing to recall");
+    }
+
+    #[test]
+    fn unmatched_brackets() {
+        check_error(
+            "+[[-]",
+            expect![[r#"error at 1..2: this ‘[’ is never closed"#]],
+        );
+        check_error("+]", expect![[r#"error at 1..2: this ‘]’ closes nothing"#]]);
+    }
+
+    #[test]
+    fn running_out_of_scenes() {
+        check_error(
+            &"[]".repeat(2500),
+            expect![[
+                r#"error at 4996..4997: this loop needs a scene numbered past MMMMCMXCIX, the most a heading can be"#
+            ]],
+        );
+    }
+}
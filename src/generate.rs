@@ -0,0 +1,119 @@
+use crate::encode::encode;
+use std::fmt::Write;
+
+const CHARACTERS: [&str; 2] = ["Romeo", "Juliet"];
+
+/// Writes a play in which Romeo and Juliet say `text` between them, one
+/// character at a time. Each of them holds a value, so a character either
+/// of them already holds is spoken again without being set, and otherwise
+/// the value that is needed again furthest ahead is the one replaced.
+pub(crate) fn generate(text: &str) -> String {
+    let values: Vec<i64> = text.chars().map(|c| i64::from(u32::from(c))).collect();
+    let mut held = [0; 2];
+    // Who speaks, and what they say, with sentences in a row by the same
+    // speaker kept together.
+    let mut speeches: Vec<(usize, Vec<String>)> = Vec::new();
+
+    for (i, value) in values.iter().copied().enumerate() {
+        let mut sentences = Vec::new();
+        let listener = match held.iter().position(|held| *held == value) {
+            Some(listener) => listener,
+            None => {
+                let next_use = |held| {
+                    values[i + 1..]
+                        .iter()
+                        .position(|value| *value == held)
+                        .unwrap_or(usize::MAX)
+                };
+                let listener = if next_use(held[0]) >= next_use(held[1]) {
+                    0
+                } else {
+                    1
+                };
+                held[listener] = value;
+                sentences.push(format!("You are {}.", encode(value, true)));
+                listener
+            }
+        };
+        sentences.push("Speak your mind.".to_string());
+
+        let speaker = 1 - listener;
+        match speeches.last_mut() {
+            Some((previous, said)) if *previous == speaker => said.extend(sentences),
+            _ => speeches.push((speaker, sentences)),
+        }
+    }
+
+    let mut s = String::new();
+    s.push_str("The Printing of a Message.\n\n");
+    writeln!(s, "{}, a young man with something to say.", CHARACTERS[0]).unwrap();
+    writeln!(s, "{}, a young woman who helps him say it.", CHARACTERS[1]).unwrap();
+    s.push_str("\nAct I: The message.\n\nScene I: Every word of it.\n\n");
+    writeln!(s, "[Enter {} and {}]", CHARACTERS[0], CHARACTERS[1]).unwrap();
+    for (speaker, sentences) in speeches {
+        write!(s, "{}: ", CHARACTERS[speaker]).unwrap();
+        s.push_str(&sentences.join("\n    "));
+        s.push('\n');
+    }
+    s.push_str("[Exeunt]\n");
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::interpret;
+    use crate::formatter::format;
+    use crate::parser::parse;
+    use expect_test::expect;
+    use proptest::prelude::*;
+
+    #[test]
+    fn generate_reuses_values() {
+        expect![[r#"
+The Printing of a Message.
+
+Romeo, a young man with something to say.
+Juliet, a young woman who helps him say it.
+
+Act I: The message.
+
+Scene I: Every word of it.
+
+[Enter Romeo and Juliet]
+Juliet: You are the difference between a big big big big big big big joy and the sum of a big big big big joy and the sum of a big joy and a joy.
+    Speak your mind.
+Romeo: You are the sum of a big big big big big big joy and the sum of a big big big big big joy and a joy.
+    Speak your mind.
+Juliet: Speak your mind.
+Romeo: Speak your mind.
+[Exeunt]
+"#]]
+        .assert_eq(&generate("mama"));
+    }
+
+    #[test]
+    fn generate_hello_world() {
+        let text = "Hello, World!";
+        assert_eq!(interpret(&generate(text), ""), text);
+    }
+
+    #[test]
+    fn generate_nothing() {
+        assert_eq!(interpret(&generate(""), ""), "");
+    }
+
+    #[test]
+    fn generated_play_is_formatted() {
+        let play = generate("Hello, World!\n");
+        assert_eq!(format(&parse(&play, true).syntax_node()), play);
+    }
+
+    proptest! {
+        #[test]
+        fn says_text(text: String) {
+            prop_assert_eq!(interpret(&generate(&text), ""), text);
+        }
+    }
+}
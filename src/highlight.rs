@@ -0,0 +1,226 @@
+use crate::lexer::SyntaxKind;
+use crate::syntax::{SyntaxElement, SyntaxNode, SyntaxToken};
+use std::fmt;
+use text_size::TextRange;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct HighlightedRange {
+    pub(crate) range: TextRange,
+    pub(crate) tag: Tag,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Tag {
+    Character,
+    Noun(Polarity),
+    Adjective(Polarity),
+    Operator,
+    Keyword,
+    StageDirection,
+    Comment,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Polarity {
+    Positive,
+    Negative,
+    Neutral,
+}
+
+/// Classifies the text of a play for highlighting, in order. Punctuation and
+/// whitespace are left out. Neighbouring tokens with the same tag are joined,
+/// since comments and unknown words are lexed a few letters at a time, and
+/// comments and errors are also joined across spaces up to the end of the
+/// line.
+pub(crate) fn highlight(root: &SyntaxNode) -> Vec<HighlightedRange> {
+    let mut highlights: Vec<HighlightedRange> = Vec::new();
+    let mut same_line = false;
+
+    for token in root
+        .descendants_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+    {
+        if token.kind() == SyntaxKind::Whitespace {
+            same_line &= !token.text().contains('\n');
+            continue;
+        }
+
+        let tag = match tag(&token) {
+            Some(tag) => tag,
+            None => {
+                same_line = false;
+                continue;
+            }
+        };
+        let range = token.text_range();
+
+        match highlights.last_mut() {
+            Some(last)
+                if last.tag == tag
+                    && same_line
+                    && (last.range.end() == range.start()
+                        || matches!(tag, Tag::Comment | Tag::Error)) =>
+            {
+                last.range = last.range.cover(range);
+            }
+            _ => highlights.push(HighlightedRange { range, tag }),
+        }
+
+        same_line = true;
+    }
+
+    highlights
+}
+
+fn tag(token: &SyntaxToken) -> Option<Tag> {
+    // Whatever the parser gave up on, or took as free text, is shown as such
+    // regardless of which words it contains.
+    let enclosing = token
+        .parent()?
+        .ancestors()
+        .find_map(|node| match node.kind() {
+            SyntaxKind::Error => Some(Tag::Error),
+            SyntaxKind::Comment => Some(Tag::Comment),
+            _ => None,
+        });
+    if enclosing.is_some() {
+        return enclosing;
+    }
+
+    tag_of_kind(token.kind())
+}
+
+/// How a kind of token is highlighted outside of comments and errors, if at
+/// all.
+pub(crate) fn tag_of_kind(kind: SyntaxKind) -> Option<Tag> {
+    let tag = match kind {
+        SyntaxKind::Character => Tag::Character,
+        SyntaxKind::PositiveNoun => Tag::Noun(Polarity::Positive),
+        SyntaxKind::NegativeNoun => Tag::Noun(Polarity::Negative),
+        SyntaxKind::NeutralNoun => Tag::Noun(Polarity::Neutral),
+        SyntaxKind::PositiveAdjective => Tag::Adjective(Polarity::Positive),
+        SyntaxKind::NegativeAdjective => Tag::Adjective(Polarity::Negative),
+        SyntaxKind::NeutralAdjective => Tag::Adjective(Polarity::Neutral),
+        SyntaxKind::Difference
+        | SyntaxKind::Product
+        | SyntaxKind::Quotient
+        | SyntaxKind::Sum
+        | SyntaxKind::Remainder
+        | SyntaxKind::Cube
+        | SyntaxKind::Square
+        | SyntaxKind::SquareRoot
+        | SyntaxKind::Factorial
+        | SyntaxKind::Twice => Tag::Operator,
+        SyntaxKind::Enter
+        | SyntaxKind::Exit
+        | SyntaxKind::Exeunt
+        | SyntaxKind::LBracket
+        | SyntaxKind::RBracket => Tag::StageDirection,
+        SyntaxKind::Error => Tag::Error,
+        SyntaxKind::Period
+        | SyntaxKind::Exclamation
+        | SyntaxKind::Question
+        | SyntaxKind::Comma
+        | SyntaxKind::Colon
+        | SyntaxKind::Whitespace => return None,
+        _ => Tag::Keyword,
+    };
+
+    Some(tag)
+}
+
+impl Tag {
+    /// A stable name for the tag, for tools that consume highlighting.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Character => "character",
+            Self::Noun(Polarity::Positive) => "positive-noun",
+            Self::Noun(Polarity::Negative) => "negative-noun",
+            Self::Noun(Polarity::Neutral) => "neutral-noun",
+            Self::Adjective(Polarity::Positive) => "positive-adjective",
+            Self::Adjective(Polarity::Negative) => "negative-adjective",
+            Self::Adjective(Polarity::Neutral) => "neutral-adjective",
+            Self::Operator => "operator",
+            Self::Keyword => "keyword",
+            Self::StageDirection => "stage-direction",
+            Self::Comment => "comment",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for HighlightedRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{:?}", self.tag.name(), self.range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use expect_test::{expect, Expect};
+
+    fn check(input: &str, expected: Expect) {
+        let highlights: String = highlight(&parse(input, true).syntax_node())
+            .iter()
+            .map(|highlight| {
+                let text = &input[highlight.range];
+                format!("{} {:?}\n", highlight, text)
+            })
+            .collect();
+
+        expected.assert_eq(&highlights);
+    }
+
+    #[test]
+    fn highlight_play() {
+        check(
+            "Title.
+Romeo, a man.
+[Enter Romeo]
+Romeo: You are the sum of a big cat and a pig. Speak your mind!
+",
+            expect![[r#"
+comment@0..6 "Title."
+character@7..12 "Romeo"
+comment@12..20 ", a man."
+stage-direction@21..27 "[Enter"
+character@28..33 "Romeo"
+stage-direction@33..34 "]"
+character@35..40 "Romeo"
+keyword@42..45 "You"
+keyword@46..49 "are"
+keyword@50..53 "the"
+operator@54..57 "sum"
+keyword@58..60 "of"
+keyword@61..62 "a"
+neutral-adjective@63..66 "big"
+neutral-noun@67..70 "cat"
+keyword@71..74 "and"
+keyword@75..76 "a"
+negative-noun@77..80 "pig"
+keyword@82..87 "Speak"
+keyword@88..92 "your"
+keyword@93..97 "mind"
+"#]],
+        );
+    }
+
+    #[test]
+    fn highlight_errors() {
+        check(
+            "Title.
+Romeo: You are foo a cat.
+",
+            expect![[r#"
+comment@0..6 "Title."
+character@7..12 "Romeo"
+keyword@14..17 "You"
+keyword@18..21 "are"
+error@22..32 "foo a cat."
+"#]],
+        );
+    }
+}
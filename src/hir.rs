@@ -1,28 +1,210 @@
 mod database;
 
 use crate::ast;
-pub(crate) use database::Database;
+pub(crate) use database::{ConstError, Database};
 use la_arena::Idx;
+use std::fmt::Write;
 
-pub(crate) fn lower(ast: ast::Root) -> (Vec<Item>, Database) {
-    let mut db = Database::default();
-    let items = ast.items().map(|item| db.lower_item(item)).collect();
-    (items, db)
+pub(crate) fn lower(ast: &ast::Root, db: &mut Database) -> Vec<Item> {
+    ast.items().map(|item| db.lower_item(item)).collect()
 }
 
+/// Checks whether lowering had to fill in any `Missing` placeholders, which
+/// happens when the source contains syntax errors.
+pub(crate) fn has_missing(items: &[Item], db: &Database) -> bool {
+    items.iter().any(|item| match item {
+        Item::CharacterDef { character } => character.is_none(),
+        Item::StageDirection { direction, .. } => matches!(direction, Direction::Missing),
+        Item::Dialog {
+            character,
+            sentences,
+        } => {
+            character.is_none()
+                || sentences
+                    .iter()
+                    .any(|sentence| sentence_has_missing(sentence, db))
+        }
+        Item::Act { number } | Item::Scene { number } => number.is_none(),
+    })
+}
+
+fn sentence_has_missing(sentence: &Sentence, db: &Database) -> bool {
+    match sentence {
+        Sentence::Statement { expr } | Sentence::Push { expr } => db.expr_has_missing(expr),
+        Sentence::Question {
+            lhs,
+            comparison,
+            rhs,
+            ..
+        } => {
+            matches!(comparison, Comparison::Missing)
+                || db.expr_has_missing(lhs)
+                || db.expr_has_missing(rhs)
+        }
+        Sentence::Conditional { answer, sentence } => {
+            answer.is_none()
+                || sentence
+                    .as_ref()
+                    .is_none_or(|sentence| sentence_has_missing(sentence, db))
+        }
+        Sentence::Goto { scene } => scene.is_none(),
+        Sentence::Vocative { .. }
+        | Sentence::Pop
+        | Sentence::IntOutput
+        | Sentence::CharOutput
+        | Sentence::IntInput
+        | Sentence::CharInput => false,
+    }
+}
+
+/// Renders the items one per line, with their sentences indented beneath
+/// them, for inspecting what lowering produced.
+pub(crate) fn debug(items: &[Item], db: &Database) -> String {
+    let name = |character: &Option<CharacterIdx>| match character {
+        Some(character) => db[*character].name.as_str(),
+        None => "<missing>",
+    };
+
+    let mut s = String::new();
+
+    for item in items {
+        match item {
+            Item::CharacterDef { character } => {
+                writeln!(s, "CharacterDef {}", name(character)).unwrap();
+            }
+            Item::StageDirection {
+                characters,
+                direction,
+            } => {
+                write!(s, "StageDirection {:?}", direction).unwrap();
+                for character in characters {
+                    write!(s, " {}", db[*character].name).unwrap();
+                }
+                s.push('\n');
+            }
+            Item::Dialog {
+                character,
+                sentences,
+            } => {
+                writeln!(s, "Dialog {}", name(character)).unwrap();
+                for sentence in sentences {
+                    s.push_str("  ");
+                    debug_sentence(&mut s, sentence, db);
+                    s.push('\n');
+                }
+            }
+            Item::Act { number } => writeln!(s, "Act {:?}", number).unwrap(),
+            Item::Scene { number } => writeln!(s, "Scene {:?}", number).unwrap(),
+        }
+    }
+
+    s
+}
+
+fn debug_sentence(s: &mut String, sentence: &Sentence, db: &Database) {
+    match sentence {
+        Sentence::Vocative { character } => write!(s, "Vocative {}", db[*character].name).unwrap(),
+        Sentence::Statement { expr } => {
+            s.push_str("Statement ");
+            debug_expr(s, expr, db);
+        }
+        Sentence::Question {
+            lhs,
+            comparison,
+            negated,
+            rhs,
+        } => {
+            s.push_str("Question ");
+            if *negated {
+                s.push_str("Not ");
+            }
+            write!(s, "{:?}(", comparison).unwrap();
+            debug_expr(s, lhs, db);
+            s.push_str(", ");
+            debug_expr(s, rhs, db);
+            s.push(')');
+        }
+        Sentence::Conditional { answer, sentence } => {
+            write!(s, "Conditional {:?} ", answer).unwrap();
+            match sentence {
+                Some(sentence) => debug_sentence(s, sentence, db),
+                None => s.push_str("<missing>"),
+            }
+        }
+        Sentence::Goto { scene } => write!(s, "Goto {:?}", scene).unwrap(),
+        Sentence::Push { expr } => {
+            s.push_str("Push ");
+            debug_expr(s, expr, db);
+        }
+        Sentence::Pop => s.push_str("Pop"),
+        Sentence::IntOutput => s.push_str("IntOutput"),
+        Sentence::CharOutput => s.push_str("CharOutput"),
+        Sentence::IntInput => s.push_str("IntInput"),
+        Sentence::CharInput => s.push_str("CharInput"),
+    }
+}
+
+fn debug_expr(s: &mut String, expr: &Expr, db: &Database) {
+    match expr {
+        Expr::Noun { adjectives, noun } => {
+            s.push('(');
+            for adjective in adjectives {
+                write!(s, "{:?} ", adjective).unwrap();
+            }
+            write!(s, "{:?})", noun).unwrap();
+        }
+        Expr::Bin {
+            operation,
+            lhs,
+            rhs,
+        } => {
+            write!(s, "{:?}(", operation).unwrap();
+            debug_expr(s, &db[*lhs], db);
+            s.push_str(", ");
+            debug_expr(s, &db[*rhs], db);
+            s.push(')');
+        }
+        Expr::Unary { operation, operand } => {
+            write!(s, "{:?}(", operation).unwrap();
+            debug_expr(s, &db[*operand], db);
+            s.push(')');
+        }
+        Expr::Nothing => s.push_str("Nothing"),
+        Expr::Character(character) => s.push_str(&db[*character].name),
+        Expr::Speaker => s.push_str("Speaker"),
+        Expr::Listener => s.push_str("Listener"),
+        Expr::Missing => s.push_str("Missing"),
+    }
+}
+
+/// Characters are `None` when their name is missing from the source.
 #[derive(Debug)]
 pub(crate) enum Item {
     CharacterDef {
-        character: String,
+        character: Option<CharacterIdx>,
     },
     StageDirection {
-        characters: Vec<String>,
+        characters: Vec<CharacterIdx>,
         direction: Direction,
     },
     Dialog {
-        character: String,
+        character: Option<CharacterIdx>,
         sentences: Vec<Sentence>,
     },
+    /// Numbers are `None` when they are missing from the source.
+    Act {
+        number: Option<u32>,
+    },
+    Scene {
+        number: Option<u32>,
+    },
+}
+
+pub(crate) type CharacterIdx = Idx<Character>;
+
+#[derive(Debug)]
+pub(crate) struct Character {
+    pub(crate) name: String,
 }
 
 #[derive(Debug)]
@@ -30,13 +212,53 @@ pub(crate) enum Direction {
     Enter,
     Exit,
     Exeunt,
+    Missing,
 }
 
 #[derive(Debug)]
 pub(crate) enum Sentence {
-    Statement { expr: Expr },
+    Vocative {
+        character: CharacterIdx,
+    },
+    Statement {
+        expr: Expr,
+    },
+    /// Asks whether `lhs` compares to `rhs` the given way, and remembers the
+    /// answer for the next conditional.
+    Question {
+        lhs: Expr,
+        comparison: Comparison,
+        negated: bool,
+        rhs: Expr,
+    },
+    /// A sentence that only happens if the last question was answered
+    /// `answer`.
+    Conditional {
+        answer: Option<bool>,
+        sentence: Option<Box<Sentence>>,
+    },
+    /// Carries on from the start of a scene in the current act.
+    Goto {
+        scene: Option<u32>,
+    },
+    /// Puts a value on the listener’s stack.
+    Push {
+        expr: Expr,
+    },
+    /// Takes the listener’s value off the top of their stack.
+    Pop,
     IntOutput,
     CharOutput,
+    IntInput,
+    CharInput,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Comparison {
+    Greater,
+    Less,
+    Equal,
+    Missing,
 }
 
 type ExprIdx = Idx<Expr>;
@@ -52,7 +274,17 @@ pub(crate) enum Expr {
         lhs: ExprIdx,
         rhs: ExprIdx,
     },
+    Unary {
+        operation: UnaryOperation,
+        operand: ExprIdx,
+    },
     Nothing,
+    Character(CharacterIdx),
+    /// Whoever is speaking, called ‘I’ or ‘myself’.
+    Speaker,
+    /// Whoever is spoken to, called ‘you’ or ‘thyself’.
+    Listener,
+    Missing,
 }
 
 #[derive(Debug)]
@@ -69,7 +301,7 @@ pub(crate) enum Noun {
     Neutral,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum Operation {
     Remainder,
     Difference,
@@ -77,3 +309,197 @@ pub(crate) enum Operation {
     Product,
     Sum,
 }
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum UnaryOperation {
+    Twice,
+    Square,
+    Cube,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use expect_test::expect;
+
+    fn lower_str(input: &str) -> (Vec<Item>, Database) {
+        let root = ast::Root::cast(parse(input, false).syntax_node()).unwrap();
+        let mut db = Database::default();
+        let items = lower(&root, &mut db);
+
+        (items, db)
+    }
+
+    #[test]
+    fn characters_resolve_case_insensitively() {
+        let (items, db) =
+            lower_str("Romeo, a test. [Enter ROMEO and juliet] romeo: Open your heart!");
+
+        let romeo = match items[0] {
+            Item::CharacterDef { character } => character.unwrap(),
+            _ => unreachable!(),
+        };
+
+        match &items[1] {
+            Item::StageDirection { characters, .. } => assert_eq!(characters[0], romeo),
+            _ => unreachable!(),
+        }
+
+        match &items[2] {
+            Item::Dialog { character, .. } => assert_eq!(*character, Some(romeo)),
+            _ => unreachable!(),
+        }
+
+        assert_eq!(db.characters().count(), 2);
+    }
+
+    #[test]
+    fn character_keeps_declared_spelling() {
+        let (_, db) = lower_str("Lady Macbeth, a test. [Enter LADY MACBETH]");
+        let (_, character) = db.characters().next().unwrap();
+
+        assert_eq!(character.name, "Lady Macbeth");
+    }
+
+    #[test]
+    fn characters_persist_across_lowerings() {
+        let mut db = Database::default();
+
+        let root = ast::Root::cast(parse("Puck, a test.", false).syntax_node()).unwrap();
+        let first = lower(&root, &mut db);
+        let root = ast::Root::cast(parse("[Enter puck]", false).syntax_node()).unwrap();
+        let second = lower(&root, &mut db);
+
+        let puck = match first[0] {
+            Item::CharacterDef { character } => character.unwrap(),
+            _ => unreachable!(),
+        };
+
+        match &second[0] {
+            Item::StageDirection { characters, .. } => assert_eq!(characters, &[puck]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn lower_missing_expr() {
+        let (items, db) = lower_str("Juliet: You are a fine.");
+
+        match &items[0] {
+            Item::Dialog { sentences, .. } => match &sentences[0] {
+                Sentence::Statement { expr } => assert!(matches!(expr, Expr::Missing)),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        assert!(has_missing(&items, &db));
+    }
+
+    #[test]
+    fn lower_missing_lhs_keeps_rhs() {
+        let (items, db) = lower_str("Juliet: You are the sum of and a cat.");
+
+        let (lhs, rhs) = match &items[0] {
+            Item::Dialog { sentences, .. } => match &sentences[0] {
+                Sentence::Statement {
+                    expr: Expr::Bin { lhs, rhs, .. },
+                } => (*lhs, *rhs),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        assert!(matches!(db[lhs], Expr::Missing));
+        assert!(matches!(db[rhs], Expr::Noun { .. }));
+    }
+
+    #[test]
+    fn lower_missing_direction() {
+        let (items, db) = lower_str("[Romeo]");
+
+        match &items[0] {
+            Item::StageDirection { direction, .. } => {
+                assert!(matches!(direction, Direction::Missing))
+            }
+            _ => unreachable!(),
+        }
+
+        assert!(has_missing(&items, &db));
+    }
+
+    #[test]
+    fn valid_program_has_nothing_missing() {
+        let (items, db) = lower_str("Romeo, a test. Juliet: You are the sum of a cat and a pig.");
+
+        assert!(!has_missing(&items, &db));
+    }
+
+    #[test]
+    fn debug_items() {
+        let (items, db) = lower_str(
+            "Romeo, a test. [Enter Romeo and Juliet] Romeo: You are the sum of a big cat and nothing. Speak your mind!",
+        );
+
+        expect![[r#"
+CharacterDef Romeo
+StageDirection Enter Romeo Juliet
+Dialog Romeo
+  Statement Sum((Neutral Neutral), Nothing)
+  CharOutput
+"#]]
+        .assert_eq(&debug(&items, &db));
+    }
+
+    #[test]
+    fn debug_unary_exprs() {
+        let (items, db) =
+            lower_str("Romeo: You are twice the square of the sum of a cat and the cube of a pig.");
+
+        expect![[r#"
+Dialog Romeo
+  Statement Twice(Square(Sum((Neutral), Cube((Negative)))))
+"#]]
+        .assert_eq(&debug(&items, &db));
+    }
+
+    #[test]
+    fn debug_character_references() {
+        let (items, db) = lower_str(
+            "Romeo: Juliet, you are the sum of Juliet and the difference between me and thee.",
+        );
+
+        expect![[r#"
+Dialog Romeo
+  Vocative Juliet
+  Statement Sum(Juliet, Difference(Speaker, Listener))
+"#]]
+        .assert_eq(&debug(&items, &db));
+    }
+
+    #[test]
+    fn debug_control_flow() {
+        let (items, db) = lower_str(
+            "Act I: A loop. Scene II: The top. Romeo: Remember me. Are you not better than nothing? If so, let us return to scene II. Recall thy sins.",
+        );
+
+        expect![[r#"
+Act Some(1)
+Scene Some(2)
+Dialog Romeo
+  Push Speaker
+  Question Not Greater(Listener, Nothing)
+  Conditional Some(true) Goto Some(2)
+  Pop
+"#]]
+        .assert_eq(&debug(&items, &db));
+    }
+
+    #[test]
+    fn lower_missing_scene_number() {
+        let (items, db) = lower_str("Romeo: Let us return to scene nothing.");
+
+        assert!(has_missing(&items, &db));
+    }
+}
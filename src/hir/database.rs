@@ -1,73 +1,261 @@
 use la_arena::Arena;
 
-use super::{Adjective, Direction, Expr, ExprIdx, Item, Noun, Operation, Sentence};
+use super::{
+    Adjective, Character, CharacterIdx, Comparison, Direction, Expr, ExprIdx, Item, Noun,
+    Operation, Sentence, UnaryOperation,
+};
 use crate::ast;
+use std::collections::HashMap;
 use std::ops::Index;
 
 #[derive(Debug, Default)]
 pub(crate) struct Database {
     exprs: Arena<Expr>,
+    characters: Arena<Character>,
+    character_idxs: HashMap<String, CharacterIdx>,
 }
 
 impl Database {
     pub(crate) fn lower_item(&mut self, ast: ast::Item) -> Item {
         match ast {
             ast::Item::CharacterDef(character_def) => Item::CharacterDef {
-                character: character_def.character().unwrap(),
+                character: character_def
+                    .character()
+                    .map(|character| self.declare_character(character.text().to_string())),
             },
             ast::Item::StageDirection(stage_direction) => Item::StageDirection {
-                characters: stage_direction.characters().collect(),
-                direction: match stage_direction.direction().unwrap() {
-                    ast::Direction::Enter => Direction::Enter,
-                    ast::Direction::Exit => Direction::Exit,
-                    ast::Direction::Exeunt => Direction::Exeunt,
+                characters: stage_direction
+                    .characters()
+                    .map(|character| self.intern_character(character.text().to_string()))
+                    .collect(),
+                direction: match stage_direction.direction() {
+                    Some(ast::Direction::Enter) => Direction::Enter,
+                    Some(ast::Direction::Exit) => Direction::Exit,
+                    Some(ast::Direction::Exeunt) => Direction::Exeunt,
+                    None => Direction::Missing,
                 },
             },
             ast::Item::Dialog(dialog) => Item::Dialog {
-                character: dialog.character().unwrap(),
+                character: dialog
+                    .character()
+                    .map(|character| self.intern_character(character.text().to_string())),
                 sentences: dialog
                     .sentences()
                     .map(|sentence| self.lower_sentence(sentence))
                     .collect(),
             },
+            ast::Item::Act(heading) => Item::Act {
+                number: heading.number(),
+            },
+            ast::Item::Scene(heading) => Item::Scene {
+                number: heading.number(),
+            },
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn characters(&self) -> impl Iterator<Item = (CharacterIdx, &Character)> {
+        self.characters.iter()
+    }
+
+    /// Looks up a character by name, ignoring case like the lexer does.
+    pub(crate) fn find_character(&self, name: &str) -> Option<CharacterIdx> {
+        self.character_idxs.get(&name.to_lowercase()).copied()
+    }
+
+    fn intern_character(&mut self, name: String) -> CharacterIdx {
+        if let Some(idx) = self.find_character(&name) {
+            return idx;
         }
+
+        let key = name.to_lowercase();
+        let idx = self.characters.alloc(Character { name });
+        self.character_idxs.insert(key, idx);
+
+        idx
+    }
+
+    /// Interns a character and makes its declared spelling canonical, even if
+    /// it was mentioned under a different spelling first.
+    fn declare_character(&mut self, name: String) -> CharacterIdx {
+        let idx = self.intern_character(name.clone());
+        self.characters[idx].name = name;
+
+        idx
     }
 
     fn lower_sentence(&mut self, ast: ast::Sentence) -> Sentence {
         match ast {
+            ast::Sentence::Vocative(vocative) => Sentence::Vocative {
+                character: self.intern_character(vocative.character().unwrap().text().to_string()),
+            },
             ast::Sentence::Statement(statement) => Sentence::Statement {
-                expr: self.lower_expr(statement.expr().unwrap()),
+                expr: self.lower_expr(statement.expr()),
+            },
+            ast::Sentence::Query(query) => Sentence::Question {
+                lhs: self.lower_expr(query.lhs()),
+                comparison: match query.comparison() {
+                    Some(ast::Comparison::Greater) => Comparison::Greater,
+                    Some(ast::Comparison::Less) => Comparison::Less,
+                    Some(ast::Comparison::Equal) => Comparison::Equal,
+                    None => Comparison::Missing,
+                },
+                negated: query.is_negated(),
+                rhs: self.lower_expr(query.rhs()),
+            },
+            ast::Sentence::Conditional(conditional) => Sentence::Conditional {
+                answer: conditional.answer(),
+                sentence: conditional
+                    .sentence()
+                    .map(|sentence| Box::new(self.lower_sentence(sentence))),
+            },
+            ast::Sentence::Goto(goto) => Sentence::Goto {
+                scene: goto.scene(),
+            },
+            ast::Sentence::Push(push) => Sentence::Push {
+                expr: self.lower_expr(push.expr()),
             },
+            ast::Sentence::Pop(_) => Sentence::Pop,
             ast::Sentence::IntOutput(_) => Sentence::IntOutput,
             ast::Sentence::CharOutput(_) => Sentence::CharOutput,
+            ast::Sentence::IntInput(_) => Sentence::IntInput,
+            ast::Sentence::CharInput(_) => Sentence::CharInput,
         }
     }
 
-    fn lower_expr(&mut self, ast: ast::Expr) -> Expr {
+    fn lower_expr(&mut self, ast: Option<ast::Expr>) -> Expr {
+        let ast = match ast {
+            Some(ast) => ast,
+            None => return Expr::Missing,
+        };
+
         match ast {
-            ast::Expr::Noun(noun_expr) => Expr::Noun {
-                adjectives: noun_expr
-                    .adjectives()
-                    .map(|adjective| self.lower_adjective(adjective))
-                    .collect(),
-                noun: self.lower_noun(noun_expr.noun().unwrap()),
+            ast::Expr::Noun(noun_expr) => match noun_expr.noun() {
+                Some(noun) => Expr::Noun {
+                    adjectives: noun_expr
+                        .adjectives()
+                        .map(|adjective| self.lower_adjective(adjective))
+                        .collect(),
+                    noun: self.lower_noun(noun),
+                },
+                None => Expr::Missing,
             },
             ast::Expr::Bin(bin) => {
-                let lhs = self.lower_expr(bin.lhs().unwrap());
-                let rhs = self.lower_expr(bin.rhs().unwrap());
+                let operation = match bin.operation() {
+                    Some(ast::Operation::Difference) => Operation::Difference,
+                    Some(ast::Operation::Quotient) => Operation::Quotient,
+                    Some(ast::Operation::Product) => Operation::Product,
+                    Some(ast::Operation::Sum) => Operation::Sum,
+                    Some(ast::Operation::Remainder) => Operation::Remainder,
+                    None => return Expr::Missing,
+                };
+                let lhs = self.lower_expr(bin.lhs());
+                let rhs = self.lower_expr(bin.rhs());
                 Expr::Bin {
-                    operation: match bin.operation().unwrap() {
-                        ast::Operation::Difference => Operation::Difference,
-                        ast::Operation::Quotient => Operation::Quotient,
-                        ast::Operation::Product => Operation::Product,
-                        ast::Operation::Sum => Operation::Sum,
-                        ast::Operation::Remainder => Operation::Remainder,
-                    },
+                    operation,
                     lhs: self.exprs.alloc(lhs),
                     rhs: self.exprs.alloc(rhs),
                 }
             }
+            ast::Expr::Unary(unary) => {
+                let operation = match unary.operation() {
+                    Some(ast::UnaryOperation::Twice) => UnaryOperation::Twice,
+                    Some(ast::UnaryOperation::Square) => UnaryOperation::Square,
+                    Some(ast::UnaryOperation::Cube) => UnaryOperation::Cube,
+                    None => return Expr::Missing,
+                };
+                let operand = self.lower_expr(unary.operand());
+                Expr::Unary {
+                    operation,
+                    operand: self.exprs.alloc(operand),
+                }
+            }
             ast::Expr::Nothing(_) => Expr::Nothing,
+            ast::Expr::Character(character_expr) => match character_expr.character() {
+                Some(character) => {
+                    Expr::Character(self.intern_character(character.text().to_string()))
+                }
+                None => Expr::Missing,
+            },
+            ast::Expr::Pronoun(pronoun) => match pronoun.person() {
+                Some(ast::Person::First) => Expr::Speaker,
+                Some(ast::Person::Second) => Expr::Listener,
+                None => Expr::Missing,
+            },
+        }
+    }
+
+    pub(crate) fn expr_count(&self) -> usize {
+        self.exprs.len()
+    }
+
+    /// Folds an expression down to its value, failing rather than panicking
+    /// on arithmetic the evaluator couldn’t perform either.
+    pub(crate) fn constant_value(&self, expr: &Expr) -> Result<i64, ConstError> {
+        match expr {
+            Expr::Noun { adjectives, noun } => {
+                let value = match noun {
+                    Noun::Positive | Noun::Neutral => 1,
+                    Noun::Negative => -1,
+                };
+
+                2_i64
+                    .checked_pow(adjectives.len() as u32)
+                    .map(|power| value * power)
+                    .ok_or(ConstError::Overflow)
+            }
+            Expr::Bin {
+                operation,
+                lhs,
+                rhs,
+            } => {
+                let lhs = self.constant_value(&self[*lhs]);
+                let rhs = self.constant_value(&self[*rhs]);
+
+                // Dividing by nothing is wrong whatever the value of a
+                // character being divided.
+                if rhs == Ok(0) && matches!(operation, Operation::Quotient | Operation::Remainder) {
+                    return Err(ConstError::DivisionByZero);
+                }
+                let (lhs, rhs) = (lhs?, rhs?);
+
+                match operation {
+                    Operation::Remainder => lhs.checked_rem(rhs),
+                    Operation::Difference => lhs.checked_sub(rhs),
+                    Operation::Quotient => lhs.checked_div(rhs),
+                    Operation::Product => lhs.checked_mul(rhs),
+                    Operation::Sum => lhs.checked_add(rhs),
+                }
+                .ok_or(ConstError::Overflow)
+            }
+            Expr::Unary { operation, operand } => {
+                let operand = self.constant_value(&self[*operand])?;
+
+                match operation {
+                    UnaryOperation::Twice => operand.checked_mul(2),
+                    UnaryOperation::Square => operand.checked_mul(operand),
+                    UnaryOperation::Cube => operand.checked_pow(3),
+                }
+                .ok_or(ConstError::Overflow)
+            }
+            Expr::Nothing => Ok(0),
+            Expr::Character(_) | Expr::Speaker | Expr::Listener => Err(ConstError::NotConstant),
+            Expr::Missing => Err(ConstError::Missing),
+        }
+    }
+
+    pub(crate) fn expr_has_missing(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Bin { lhs, rhs, .. } => {
+                self.expr_has_missing(&self[*lhs]) || self.expr_has_missing(&self[*rhs])
+            }
+            Expr::Unary { operand, .. } => self.expr_has_missing(&self[*operand]),
+            Expr::Noun { .. }
+            | Expr::Nothing
+            | Expr::Character(_)
+            | Expr::Speaker
+            | Expr::Listener => false,
+            Expr::Missing => true,
         }
     }
 
@@ -88,6 +276,16 @@ impl Database {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ConstError {
+    DivisionByZero,
+    Overflow,
+    /// The value depends on a character’s, which is only known once the play
+    /// is running.
+    NotConstant,
+    Missing,
+}
+
 impl Index<ExprIdx> for Database {
     type Output = Expr;
 
@@ -95,3 +293,11 @@ impl Index<ExprIdx> for Database {
         &self.exprs[index]
     }
 }
+
+impl Index<CharacterIdx> for Database {
+    type Output = Character;
+
+    fn index(&self, index: CharacterIdx) -> &Self::Output {
+        &self.characters[index]
+    }
+}
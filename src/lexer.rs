@@ -1,6 +1,8 @@
 use logos::Logos;
 use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive as _;
 use std::convert::TryFrom;
+use std::fmt;
 use std::ops::Range as StdRange;
 use text_size::{TextRange, TextSize};
 
@@ -20,8 +22,19 @@ impl<'a> Iterator for Lexer<'a> {
     type Item = Token<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let syntax_kind = self.inner.next()?;
+        let mut syntax_kind = self.inner.next()?;
         let slice = self.inner.slice();
+
+        // When a longer word fails to match, logos can give the word it
+        // falls back to the kind of a shorter one, so that the ‘your’ of
+        // ‘yoursel’ comes out as ‘you’.
+        let words = syntax_kind.words();
+        if !words.is_empty() && !words.iter().any(|word| word.eq_ignore_ascii_case(slice)) {
+            syntax_kind = vocabulary()
+                .find(|(_, word)| word.eq_ignore_ascii_case(slice))
+                .map_or(SyntaxKind::Error, |(kind, _)| kind);
+        }
+
         let range = {
             let StdRange { start, end } = self.inner.span();
             let start = TextSize::try_from(start).unwrap();
@@ -45,223 +58,189 @@ pub(crate) struct Token<'a> {
     pub(crate) range: TextRange,
 }
 
-#[derive(Logos, Debug, PartialEq, Eq, FromPrimitive, ToPrimitive, Clone, Copy, PartialOrd, Ord)]
-pub(crate) enum SyntaxKind {
-    Root,
-
-    CharacterDef,
-
-    Comment,
-
-    Skip,
-
-    StageDirection,
-
-    Dialog,
-
-    NounExpr,
-
-    BinExpr,
-
-    NothingExpr,
-
-    Statement,
-
-    IntOutput,
-
-    CharOutput,
-
-    #[regex("(?i)(am|are|art|be|is)")]
-    Be,
-
-    #[regex("(?i)(a|an|the)")]
-    Article,
-
-    #[regex("(?i)(i|me)")]
-    FirstPerson,
-
-    #[regex("(?i)(myself)")]
-    FirstPersonReflexive,
-
-    #[regex("(?i)(mine|my)")]
-    FirstPersonPossessive,
-
-    #[regex("(?i)(thee|thou|you)")]
-    SecondPerson,
-
-    #[regex("(?i)(thyself|yourself)")]
-    SecondPersonReflexive,
-
-    #[regex("(?i)(thine|thy|your)")]
-    SecondPersonPossessive,
-
-    #[regex("(?i)(his|her|its|their)")]
-    ThirdPersonPossessive,
-
-    #[regex("(?i)(better|bigger|fresher|friendlier|nicer|jollier)")]
-    PositiveComparative,
-
-    #[regex("(?i)(punier|smaller|worse)")]
-    NegativeComparative,
-
-    #[regex("(?i)(more)")]
-    More,
-
-    #[regex("(?i)(than)")]
-    Than,
-
-    #[regex("(?i)(as)")]
-    As,
-
-    #[regex("(?i)(and)")]
-    And,
-
-    #[regex("(?i)(to)")]
-    To,
-
-    #[regex("(?i)(of)")]
-    Of,
-
-    #[regex("(?i)(if)")]
-    If,
-
-    #[regex("(?i)(not)")]
-    Not,
-
-    #[regex("(?i)(so)")]
-    So,
-
-    #[regex("(?i)(proceed|return)")]
-    Jump,
-
-    #[regex("(?i)(let)")]
-    Let,
-
-    #[regex("(?i)(we)")]
-    We,
-
-    #[regex("(?i)(us)")]
-    Us,
-
-    #[regex("(?i)(shall|must)")]
-    Must,
-
-    #[regex("(?i)(difference)")]
-    Difference,
-
-    #[regex("(?i)(product)")]
-    Product,
-
-    #[regex("(?i)(quotient)")]
-    Quotient,
-
-    #[regex("(?i)(sum)")]
-    Sum,
-
-    #[regex("(?i)(remainder)")]
-    Remainder,
-
-    #[regex("(?i)(cube)")]
-    Cube,
-
-    #[regex("(?i)(square)")]
-    Square,
-
-    #[regex("(?i)(square root)")]
-    SquareRoot,
-
-    #[regex("(?i)(factorial)")]
-    Factorial,
-
-    #[regex("(?i)(twice)")]
-    Twice,
-
-    #[regex("(?i)(between)")]
-    Between,
-
-    #[regex("(?i)(bad|cowardly|cursed|damned|dirty|disgusting|distasteful|dusty|evil|fat|fat-kidneyed|fatherless|foul|hairy|half-witted|horrible|horrid|infected|lying|miserable|misused|oozing|rotten|rotten|smelly|snotty|sorry|stinking|stuffed|stupid|vile|villainous|worried)")]
-    NegativeAdjective,
-
-    #[regex("(?i)(big|black|blue|bluest|bottomless|furry|green|hard|huge|large|little|normal|old|purple|red|rural|small|tiny|white|yellow)")]
-    NeutralAdjective,
-
-    #[regex("(?i)(amazing|beautiful|blossoming|bold|brave|charming|clearest|cunning|cute|delicious|embroidered|fair|fine|gentle|golden|good|handsome|happy|healthy|honest|lovely|loving|mighty|noble|peaceful|pretty|prompt|proud|reddest|rich|smooth|sunny|sweet|sweetest|trustworthy|warm)")]
-    PositiveAdjective,
-
-    #[regex("(?i)(hell|bastard|beggar|blister|codpiece|coward|curse|death|devil|draught|famine|flirt-gill|goat|hate|hog|hound|leech|lie|pig|plague|starvation|toad|war|wolf)")]
-    NegativeNoun,
-
-    #[regex("(?i)(animal|aunt|brother|cat|chihuahua|cousin|cow|daughter|door|face|father|fellow|granddaughter|grandfather|grandmother|grandson|hair|hamster|horse|lamp|lantern|mistletoe|moon|morning|mother|nephew|niece|nose|purse|road|roman|sister|sky|son|squirrel|stone wall|thing|town|tree|uncle|wind)")]
-    NeutralNoun,
-
-    #[regex("(?i)(heaven|king|lord|angel|flower|happiness|joy|plum|summer's day|hero|rose|kingdom|pony)")]
-    PositiveNoun,
-
-    #[regex("(?i)(achilles|adonis|adriana|aegeon|aemilia|agamemnon|agrippa|ajax|alonso|andromache|angelo|antiochus|antonio|arthur|autolycus|balthazar|banquo|beatrice|benedick|benvolio|bianca|brabantio|brutus|capulet|cassandra|cassius|christopher sly|cicero|claudio|claudius|cleopatra|cordelia|cornelius|cressida|cymberline|demetrius|desdemona|dionyza|doctor caius|dogberry|don john|don pedro|donalbain|dorcas|duncan|egeus|emilia|escalus|falstaff|fenton|ferdinand|ford|fortinbras|francisca|friar john|friar laurence|gertrude|goneril|hamlet|hecate|hector|helen|helena|hermia|hermonie|hippolyta|horatio|imogen|isabella|john of gaunt|john of lancaster|julia|juliet|julius caesar|king henry|king john|king lear|king richard|lady capulet|lady macbeth|lady macduff|lady montague|lennox|leonato|luciana|lucio|lychorida|lysander|macbeth|macduff|malcolm|mariana|mark antony|mercutio|miranda|mistress ford|mistress overdone|mistress page|montague|mopsa|oberon|octavia|octavius caesar|olivia|ophelia|orlando|orsino|othello|page|pantino|paris|pericles|pinch|polonius|pompeius|portia|priam|prince henry|prospero|proteus|publius|puck|queen elinor|regan|robin|romeo|rosalind|sebastian|shallow|shylock|slender|solinus|stephano|thaisa|the abbot of westminster|the apothecary|the archbishop of canterbury|the duke of milan|the duke of venice|the ghost|theseus|thurio|timon|titania|titus|troilus|tybalt|ulysses|valentine|venus|vincentio|viola)")]
-    Character,
-
-    #[regex("(?i)(nothing|zero)")]
-    Nothing,
-
-    #[regex("(?i)(open)")]
-    Open,
-
-    #[regex("(?i)(speak)")]
-    Speak,
-
-    #[regex("(?i)(listen)")]
-    Listen,
-
-    #[regex("(?i)(heart)")]
-    Heart,
-
-    #[regex("(?i)(mind)")]
-    Mind,
-
-    #[regex("(?i)(remember)")]
-    Remember,
-
-    #[regex("(?i)(recall)")]
-    Recall,
-
-    #[regex("(?i)(scene)")]
-    Scene,
-
-    #[regex("(?i)(act)")]
-    Act,
-
-    #[regex("(?i)(pause)")]
-    Pause,
-
-    #[regex("(?i)(enter)")]
-    Enter,
-
-    #[regex("(?i)(exit)")]
-    Exit,
-
-    #[regex("(?i)(exeunt)")]
-    Exeunt,
-
-    #[token(".")]
-    Period,
-
-    #[token("!")]
-    Exclamation,
-
-    #[token("?")]
-    Question,
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{:?} {:?}", self.kind, self.range, self.text)
+    }
+}
 
-    #[token(",")]
-    Comma,
+/// Declares the syntax kinds, where each kind that is lexed from a fixed
+/// set of words lists them just once, for both the lexer and `words`.
+macro_rules! syntax_kinds {
+    (
+        nodes { $($node:ident,)* }
+        words { $($kind:ident: [$($word:tt),+ $(,)?],)* }
+        $($(#[$($attr:tt)*])* $other:ident,)*
+    ) => {
+        #[derive(
+            Logos, Debug, PartialEq, Eq, FromPrimitive, ToPrimitive, Clone, Copy, PartialOrd, Ord,
+        )]
+        pub(crate) enum SyntaxKind {
+            $($node,)*
+            $($(#[token($word, ignore(ascii_case))])+ $kind,)*
+            $($(#[$($attr)*])* $other,)*
+        }
 
-    #[token(":")]
-    Colon,
+        impl SyntaxKind {
+            /// The words this kind is lexed from, in lowercase.
+            pub(crate) fn words(self) -> &'static [&'static str] {
+                match self {
+                    $(SyntaxKind::$kind => &[$($word),+],)*
+                    $(SyntaxKind::$node)|* $(| SyntaxKind::$other)* => &[],
+                }
+            }
+        }
+    };
+}
 
-    #[token("[")]
-    LBracket,
+syntax_kinds! {
+    nodes {
+        Root,
+        CharacterDef,
+        Comment,
+        Skip,
+        StageDirection,
+        Dialog,
+        NounExpr,
+        BinExpr,
+        UnaryExpr,
+        NothingExpr,
+        CharacterExpr,
+        PronounExpr,
+        Vocative,
+        Statement,
+        Query,
+        Comparison,
+        Conditional,
+        Goto,
+        Push,
+        Pop,
+        IntOutput,
+        CharOutput,
+        IntInput,
+        CharInput,
+        ActHeading,
+        SceneHeading,
+    }
 
-    #[token("]")]
-    RBracket,
+    words {
+        Be: ["am", "are", "art", "be", "is"],
+        Article: ["a", "an", "the"],
+        FirstPerson: ["i", "me"],
+        FirstPersonReflexive: ["myself"],
+        FirstPersonPossessive: ["mine", "my"],
+        SecondPerson: ["thee", "thou", "you"],
+        SecondPersonReflexive: ["thyself", "yourself"],
+        SecondPersonPossessive: ["thine", "thy", "your"],
+        ThirdPersonPossessive: ["his", "her", "its", "their"],
+        PositiveComparative: ["better", "bigger", "fresher", "friendlier", "nicer", "jollier"],
+        NegativeComparative: ["punier", "smaller", "worse"],
+        More: ["more"],
+        Than: ["than"],
+        As: ["as"],
+        And: ["and"],
+        To: ["to"],
+        Of: ["of"],
+        If: ["if"],
+        Not: ["not"],
+        So: ["so"],
+        Jump: ["proceed", "return"],
+        Let: ["let"],
+        We: ["we"],
+        Us: ["us"],
+        Must: ["shall", "must"],
+        Difference: ["difference"],
+        Product: ["product"],
+        Quotient: ["quotient"],
+        Sum: ["sum"],
+        Remainder: ["remainder"],
+        Cube: ["cube"],
+        Square: ["square"],
+        SquareRoot: ["square root"],
+        Factorial: ["factorial"],
+        Twice: ["twice"],
+        Between: ["between"],
+        NegativeAdjective: [
+            "bad", "cowardly", "cursed", "damned", "dirty", "disgusting", "distasteful", "dusty",
+            "evil", "fat", "fat-kidneyed", "fatherless", "foul", "hairy", "half-witted", "horrible",
+            "horrid", "infected", "lying", "miserable", "misused", "oozing", "rotten", "smelly",
+            "snotty", "sorry", "stinking", "stuffed", "stupid", "vile", "villainous", "worried",
+        ],
+        NeutralAdjective: [
+            "big", "black", "blue", "bluest", "bottomless", "furry", "green", "hard", "huge",
+            "large", "little", "normal", "old", "purple", "red", "rural", "small", "tiny", "white",
+            "yellow",
+        ],
+        PositiveAdjective: [
+            "amazing", "beautiful", "blossoming", "bold", "brave", "charming", "clearest",
+            "cunning", "cute", "delicious", "embroidered", "fair", "fine", "gentle", "golden",
+            "good", "handsome", "happy", "healthy", "honest", "lovely", "loving", "mighty", "noble",
+            "peaceful", "pretty", "prompt", "proud", "reddest", "rich", "smooth", "sunny", "sweet",
+            "sweetest", "trustworthy", "warm",
+        ],
+        NegativeNoun: [
+            "hell", "bastard", "beggar", "blister", "codpiece", "coward", "curse", "death", "devil",
+            "draught", "famine", "flirt-gill", "goat", "hate", "hog", "hound", "leech", "lie",
+            "pig", "plague", "starvation", "toad", "war", "wolf",
+        ],
+        NeutralNoun: [
+            "animal", "aunt", "brother", "cat", "chihuahua", "cousin", "cow", "daughter", "door",
+            "face", "father", "fellow", "granddaughter", "grandfather", "grandmother", "grandson",
+            "hair", "hamster", "horse", "lamp", "lantern", "mistletoe", "moon", "morning", "mother",
+            "nephew", "niece", "nose", "purse", "road", "roman", "sister", "sky", "son", "squirrel",
+            "stone wall", "thing", "town", "tree", "uncle", "wind",
+        ],
+        PositiveNoun: [
+            "heaven", "king", "lord", "angel", "flower", "happiness", "joy", "plum", "summer's day",
+            "hero", "rose", "kingdom", "pony",
+        ],
+        Character: [
+            "achilles", "adonis", "adriana", "aegeon", "aemilia", "agamemnon", "agrippa", "ajax",
+            "alonso", "andromache", "angelo", "antiochus", "antonio", "arthur", "autolycus",
+            "balthazar", "banquo", "beatrice", "benedick", "benvolio", "bianca", "brabantio",
+            "brutus", "capulet", "cassandra", "cassius", "christopher sly", "cicero", "claudio",
+            "claudius", "cleopatra", "cordelia", "cornelius", "cressida", "cymberline", "demetrius",
+            "desdemona", "dionyza", "doctor caius", "dogberry", "don john", "don pedro",
+            "donalbain", "dorcas", "duncan", "egeus", "emilia", "escalus", "falstaff", "fenton",
+            "ferdinand", "ford", "fortinbras", "francisca", "friar john", "friar laurence",
+            "gertrude", "goneril", "hamlet", "hecate", "hector", "helen", "helena", "hermia",
+            "hermonie", "hippolyta", "horatio", "imogen", "isabella", "john of gaunt",
+            "john of lancaster", "julia", "juliet", "julius caesar", "king henry", "king john",
+            "king lear", "king richard", "lady capulet", "lady macbeth", "lady macduff",
+            "lady montague", "lennox", "leonato", "luciana", "lucio", "lychorida", "lysander",
+            "macbeth", "macduff", "malcolm", "mariana", "mark antony", "mercutio", "miranda",
+            "mistress ford", "mistress overdone", "mistress page", "montague", "mopsa", "oberon",
+            "octavia", "octavius caesar", "olivia", "ophelia", "orlando", "orsino", "othello",
+            "page", "pantino", "paris", "pericles", "pinch", "polonius", "pompeius", "portia",
+            "priam", "prince henry", "prospero", "proteus", "publius", "puck", "queen elinor",
+            "regan", "robin", "romeo", "rosalind", "sebastian", "shallow", "shylock", "slender",
+            "solinus", "stephano", "thaisa", "the abbot of westminster", "the apothecary",
+            "the archbishop of canterbury", "the duke of milan", "the duke of venice", "the ghost",
+            "theseus", "thurio", "timon", "titania", "titus", "troilus", "tybalt", "ulysses",
+            "valentine", "venus", "vincentio", "viola",
+        ],
+        Nothing: ["nothing", "zero"],
+        Open: ["open"],
+        Speak: ["speak"],
+        Listen: ["listen"],
+        Heart: ["heart"],
+        Mind: ["mind"],
+        Remember: ["remember"],
+        Recall: ["recall"],
+        Scene: ["scene"],
+        Act: ["act"],
+        Pause: ["pause"],
+        Enter: ["enter"],
+        Exit: ["exit"],
+        Exeunt: ["exeunt"],
+        Period: ["."],
+        Exclamation: ["!"],
+        Question: ["?"],
+        Comma: [","],
+        Colon: [":"],
+        LBracket: ["["],
+        RBracket: ["]"],
+    }
 
     #[regex("M*(CM|CD|D?C*)(XC|XL|L?X*)(IX|IV|V?I*)", roman_numeral)]
     RomanNumeral,
@@ -364,15 +343,36 @@ impl SyntaxKind {
             | SyntaxKind::Dialog
             | SyntaxKind::NounExpr
             | SyntaxKind::BinExpr
+            | SyntaxKind::UnaryExpr
             | SyntaxKind::NothingExpr
+            | SyntaxKind::CharacterExpr
+            | SyntaxKind::PronounExpr
+            | SyntaxKind::Vocative
             | SyntaxKind::Statement
+            | SyntaxKind::Query
+            | SyntaxKind::Comparison
+            | SyntaxKind::Conditional
+            | SyntaxKind::Goto
+            | SyntaxKind::Push
+            | SyntaxKind::Pop
             | SyntaxKind::IntOutput
             | SyntaxKind::CharOutput
+            | SyntaxKind::IntInput
+            | SyntaxKind::CharInput
+            | SyntaxKind::ActHeading
+            | SyntaxKind::SceneHeading
             | SyntaxKind::Whitespace => unreachable!(),
         }
     }
 }
 
+/// Every word the lexer knows, along with the kind it is lexed as.
+pub(crate) fn vocabulary() -> impl Iterator<Item = (SyntaxKind, &'static str)> {
+    (0..)
+        .map_while(SyntaxKind::from_u16)
+        .flat_map(|kind| kind.words().iter().map(move |word| (kind, *word)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -449,6 +449,59 @@ mod tests {
         check("theIR", SyntaxKind::ThirdPersonPossessive);
     }
 
+    #[test]
+    fn lex_vocabulary() {
+        for (kind, word) in vocabulary() {
+            check(word, kind);
+        }
+    }
+
+    /// Words next to ones the lexer knows, which it mustn’t take for them.
+    #[test]
+    fn lex_nothing_outside_vocabulary() {
+        for (_, word) in vocabulary() {
+            let near_misses = [
+                format!("{}s", word),
+                format!("x{}", word),
+                word[..word.len() - 1].to_string(),
+                word.replace(' ', "  "),
+            ];
+
+            for near_miss in &near_misses {
+                for token in Lexer::new(near_miss) {
+                    let text = token.text.to_lowercase();
+                    assert!(
+                        token.kind.words().is_empty()
+                            || token.kind.words().contains(&text.as_str()),
+                        "{:?} lexed {:?} as {:?}",
+                        near_miss,
+                        text,
+                        token.kind
+                    );
+                }
+            }
+        }
+    }
+
+    /// Logos can give a word it falls back to, after a longer one fails to
+    /// match, the kind of the longer one’s prefix, so these check that
+    /// `Lexer` looks the word up again.
+    #[test]
+    fn lex_word_after_longer_one_fails() {
+        for (input, kind, text) in [
+            ("yoursel", SyntaxKind::SecondPersonPossessive, "your"),
+            ("cowardl", SyntaxKind::NegativeNoun, "coward"),
+            ("fatherles", SyntaxKind::NeutralNoun, "father"),
+            ("amazin", SyntaxKind::Be, "am"),
+            ("andromach", SyntaxKind::And, "and"),
+            ("arthu", SyntaxKind::Be, "art"),
+            ("isabell", SyntaxKind::Be, "is"),
+        ] {
+            let token = Lexer::new(input).next().unwrap();
+            assert_eq!((token.kind, token.text), (kind, text), "{:?}", input);
+        }
+    }
+
     #[test]
     fn lex_with_spaces() {
         check("square root", SyntaxKind::SquareRoot);
@@ -0,0 +1,68 @@
+use std::convert::TryFrom;
+use text_size::TextSize;
+
+/// Converts byte offsets into lines and columns, for showing positions to
+/// people.
+pub(crate) struct LineIndex {
+    line_starts: Vec<TextSize>,
+}
+
+/// Both start from zero, and the column counts bytes from the start of the
+/// line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LineCol {
+    pub(crate) line: u32,
+    pub(crate) col: u32,
+}
+
+impl LineIndex {
+    pub(crate) fn new(text: &str) -> Self {
+        let line_starts = std::iter::once(TextSize::from(0))
+            .chain(
+                text.match_indices('\n')
+                    .map(|(idx, _)| TextSize::try_from(idx + 1).unwrap()),
+            )
+            .collect();
+
+        Self { line_starts }
+    }
+
+    pub(crate) fn line_col(&self, offset: TextSize) -> LineCol {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+
+        LineCol {
+            line: line as u32,
+            col: u32::from(offset - self.line_starts[line]),
+        }
+    }
+
+    /// Where a line starts, or `None` past the last line.
+    pub(crate) fn line_start(&self, line: u32) -> Option<TextSize> {
+        self.line_starts.get(line as usize).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(text: &str, offset: u32, line: u32, col: u32) {
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_col(offset.into()), LineCol { line, col });
+    }
+
+    #[test]
+    fn first_line() {
+        check("Romeo, a man.", 7, 0, 7);
+    }
+
+    #[test]
+    fn later_line() {
+        check("Title.\nRomeo, a man.\nJuliet, a woman.", 21, 2, 0);
+    }
+
+    #[test]
+    fn end_of_line() {
+        check("Title.\nRomeo", 6, 0, 6);
+    }
+}
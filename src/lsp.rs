@@ -0,0 +1,885 @@
+use crate::analysis::{AnalysisDatabase, HoverKind, OutlineItem, OutlineKind};
+use crate::diagnostic::Diagnostic;
+use crate::highlight::{highlight, Polarity, Tag};
+use crate::line_index::LineIndex;
+use crate::parser::Edit;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition, HoverRequest,
+    InlayHintRequest, PrepareRenameRequest, References, Rename, Request as _,
+    SemanticTokensFullRequest,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+    FoldingRange, FoldingRangeKind, FoldingRangeParams, FoldingRangeProviderCapability,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, InlayHint, InlayHintLabel, InlayHintParams, Location, MarkupContent,
+    MarkupKind, NumberOrString, OneOf, Position, PrepareRenameResponse, PublishDiagnosticsParams,
+    Range, ReferenceParams, RenameOptions, RenameParams, SemanticToken, SemanticTokenModifier,
+    SemanticTokenType, SemanticTokens, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
+    SemanticTokensServerCapabilities, ServerCapabilities, SymbolKind, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use text_size::{TextRange, TextSize};
+
+/// Serves editors over stdin and stdout until they shut the server down.
+pub(crate) fn run() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: Default::default(),
+        })),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: legend(),
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..SemanticTokensOptions::default()
+            },
+        )),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    Server::default().main_loop(&connection)?;
+
+    // The thread writing to stdout only finishes once nothing can send to it.
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
+
+/// The plays open in the editor. Each is analysed incrementally, so only the
+/// items touched by a change are looked at again.
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Uri, AnalysisDatabase>,
+}
+
+impl Server {
+    fn main_loop(&mut self, connection: &Connection) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    connection.sender.send(response.into())?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(uri) = self.handle_notification(notification) {
+                        let params = self.diagnostics(uri);
+                        let notification =
+                            Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+                        connection.sender.send(notification.into())?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: Request) -> Response {
+        match request.method.as_str() {
+            HoverRequest::METHOD => self.respond::<HoverRequest>(request, Self::hover),
+            SemanticTokensFullRequest::METHOD => {
+                self.respond::<SemanticTokensFullRequest>(request, Self::semantic_tokens)
+            }
+            Completion::METHOD => self.respond::<Completion>(request, Self::completion),
+            InlayHintRequest::METHOD => {
+                self.respond::<InlayHintRequest>(request, Self::inlay_hints)
+            }
+            DocumentSymbolRequest::METHOD => {
+                self.respond::<DocumentSymbolRequest>(request, Self::document_symbols)
+            }
+            FoldingRangeRequest::METHOD => {
+                self.respond::<FoldingRangeRequest>(request, Self::folding_ranges)
+            }
+            GotoDefinition::METHOD => self.respond::<GotoDefinition>(request, Self::definition),
+            References::METHOD => self.respond::<References>(request, Self::references),
+            PrepareRenameRequest::METHOD => {
+                self.respond::<PrepareRenameRequest>(request, Self::prepare_rename)
+            }
+            Rename::METHOD => self.try_respond::<Rename>(request, Self::rename),
+            _ => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unknown request {}", request.method),
+            ),
+        }
+    }
+
+    fn respond<R: lsp_types::request::Request>(
+        &mut self,
+        request: Request,
+        handler: impl FnOnce(&mut Self, R::Params) -> R::Result,
+    ) -> Response {
+        self.try_respond::<R>(request, |server, params| Ok(handler(server, params)))
+    }
+
+    /// Like `respond`, for requests that can be refused with a message for
+    /// the user.
+    fn try_respond<R: lsp_types::request::Request>(
+        &mut self,
+        request: Request,
+        handler: impl FnOnce(&mut Self, R::Params) -> Result<R::Result, String>,
+    ) -> Response {
+        let params = match serde_json::from_value(request.params) {
+            Ok(params) => params,
+            Err(error) => {
+                return Response::new_err(
+                    request.id,
+                    ErrorCode::InvalidParams as i32,
+                    error.to_string(),
+                )
+            }
+        };
+
+        match handler(self, params) {
+            Ok(result) => Response::new_ok(request.id, result),
+            Err(message) => Response::new_err(request.id, ErrorCode::RequestFailed as i32, message),
+        }
+    }
+
+    /// Keeps the open plays in sync, giving back the play whose diagnostics
+    /// need publishing again.
+    fn handle_notification(&mut self, notification: Notification) -> Option<Uri> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                let document = params.text_document;
+                self.documents.insert(
+                    document.uri.clone(),
+                    AnalysisDatabase::new(document.text, true),
+                );
+
+                Some(document.uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                let db = self.documents.get_mut(&uri)?;
+
+                for change in params.content_changes {
+                    match change.range {
+                        Some(range) => {
+                            let delete = text_range(db.text(), range);
+                            db.apply_edit(&Edit {
+                                delete,
+                                insert: change.text,
+                            });
+                        }
+                        None => db.set_text(change.text),
+                    }
+                }
+
+                Some(uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                self.documents.remove(&params.text_document.uri);
+
+                // Clears the play’s diagnostics, as it is no longer open.
+                Some(params.text_document.uri)
+            }
+            _ => None,
+        }
+    }
+
+    /// Every parse and validation error in a play. Validation errors are
+    /// shown even when the play doesn’t parse, since they are still right
+    /// about the parts that do.
+    fn diagnostics(&mut self, uri: Uri) -> PublishDiagnosticsParams {
+        let db = match self.documents.get_mut(&uri) {
+            Some(db) => db,
+            None => return PublishDiagnosticsParams::new(uri, Vec::new(), None),
+        };
+
+        let mut diagnostics: Vec<_> = db
+            .parse()
+            .errors
+            .iter()
+            .map(Diagnostic::from_parse_error)
+            .collect();
+        diagnostics.extend(
+            db.validation_errors()
+                .iter()
+                .map(Diagnostic::from_validation_error),
+        );
+
+        let line_index = LineIndex::new(db.text());
+        let diagnostics = diagnostics
+            .into_iter()
+            .map(|diagnostic| lsp_types::Diagnostic {
+                range: range(db.text(), &line_index, diagnostic.range.unwrap_or_default()),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(NumberOrString::String(diagnostic.code.to_string())),
+                source: Some("puck".to_string()),
+                message: diagnostic.message,
+                ..lsp_types::Diagnostic::default()
+            })
+            .collect();
+
+        PublishDiagnosticsParams::new(uri, diagnostics, None)
+    }
+
+    /// The play a position is in, along with the position as an offset.
+    fn locate(
+        &mut self,
+        position: &TextDocumentPositionParams,
+    ) -> Option<(&mut AnalysisDatabase, LineIndex, TextSize)> {
+        let db = self.documents.get_mut(&position.text_document.uri)?;
+        let line_index = LineIndex::new(db.text());
+        let offset = offset(db.text(), &line_index, position.position);
+
+        Some((db, line_index, offset))
+    }
+
+    fn hover(&mut self, params: HoverParams) -> Option<Hover> {
+        let (db, line_index, offset) = self.locate(&params.text_document_position_params)?;
+        let hover = db.hover(offset)?;
+
+        let value = match hover.kind {
+            HoverKind::Value(value) => format!("evaluates to `{}`", value),
+            HoverKind::Listener(name) => format!("refers to {}", name),
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range(db.text(), &line_index, hover.range)),
+        })
+    }
+
+    fn completion(&mut self, params: CompletionParams) -> Option<CompletionResponse> {
+        let (db, _, offset) = self.locate(&params.text_document_position)?;
+
+        let items = db
+            .completions(offset)
+            .into_iter()
+            .map(|completion| {
+                let (kind, group) = completion_kind(completion.tag);
+                CompletionItem {
+                    kind: Some(kind),
+                    detail: Some(completion_detail(completion.tag)),
+                    // Keeps the vocabulary grouped by value rather than mixed
+                    // together alphabetically.
+                    sort_text: Some(format!("{}{}", group, completion.label)),
+                    label: completion.label,
+                    ..CompletionItem::default()
+                }
+            })
+            .collect();
+
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// The value of every constant expression, and who each speaker is
+    /// talking to.
+    fn inlay_hints(&mut self, params: InlayHintParams) -> Option<Vec<InlayHint>> {
+        let db = self.documents.get_mut(&params.text_document.uri)?;
+        let line_index = LineIndex::new(db.text());
+        let visible = text_range(db.text(), params.range);
+
+        let values = db
+            .constant_values()
+            .into_iter()
+            .map(|(range, value)| (range.end(), format!("= {}", value)));
+        let listeners = db
+            .listeners()
+            .into_iter()
+            .map(|(range, listener)| (range.end(), format!("to {}", listener)));
+
+        let mut hints: Vec<_> = values
+            .chain(listeners)
+            .filter(|(offset, _)| visible.contains_inclusive(*offset))
+            .map(|(offset, label)| InlayHint {
+                position: position(db.text(), &line_index, offset),
+                label: InlayHintLabel::String(label),
+                kind: None,
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            })
+            .collect();
+        hints.sort_by_key(|hint| (hint.position.line, hint.position.character));
+
+        Some(hints)
+    }
+
+    fn document_symbols(&mut self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let db = self.documents.get_mut(&params.text_document.uri)?;
+        let outline = db.outline();
+        let line_index = LineIndex::new(db.text());
+
+        Some(DocumentSymbolResponse::Nested(document_symbols(
+            db.text(),
+            &line_index,
+            outline,
+        )))
+    }
+
+    fn folding_ranges(&mut self, params: FoldingRangeParams) -> Option<Vec<FoldingRange>> {
+        let db = self.documents.get_mut(&params.text_document.uri)?;
+        let outline = db.outline();
+        let line_index = LineIndex::new(db.text());
+
+        let mut folding_ranges = Vec::new();
+        let mut sections: Vec<_> = outline.iter().collect();
+        while let Some(section) = sections.pop() {
+            sections.extend(&section.children);
+            if !matches!(section.kind, OutlineKind::Act | OutlineKind::Scene) {
+                continue;
+            }
+
+            let range = range(db.text(), &line_index, section.range);
+            if range.start.line < range.end.line {
+                folding_ranges.push(FoldingRange {
+                    start_line: range.start.line,
+                    end_line: range.end.line,
+                    kind: Some(FoldingRangeKind::Region),
+                    ..FoldingRange::default()
+                });
+            }
+        }
+        folding_ranges.sort_by_key(|folding_range| folding_range.start_line);
+
+        Some(folding_ranges)
+    }
+
+    fn definition(&mut self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let (db, line_index, offset) = self.locate(&position)?;
+        let definition = db.definition(offset)?;
+
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            position.text_document.uri,
+            range(db.text(), &line_index, definition),
+        )))
+    }
+
+    fn references(&mut self, params: ReferenceParams) -> Option<Vec<Location>> {
+        let position = params.text_document_position;
+        let (db, line_index, offset) = self.locate(&position)?;
+
+        let references = db
+            .references(offset, params.context.include_declaration)
+            .into_iter()
+            .map(|reference| {
+                Location::new(
+                    position.text_document.uri.clone(),
+                    range(db.text(), &line_index, reference),
+                )
+            })
+            .collect();
+
+        Some(references)
+    }
+
+    fn prepare_rename(
+        &mut self,
+        params: TextDocumentPositionParams,
+    ) -> Option<PrepareRenameResponse> {
+        let (db, line_index, offset) = self.locate(&params)?;
+        let character = db.character_at(offset)?;
+
+        Some(PrepareRenameResponse::Range(range(
+            db.text(),
+            &line_index,
+            character,
+        )))
+    }
+
+    // `Uri` only looks mutable to clippy, and lsp-types asks for the map.
+    #[allow(clippy::mutable_key_type)]
+    fn rename(&mut self, params: RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+        let (position, new_name) = (params.text_document_position, params.new_name);
+        let (db, line_index, offset) = match self.locate(&position) {
+            Some(located) => located,
+            None => return Ok(None),
+        };
+
+        let edits = db
+            .rename(offset, &new_name)
+            .map_err(|error| error.to_string())?
+            .into_iter()
+            .map(|mention| TextEdit {
+                range: range(db.text(), &line_index, mention),
+                new_text: new_name.clone(),
+            })
+            .collect();
+
+        let changes = HashMap::from([(position.text_document.uri, edits)]);
+        Ok(Some(WorkspaceEdit::new(changes)))
+    }
+
+    fn semantic_tokens(&mut self, params: SemanticTokensParams) -> Option<SemanticTokensResult> {
+        let db = self.documents.get_mut(&params.text_document.uri)?;
+        let root = db.parse().syntax_node();
+        let text = db.text();
+        let line_index = LineIndex::new(text);
+
+        // Each token is placed relative to the one before it.
+        let mut previous = Position::new(0, 0);
+        let data = highlight(&root)
+            .into_iter()
+            .map(|highlight| {
+                let start = position(text, &line_index, highlight.range.start());
+                let end = position(text, &line_index, highlight.range.end());
+                let (token_type, token_modifiers_bitset) = semantic_token_type(highlight.tag);
+
+                let token = SemanticToken {
+                    delta_line: start.line - previous.line,
+                    delta_start: if start.line == previous.line {
+                        start.character - previous.character
+                    } else {
+                        start.character
+                    },
+                    length: end.character - start.character,
+                    token_type,
+                    token_modifiers_bitset,
+                };
+                previous = start;
+
+                token
+            })
+            .collect();
+
+        Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data,
+        }))
+    }
+}
+
+// Editors already have colours for the standard token types, so those are
+// used where they fit, with nouns and adjectives told apart by modifiers.
+
+const TOKEN_TYPES: [&str; 8] = [
+    "variable",
+    "noun",
+    "adjective",
+    "operator",
+    "keyword",
+    "stageDirection",
+    "comment",
+    "error",
+];
+
+const TOKEN_MODIFIERS: [&str; 3] = ["positive", "negative", "neutral"];
+
+fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES
+            .iter()
+            .map(|name| SemanticTokenType::new(name))
+            .collect(),
+        token_modifiers: TOKEN_MODIFIERS
+            .iter()
+            .map(|name| SemanticTokenModifier::new(name))
+            .collect(),
+    }
+}
+
+/// The indices of the token type and modifiers in the legend.
+fn semantic_token_type(tag: Tag) -> (u32, u32) {
+    let polarity = |polarity| match polarity {
+        Polarity::Positive => 1 << 0,
+        Polarity::Negative => 1 << 1,
+        Polarity::Neutral => 1 << 2,
+    };
+
+    match tag {
+        Tag::Character => (0, 0),
+        Tag::Noun(p) => (1, polarity(p)),
+        Tag::Adjective(p) => (2, polarity(p)),
+        Tag::Operator => (3, 0),
+        Tag::Keyword => (4, 0),
+        Tag::StageDirection => (5, 0),
+        Tag::Comment => (6, 0),
+        Tag::Error => (7, 0),
+    }
+}
+
+fn document_symbols(
+    text: &str,
+    line_index: &LineIndex,
+    outline: Vec<OutlineItem>,
+) -> Vec<DocumentSymbol> {
+    outline
+        .into_iter()
+        .map(|item| {
+            let kind = match item.kind {
+                OutlineKind::DramatisPersonae => SymbolKind::NAMESPACE,
+                OutlineKind::Character => SymbolKind::VARIABLE,
+                OutlineKind::Act => SymbolKind::MODULE,
+                OutlineKind::Scene => SymbolKind::FUNCTION,
+            };
+
+            // Only `deprecated` is deprecated, but it still has to be given.
+            #[allow(deprecated)]
+            DocumentSymbol {
+                name: item.name,
+                detail: None,
+                kind,
+                tags: None,
+                deprecated: None,
+                range: range(text, line_index, item.range),
+                selection_range: range(text, line_index, item.selection_range),
+                children: Some(document_symbols(text, line_index, item.children)),
+            }
+        })
+        .collect()
+}
+
+/// The kind of item a completion is shown as, and where its group is
+/// sorted.
+fn completion_kind(tag: Tag) -> (CompletionItemKind, u8) {
+    let polarity = |polarity| match polarity {
+        Polarity::Positive => 3,
+        Polarity::Neutral => 4,
+        Polarity::Negative => 5,
+    };
+
+    match tag {
+        Tag::Character => (CompletionItemKind::VARIABLE, 0),
+        Tag::Keyword | Tag::StageDirection | Tag::Comment | Tag::Error => {
+            (CompletionItemKind::KEYWORD, 1)
+        }
+        Tag::Operator => (CompletionItemKind::OPERATOR, 2),
+        Tag::Noun(p) => (CompletionItemKind::VALUE, polarity(p)),
+        Tag::Adjective(p) => (CompletionItemKind::VALUE, polarity(p)),
+    }
+}
+
+fn completion_detail(tag: Tag) -> String {
+    match tag {
+        Tag::Character => "character".to_string(),
+        Tag::Noun(Polarity::Negative) => "negative noun, −1".to_string(),
+        Tag::Noun(_) => format!("{}, 1", tag.name().replace('-', " ")),
+        Tag::Adjective(_) => format!("{}, doubles", tag.name().replace('-', " ")),
+        _ => tag.name().replace('-', " "),
+    }
+}
+
+// Editors count columns in UTF-16 code units unless told otherwise.
+
+fn position(text: &str, line_index: &LineIndex, offset: TextSize) -> Position {
+    let line_col = line_index.line_col(offset);
+    let end = usize::from(offset);
+    let start = end - line_col.col as usize;
+
+    Position::new(
+        line_col.line,
+        text[start..end].encode_utf16().count() as u32,
+    )
+}
+
+fn range(text: &str, line_index: &LineIndex, range: TextRange) -> Range {
+    Range::new(
+        position(text, line_index, range.start()),
+        position(text, line_index, range.end()),
+    )
+}
+
+/// Positions past the end of a line or of the text are moved back to it.
+fn offset(text: &str, line_index: &LineIndex, position: Position) -> TextSize {
+    let line_start = match line_index.line_start(position.line) {
+        Some(line_start) => line_start,
+        None => return TextSize::of(text),
+    };
+    let line = text[usize::from(line_start)..].split('\n').next().unwrap();
+
+    let mut col = 0;
+    let mut utf16_col = 0;
+    for c in line.chars() {
+        if utf16_col >= position.character {
+            break;
+        }
+        col += c.len_utf8();
+        utf16_col += c.len_utf16() as u32;
+    }
+
+    line_start + TextSize::try_from(col).unwrap()
+}
+
+fn text_range(text: &str, range: Range) -> TextRange {
+    let line_index = LineIndex::new(text);
+    TextRange::new(
+        offset(text, &line_index, range.start),
+        offset(text, &line_index, range.end),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{TextDocumentIdentifier, TextDocumentPositionParams};
+    use std::str::FromStr;
+
+    const PLAY: &str = "Title.
+Romeo, a man. Juliet, a woman.
+[Enter Romeo and Juliet]
+Romeo: You are the sum of a cat and a big cat.
+Juliet: You are nothing.
+";
+
+    fn server() -> (Server, Uri) {
+        let uri = Uri::from_str("file:///play.spl").unwrap();
+        let mut server = Server::default();
+        server
+            .documents
+            .insert(uri.clone(), AnalysisDatabase::new(PLAY.to_string(), true));
+
+        (server, uri)
+    }
+
+    fn hover_at(line: u32, character: u32) -> Option<String> {
+        let (mut server, uri) = server();
+        let hover = server.hover(HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position::new(line, character),
+            },
+            work_done_progress_params: Default::default(),
+        })?;
+
+        match hover.contents {
+            HoverContents::Markup(markup) => Some(markup.value),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn hover_noun_phrases() {
+        assert_eq!(hover_at(3, 29).as_deref(), Some("evaluates to `1`"));
+        assert_eq!(hover_at(3, 40).as_deref(), Some("evaluates to `2`"));
+        assert_eq!(hover_at(3, 17).as_deref(), Some("evaluates to `3`"));
+        assert_eq!(hover_at(1, 3), None);
+    }
+
+    #[test]
+    fn hover_second_person() {
+        assert_eq!(hover_at(3, 8).as_deref(), Some("refers to Juliet"));
+        assert_eq!(hover_at(4, 8).as_deref(), Some("refers to Romeo"));
+    }
+
+    #[test]
+    fn publish_diagnostics_after_changes() {
+        let (mut server, uri) = server();
+        assert!(server.diagnostics(uri.clone()).diagnostics.is_empty());
+
+        let change = serde_json::json!({
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{
+                "range": {
+                    "start": { "line": 2, "character": 12 },
+                    "end": { "line": 2, "character": 23 },
+                },
+                "text": "",
+            }],
+        });
+        let uri = server
+            .handle_notification(Notification::new(
+                DidChangeTextDocument::METHOD.to_string(),
+                change,
+            ))
+            .unwrap();
+
+        let diagnostics = server.diagnostics(uri).diagnostics;
+        let messages: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.range.start.line, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (3, "Romeo has no one on stage to talk to"),
+                (4, "Juliet is not on stage")
+            ]
+        );
+    }
+
+    #[test]
+    fn semantic_tokens_are_relative() {
+        let (mut server, uri) = server();
+        let tokens = match server.semantic_tokens(SemanticTokensParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }) {
+            Some(SemanticTokensResult::Tokens(tokens)) => tokens.data,
+            _ => unreachable!(),
+        };
+
+        let first: Vec<_> = tokens
+            .iter()
+            .take(4)
+            .map(|token| {
+                (
+                    token.delta_line,
+                    token.delta_start,
+                    token.length,
+                    token.token_type,
+                )
+            })
+            .collect();
+        // ‘Title.’, ‘Romeo’, ‘, a man.’ and ‘Juliet’.
+        assert_eq!(
+            first,
+            [(0, 0, 6, 6), (1, 0, 5, 0), (0, 5, 8, 6), (0, 9, 6, 0)]
+        );
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn rename_characters() {
+        let (mut server, uri) = server();
+        let rename = |server: &mut Server, new_name: &str| {
+            server.rename(RenameParams {
+                text_document_position: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                    position: Position::new(2, 18),
+                },
+                new_name: new_name.to_string(),
+                work_done_progress_params: Default::default(),
+            })
+        };
+
+        let changes = rename(&mut server, "Hamlet")
+            .unwrap()
+            .unwrap()
+            .changes
+            .unwrap();
+        let lines: Vec<_> = changes[&uri]
+            .iter()
+            .map(|edit| (edit.range.start.line, edit.new_text.as_str()))
+            .collect();
+        assert_eq!(lines, [(1, "Hamlet"), (2, "Hamlet"), (4, "Hamlet")]);
+
+        assert_eq!(
+            rename(&mut server, "Romeo"),
+            Err("Romeo is already in the play".to_string())
+        );
+    }
+
+    #[test]
+    fn complete_vocabulary_in_groups() {
+        let (mut server, uri) = server();
+        let items = match server.completion(CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position::new(3, 28),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        }) {
+            Some(CompletionResponse::Array(items)) => items,
+            _ => unreachable!(),
+        };
+
+        let pig = items.iter().find(|item| item.label == "pig").unwrap();
+        assert_eq!(pig.detail.as_deref(), Some("negative noun, −1"));
+        assert_eq!(pig.sort_text.as_deref(), Some("5pig"));
+        let big = items.iter().find(|item| item.label == "big").unwrap();
+        assert_eq!(big.detail.as_deref(), Some("neutral adjective, doubles"));
+    }
+
+    #[test]
+    fn inlay_hints_for_values_and_listeners() {
+        let (mut server, uri) = server();
+        let hints = server
+            .inlay_hints(InlayHintParams {
+                text_document: TextDocumentIdentifier { uri },
+                range: Range::new(Position::new(3, 0), Position::new(4, 0)),
+                work_done_progress_params: Default::default(),
+            })
+            .unwrap();
+
+        let hints: Vec<_> = hints
+            .into_iter()
+            .map(|hint| match hint.label {
+                InlayHintLabel::String(label) => (hint.position.character, label),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            hints,
+            [(5, "to Juliet".to_string()), (45, "= 3".to_string())]
+        );
+    }
+
+    #[test]
+    fn fold_acts_and_scenes() {
+        let uri = Uri::from_str("file:///play.spl").unwrap();
+        let mut server = Server::default();
+        let play = "Title.\n\nAct I: One.\nScene I: A.\n[Enter Romeo]\n\nScene II: B.\n[Exeunt]\n";
+        server
+            .documents
+            .insert(uri.clone(), AnalysisDatabase::new(play.to_string(), true));
+
+        let folding_ranges: Vec<_> = server
+            .folding_ranges(FoldingRangeParams {
+                text_document: TextDocumentIdentifier { uri },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .unwrap()
+            .into_iter()
+            .map(|folding_range| (folding_range.start_line, folding_range.end_line))
+            .collect();
+
+        assert_eq!(folding_ranges, [(2, 7), (3, 4), (6, 7)]);
+    }
+
+    #[test]
+    fn utf16_positions() {
+        let text = "Title — a play.\nRomeo, a man.";
+        let line_index = LineIndex::new(text);
+
+        let after_dash = TextSize::of("Title —");
+        assert_eq!(position(text, &line_index, after_dash), Position::new(0, 7));
+        assert_eq!(offset(text, &line_index, Position::new(0, 7)), after_dash);
+        assert_eq!(
+            offset(text, &line_index, Position::new(0, 99)),
+            TextSize::of("Title — a play.")
+        );
+        assert_eq!(
+            offset(text, &line_index, Position::new(5, 0)),
+            TextSize::of(text)
+        );
+    }
+}
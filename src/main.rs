@@ -1,75 +1,367 @@
+// Each of the lexer’s words is a `#[token]` of its own, and there are more
+// characters than the default limit allows for.
+#![recursion_limit = "256"]
+
+mod analysis;
 mod ast; // Abstract Syntax Tree
+mod cli;
+mod codegen;
+mod diagnostic;
+mod encode;
 mod eval;
+mod formatter;
+mod from_bf;
+mod generate;
+mod highlight;
 mod hir; // High-level Intermediate Representation
 mod lexer;
+mod line_index;
+mod lsp;
+mod obfuscate;
 mod parser; // Creates a Concrete Syntax Tree
+mod repl;
+mod roman;
 mod syntax;
+mod validation;
 
+use crate::cli::{Command, MessageFormat, ProgramInput, Target};
+use crate::diagnostic::Diagnostic;
 use crate::eval::Evaluator;
+use crate::lexer::Lexer;
+use crate::line_index::LineIndex;
 use crate::parser::parse;
+use crate::repl::Repl;
 use mimalloc::MiMalloc;
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
 use std::{env, fs};
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-fn main() -> io::Result<()> {
-    let mut args = env::args();
-    match args.len() {
-        1 => Repl::new().run()?,
-        2 => {
-            let contents = fs::read_to_string(args.nth(1).unwrap())?;
+const EXIT_FAILURE: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_PARSE: u8 = 3;
+const EXIT_VALIDATION: u8 = 4;
+const EXIT_RUNTIME: u8 = 5;
+
+fn main() -> ExitCode {
+    let args = match cli::parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, cli::USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let path = match &args.command {
+        Command::Repl => {
+            return match Repl::new().and_then(Repl::run) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(error) => {
+                    eprintln!("error: {}", error);
+                    ExitCode::from(EXIT_FAILURE)
+                }
+            }
+        }
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Command::Encode { number, shortest } => {
+            println!("{}", encode::encode(*number, *shortest));
+            return ExitCode::SUCCESS;
+        }
+        Command::Generate { text } => {
+            print!("{}", generate::generate(text));
+            return ExitCode::SUCCESS;
+        }
+        Command::Lsp => {
+            return match lsp::run() {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(error) => {
+                    eprintln!("error: {}", error);
+                    ExitCode::from(EXIT_FAILURE)
+                }
+            }
+        }
+        Command::Fmt { paths, check } => {
+            let mut code = ExitCode::SUCCESS;
+            for path in paths {
+                if let Err(error) = fmt(path, *check, args.message_format) {
+                    code = ExitCode::from(error);
+                }
+            }
+            return code;
+        }
+        Command::Run(path, _)
+        | Command::Check(path)
+        | Command::Tokens(path)
+        | Command::Tree(path)
+        | Command::Hir(path)
+        | Command::Highlight(path)
+        | Command::Emit(_, path)
+        | Command::Build { path, .. }
+        | Command::FromBf(path)
+        | Command::Obfuscate { path, .. } => path.clone(),
+    };
+
+    let contents = match read_source(&path) {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("error: could not read {}: {}", path, error);
+            return ExitCode::from(EXIT_FAILURE);
+        }
+    };
+    let path = if path == "-" { "<stdin>" } else { &path };
+
+    let reporter = Reporter {
+        path,
+        line_index: LineIndex::new(&contents),
+        format: args.message_format,
+    };
+
+    let result = match args.command {
+        Command::Run(_, input) => run(&reporter, &contents, input),
+        Command::Check(_) => check(&reporter, &contents).map(|_| ()),
+        Command::Tokens(_) => {
+            // Plays can be long, so stop quietly if the output is cut short.
+            let mut stdout = io::stdout().lock();
+            for token in Lexer::new(&contents) {
+                if writeln!(stdout, "{}", token).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        Command::Tree(_) => {
+            print!("{}", parse(&contents, true).debug_tree());
+            Ok(())
+        }
+        Command::Hir(_) => {
+            let (items, db) = lower(&contents);
+            print!("{}", hir::debug(&items, &db));
+            Ok(())
+        }
+        Command::Highlight(_) => {
+            let mut stdout = io::stdout().lock();
+            for highlight in highlight::highlight(&parse(&contents, true).syntax_node()) {
+                let text = &contents[highlight.range];
+                if writeln!(stdout, "{} {:?}", highlight, text).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        Command::Emit(target, _) => check(&reporter, &contents).map(|(_, items, db)| {
+            print!("{}", codegen::emit(target, &items, &db));
+        }),
+        Command::Build { path, output } => build(&reporter, &path, &contents, &output),
+        Command::Obfuscate { seed, minify, .. } => check(&reporter, &contents).map(|_| {
+            let root = parse(&contents, true).syntax_node();
+            print!("{}", obfuscate::obfuscate(&root, seed, minify));
+        }),
+        Command::FromBf(_) => match from_bf::from_bf(&contents) {
+            Ok(play) => {
+                print!("{}", play);
+                Ok(())
+            }
+            Err(error) => {
+                reporter.report(&Diagnostic::from_bf_error(&error));
+                Err(EXIT_PARSE)
+            }
+        },
+        Command::Repl
+        | Command::Help
+        | Command::Fmt { .. }
+        | Command::Lsp
+        | Command::Encode { .. }
+        | Command::Generate { .. } => unreachable!(),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => ExitCode::from(code),
+    }
+}
+
+fn read_source(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
+        Ok(contents)
+    } else {
+        fs::read_to_string(path)
+    }
+}
+
+/// Formats a play in place, or with `check` only reports whether it would
+/// change. A play read from stdin is written to stdout.
+/// Plays with syntax errors are left alone, as the formatter can’t tell what
+/// their unparsable parts were meant to be.
+fn fmt(path: &str, check: bool, format: MessageFormat) -> Result<(), u8> {
+    let contents = read_source(path).map_err(|error| {
+        eprintln!("error: could not read {}: {}", path, error);
+        EXIT_FAILURE
+    })?;
 
-            let parse = parse(&contents, true);
-            let root = ast::Root::cast(parse.syntax_node()).unwrap();
-            let (items, db) = hir::lower(root);
+    let name = if path == "-" { "<stdin>" } else { path };
 
-            Evaluator::default().eval(&items, db);
+    let parse = parse(&contents, true);
+    if !parse.errors.is_empty() {
+        let reporter = Reporter {
+            path: name,
+            line_index: LineIndex::new(&contents),
+            format,
+        };
+        for error in &parse.errors {
+            reporter.report(&Diagnostic::from_parse_error(error));
         }
-        _ => eprintln!("Usage: puck [filepath]"),
+        return Err(EXIT_PARSE);
+    }
+
+    let formatted = formatter::format(&parse.syntax_node());
+
+    if check {
+        if formatted != contents {
+            eprintln!("{} is not formatted", name);
+            return Err(EXIT_FAILURE);
+        }
+    } else if path == "-" {
+        print!("{}", formatted);
+    } else if formatted != contents {
+        fs::write(path, formatted).map_err(|error| {
+            eprintln!("error: could not write {}: {}", path, error);
+            EXIT_FAILURE
+        })?;
     }
 
     Ok(())
 }
 
-struct Repl {
-    stdin: io::Stdin,
-    stdout: io::Stdout,
-    input: String,
-    evaluator: Evaluator,
+fn lower(contents: &str) -> (Vec<hir::Item>, hir::Database) {
+    let parse = parse(contents, true);
+    let root = ast::Root::cast(parse.syntax_node()).unwrap();
+    let mut db = hir::Database::default();
+    let items = hir::lower(&root, &mut db);
+
+    (items, db)
+}
+
+fn run(reporter: &Reporter<'_>, contents: &str, input: ProgramInput) -> Result<(), u8> {
+    let (root, items, db) = check(reporter, contents)?;
+
+    let mut evaluator = match input {
+        ProgramInput::Stdin => Evaluator::default(),
+        ProgramInput::File(input_path) => match fs::File::open(&input_path) {
+            Ok(file) => Evaluator::with_input(io::BufReader::new(file)),
+            Err(error) => {
+                eprintln!("error: could not read {}: {}", input_path, error);
+                return Err(EXIT_FAILURE);
+            }
+        },
+        ProgramInput::Text(text) => Evaluator::with_input(io::Cursor::new(text.into_bytes())),
+    };
+
+    evaluator.eval(&root, &items, &db).map_err(|error| {
+        reporter.report(&Diagnostic::from_eval_error(&error));
+        EXIT_RUNTIME
+    })
 }
 
-impl Repl {
-    fn new() -> Self {
-        Repl {
-            stdin: io::stdin(),
-            stdout: io::stdout(),
-            input: String::new(),
-            evaluator: Evaluator::default(),
+/// Compiles a play into a native executable, by way of assembly.
+fn build(reporter: &Reporter<'_>, path: &str, contents: &str, output: &str) -> Result<(), u8> {
+    // Paths that are spelled differently can still lead to the play.
+    if let (Ok(play), Ok(executable)) = (fs::canonicalize(path), fs::canonicalize(output)) {
+        if play == executable {
+            eprintln!("error: could not build {}: it is the play itself", output);
+            return Err(EXIT_FAILURE);
         }
     }
 
-    fn run(mut self) -> io::Result<()> {
-        loop {
-            write!(self.stdout, "→ ")?;
-            self.stdout.flush()?;
+    let (_, items, db) = check(reporter, contents)?;
+    let assembly = codegen::emit(Target::Asm, &items, &db);
 
-            self.stdin.read_line(&mut self.input)?;
+    codegen::build(&assembly, Path::new(output)).map_err(|error| {
+        eprintln!("error: could not build {}: {}", output, error);
+        EXIT_FAILURE
+    })
+}
 
-            let parse = parse(&self.input, false);
-            for error in &parse.errors {
-                println!("{}", error);
-            }
+/// Reports every parse error, or failing that every validation error, giving
+/// back the lowered play if there were none.
+fn check(
+    reporter: &Reporter<'_>,
+    contents: &str,
+) -> Result<(ast::Root, Vec<hir::Item>, hir::Database), u8> {
+    let parse = parse(contents, true);
+    if !parse.errors.is_empty() {
+        for error in &parse.errors {
+            reporter.report(&Diagnostic::from_parse_error(error));
+        }
+        return Err(EXIT_PARSE);
+    }
 
-            if parse.errors.is_empty() {
-                let root = ast::Root::cast(parse.syntax_node()).unwrap();
-                let (items, db) = hir::lower(root);
+    let root = ast::Root::cast(parse.syntax_node()).unwrap();
+    let mut db = hir::Database::default();
+    let items = hir::lower(&root, &mut db);
 
-                self.evaluator.eval(&items, db);
-            }
+    let validation_errors = validation::validate(&root, &items, &db);
+    if !validation_errors.is_empty() {
+        for error in &validation_errors {
+            reporter.report(&Diagnostic::from_validation_error(error));
+        }
+        return Err(EXIT_VALIDATION);
+    }
+
+    Ok((root, items, db))
+}
+
+struct Reporter<'a> {
+    path: &'a str,
+    line_index: LineIndex,
+    format: MessageFormat,
+}
 
-            self.input.clear();
+impl Reporter<'_> {
+    fn report(&self, diagnostic: &Diagnostic) {
+        match self.format {
+            MessageFormat::Human => eprintln!("{}", diagnostic.human(self.path, &self.line_index)),
+            MessageFormat::Json => eprintln!("{}", diagnostic.json(self.path, &self.line_index)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn fmt_leaves_play_with_syntax_errors_alone() {
+        let path = env::temp_dir().join(format!("puck-fmt-{}.spl", process::id()));
+        let play = "Title.\nRomeo,  a man.\nJuliet, a woman.\n[Enter Romeo and Juliet]\nRomeo: You are a.\n";
+        fs::write(&path, play).unwrap();
+
+        let result = fmt(path.to_str().unwrap(), false, MessageFormat::Human);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, Err(EXIT_PARSE));
+        assert_eq!(contents, play);
+    }
+
+    #[test]
+    fn fmt_formats_valid_play_in_place() {
+        let path = env::temp_dir().join(format!("puck-fmt-valid-{}.spl", process::id()));
+        fs::write(&path, "Title. Romeo,  a man.  [Enter Romeo]").unwrap();
+
+        let result = fmt(path.to_str().unwrap(), false, MessageFormat::Human);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(contents, "Title.\n\nRomeo, a man.\n\n[Enter Romeo]\n");
+    }
+}
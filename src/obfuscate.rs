@@ -0,0 +1,270 @@
+use crate::lexer::SyntaxKind;
+use crate::syntax::{SyntaxElement, SyntaxNode, SyntaxToken};
+use std::collections::HashMap;
+
+/// The kinds whose words all mean the same thing, so any of them can stand
+/// in for another.
+const INTERCHANGEABLE: [SyntaxKind; 11] = [
+    SyntaxKind::Be,
+    SyntaxKind::Article,
+    SyntaxKind::SecondPerson,
+    SyntaxKind::SecondPersonPossessive,
+    SyntaxKind::Nothing,
+    SyntaxKind::PositiveNoun,
+    SyntaxKind::NeutralNoun,
+    SyntaxKind::NegativeNoun,
+    SyntaxKind::PositiveAdjective,
+    SyntaxKind::NeutralAdjective,
+    SyntaxKind::NegativeAdjective,
+];
+
+/// Rewrites a valid play so that it does the same thing in other words.
+/// Nouns, adjectives and the other interchangeable words are swapped for
+/// others of the same kind, the characters are renamed, and the title and
+/// every comment are replaced, all chosen by `seed`.
+///
+/// With `minify`, the shortest words and names are picked instead, comments
+/// are left empty and only the whitespace that keeps words apart is kept.
+pub(crate) fn obfuscate(root: &SyntaxNode, seed: u64, minify: bool) -> String {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let names = names(root, &mut rng, minify);
+
+    let mut out = String::new();
+    let mut previous: Option<SyntaxKind> = None;
+    let mut space = false;
+    // Whether the words of a comment are being replaced, which lasts until
+    // the full stop that ends it.
+    let mut in_comment = false;
+
+    for token in root
+        .descendants_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+    {
+        let kind = token.kind();
+        if in_comment && matches!(kind, SyntaxKind::Skip | SyntaxKind::Whitespace) {
+            continue;
+        }
+
+        let text = match kind {
+            SyntaxKind::Whitespace if minify => {
+                space = true;
+                continue;
+            }
+            SyntaxKind::Whitespace => token.text().to_string(),
+            // Only comments are skipped in a valid play.
+            SyntaxKind::Skip => {
+                in_comment = true;
+                if minify {
+                    continue;
+                }
+                comment(&mut rng, &token)
+            }
+            SyntaxKind::Character => names[&name(token.text())].clone(),
+            _ if INTERCHANGEABLE.contains(&kind) => {
+                let word = if minify {
+                    kind.words().iter().min_by_key(|word| word.len()).unwrap()
+                } else {
+                    pick(&mut rng, kind)
+                };
+                match_case(word, token.text())
+            }
+            _ => token.text().to_string(),
+        };
+
+        if minify && space && previous.is_some_and(is_word) && is_word(kind) {
+            out.push(' ');
+        }
+        out.push_str(&text);
+        previous = Some(kind);
+        space = false;
+        in_comment &= !matches!(kind, SyntaxKind::Period | SyntaxKind::Exclamation);
+    }
+
+    if minify && !out.is_empty() {
+        out.push('\n');
+    }
+
+    out
+}
+
+/// The names of the characters in a play, as the lexer compares them.
+fn name(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Gives every character in the play a different name.
+fn names(root: &SyntaxNode, rng: &mut fastrand::Rng, minify: bool) -> HashMap<String, String> {
+    let mut characters = Vec::new();
+    for token in root
+        .descendants_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .filter(|token| token.kind() == SyntaxKind::Character)
+    {
+        let name = name(token.text());
+        if !characters.contains(&name) {
+            characters.push(name);
+        }
+    }
+
+    let mut available = SyntaxKind::Character.words().to_vec();
+    if minify {
+        available.sort_by_key(|name| name.len());
+    } else {
+        rng.shuffle(&mut available);
+    }
+
+    characters
+        .into_iter()
+        .zip(available)
+        .map(|(character, name)| (character, title_case(name)))
+        .collect()
+}
+
+/// The lexer knows characters by their lowercase names, like ‘the abbot of
+/// westminster’.
+fn title_case(name: &str) -> String {
+    name.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) if word != "of" => first.to_uppercase().chain(chars).collect(),
+                _ => word.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// A few words in place of a comment, which reads like a description.
+fn comment(rng: &mut fastrand::Rng, first: &SyntaxToken) -> String {
+    let mut words = vec![pick(rng, SyntaxKind::Article)];
+    for _ in 0..rng.usize(..3) {
+        words.push(pick(rng, SyntaxKind::PositiveAdjective));
+    }
+    words.push(pick(rng, SyntaxKind::NeutralNoun));
+
+    match_case(&words.join(" "), first.text())
+}
+
+fn pick(rng: &mut fastrand::Rng, kind: SyntaxKind) -> &'static str {
+    let words = kind.words();
+    words[rng.usize(..words.len())]
+}
+
+/// Starts `word` with a capital if `original` did.
+fn match_case(word: &str, original: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) if original.starts_with(char::is_uppercase) => {
+            first.to_uppercase().chain(chars).collect()
+        }
+        _ => word.to_string(),
+    }
+}
+
+/// Whether a token would run into a neighbouring word without a space.
+fn is_word(kind: SyntaxKind) -> bool {
+    !matches!(
+        kind,
+        SyntaxKind::Period
+            | SyntaxKind::Exclamation
+            | SyntaxKind::Question
+            | SyntaxKind::Comma
+            | SyntaxKind::Colon
+            | SyntaxKind::LBracket
+            | SyntaxKind::RBracket
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::{interpret, CONFORMANCE};
+    use crate::parser::parse;
+    use expect_test::expect;
+    use proptest::prelude::*;
+
+    fn obfuscate_play(play: &str, seed: u64, minify: bool) -> String {
+        obfuscate(&parse(play, true).syntax_node(), seed, minify)
+    }
+
+    /// Runs a play and its rewritten form on every input the conformance
+    /// tests use, which also checks that the rewritten play is valid.
+    fn check_same_output(seed: u64, minify: bool) {
+        for (name, play, inputs) in CONFORMANCE {
+            let obfuscated = obfuscate_play(play, seed, minify);
+            for input in *inputs {
+                assert_eq!(
+                    interpret(&obfuscated, input),
+                    interpret(play, input),
+                    "{} with seed {}:\n{}",
+                    name,
+                    seed,
+                    obfuscated
+                );
+            }
+        }
+    }
+
+    const PLAY: &str = "The Infamous Hello World Program.
+
+Romeo, a young man with a remarkable patience.
+Juliet, a likewise young woman of remarkable grace.
+
+Act I: Hamlet's insults and flattery.
+
+Scene I: The insulting of Romeo.
+
+[Enter Romeo and Juliet]
+Juliet: You are the sum of a big big big big big big cat and a happy rose.
+    Speak your mind!
+Romeo: Thou art nothing.
+    Open your heart.
+[Exeunt]
+";
+
+    #[test]
+    fn obfuscate_with_seed() {
+        expect![[r#"
+The lovely sweetest fellow.
+
+Arthur, the brave grandfather.
+The Abbot of Westminster, an honest smooth morning.
+
+Act I: The granddaughter.
+
+Scene I: The charming wind.
+
+[Enter Arthur and The Abbot of Westminster]
+The Abbot of Westminster: Thee am an sum of the large red huge green normal little brother and the fair plum.
+    Speak thy mind!
+Arthur: Thou am zero.
+    Open your heart.
+[Exeunt]
+"#]]
+        .assert_eq(&obfuscate_play(PLAY, 1, false));
+    }
+
+    #[test]
+    fn minify() {
+        expect![[r#"
+.Ajax,.Ford,.Act I:.Scene I:.[Enter Ajax and Ford]Ford:You am a sum of a big big big big big big cat and a bold joy.Speak thy mind!Ajax:You am zero.Open thy heart.[Exeunt]
+"#]]
+        .assert_eq(&obfuscate_play(PLAY, 1, true));
+    }
+
+    #[test]
+    fn minify_keeps_output() {
+        check_same_output(0, true);
+    }
+
+    proptest! {
+        #[test]
+        fn obfuscate_keeps_output(seed: u64) {
+            check_same_output(seed, false);
+        }
+    }
+}
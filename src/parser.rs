@@ -2,13 +2,15 @@ mod event;
 mod grammar;
 mod imp;
 mod parse_error;
+mod reparsing;
 mod sink;
 mod source;
 
-use crate::lexer::Lexer;
+use crate::lexer::{Lexer, SyntaxKind};
 use crate::syntax::SyntaxNode;
 use imp::Parser;
-use parse_error::ParseError;
+pub(crate) use parse_error::ParseError;
+pub(crate) use reparsing::Edit;
 use rowan::GreenNode;
 use sink::Sink;
 
@@ -26,11 +28,34 @@ pub(crate) struct Parse {
 }
 
 impl Parse {
+    /// Applies `edit` to the parsed text, reusing the rest of the tree when
+    /// only a single item is affected.
+    pub(crate) fn reparse(&self, edit: &Edit, title: bool) -> Parse {
+        reparsing::reparse(self, edit, title)
+    }
+
     pub(crate) fn syntax_node(&self) -> SyntaxNode {
         SyntaxNode::new_root(self.green_node.clone())
     }
 
-    #[cfg(test)]
+    /// Whether the input ran out in the middle of an item, meaning that more
+    /// input could still turn it into a valid play.
+    pub(crate) fn is_unfinished(&self) -> bool {
+        let last_token = self
+            .syntax_node()
+            .descendants_with_tokens()
+            .filter_map(|element| element.into_token())
+            .filter(|token| token.kind() != SyntaxKind::Whitespace)
+            .last();
+
+        match (self.errors.last(), last_token) {
+            (Some(error), Some(token)) => {
+                error.found.is_none() && error.range.end() == token.text_range().end()
+            }
+            _ => false,
+        }
+    }
+
     pub(crate) fn debug_tree(&self) -> String {
         let mut s = String::new();
 
@@ -45,3 +70,38 @@ impl Parse {
         s
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_unfinished(input: &str, unfinished: bool) {
+        assert_eq!(parse(input, false).is_unfinished(), unfinished);
+    }
+
+    #[test]
+    fn unfinished_sentence() {
+        check_unfinished("Romeo: You are the sum of a cat\n", true);
+    }
+
+    #[test]
+    fn unfinished_dialog() {
+        check_unfinished("Romeo:", true);
+    }
+
+    #[test]
+    fn unfinished_stage_direction() {
+        check_unfinished("[Enter Romeo and", true);
+    }
+
+    #[test]
+    fn finished_item() {
+        check_unfinished("Romeo: You are a cat.\n", false);
+    }
+
+    #[test]
+    fn finished_with_error() {
+        check_unfinished("Romeo: You are the sum of a cat.", false);
+        check_unfinished("@", false);
+    }
+}
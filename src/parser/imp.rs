@@ -80,7 +80,7 @@ impl<'tokens, 'input> Parser<'tokens, 'input> {
     }
 
     fn at_set<const N: usize>(&self, set: [SyntaxKind; N]) -> bool {
-        self.peek().map_or(false, |k| set.contains(&k))
+        self.peek().is_some_and(|k| set.contains(&k))
     }
 
     pub(super) fn expect(&mut self, syntax_kind: SyntaxKind) {
@@ -97,6 +97,12 @@ impl<'tokens, 'input> Parser<'tokens, 'input> {
         self.peek() == Some(syntax_kind)
     }
 
+    /// Looks `n` tokens ahead without expecting anything there, for the few
+    /// places where the next token alone can’t tell what comes next.
+    pub(super) fn nth(&self, n: usize) -> Option<SyntaxKind> {
+        self.source.lookahead_kind(n)
+    }
+
     pub(super) fn at_eof(&self) -> bool {
         self.peek().is_none()
     }
@@ -10,16 +10,46 @@ pub(crate) struct ParseError {
     pub(super) range: TextRange,
 }
 
+impl ParseError {
+    pub(crate) fn range(&self) -> TextRange {
+        self.range
+    }
+
+    pub(crate) fn expected(&self) -> impl Iterator<Item = SyntaxKind> + '_ {
+        self.expected.iter().copied()
+    }
+
+    pub(crate) fn found(&self) -> Option<SyntaxKind> {
+        self.found
+    }
+
+    /// The error without its position, for callers that show positions their
+    /// own way.
+    pub(crate) fn message(&self) -> String {
+        Message(self).to_string()
+    }
+}
+
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "error at {}..{}: expected ",
+            "error at {}..{}: {}",
             u32::from(self.range.start()),
             u32::from(self.range.end()),
-        )?;
+            Message(self),
+        )
+    }
+}
+
+struct Message<'a>(&'a ParseError);
+
+impl fmt::Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected ")?;
 
         let expected: Vec<_> = self
+            .0
             .expected
             .iter()
             .flat_map(|kind| kind.to_strs())
@@ -28,7 +58,7 @@ impl fmt::Display for ParseError {
 
         comma_separate(f, &expected)?;
 
-        if let Some(found) = self.found {
+        if let Some(found) = self.0.found {
             write!(f, " but found ")?;
             comma_separate(f, found.to_strs())?;
         }
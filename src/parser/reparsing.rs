@@ -0,0 +1,224 @@
+use super::parse_error::ParseError;
+use super::{parse, Parse};
+use crate::lexer::SyntaxKind;
+use crate::syntax::SyntaxNode;
+use text_size::{TextRange, TextSize};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Edit {
+    pub(crate) delete: TextRange,
+    pub(crate) insert: String,
+}
+
+impl Edit {
+    pub(crate) fn apply(&self, text: &mut String) {
+        let start = u32::from(self.delete.start()) as usize;
+        let end = u32::from(self.delete.end()) as usize;
+        text.replace_range(start..end, &self.insert);
+    }
+}
+
+pub(super) fn reparse(old: &Parse, edit: &Edit, title: bool) -> Parse {
+    reparse_item(old, edit).unwrap_or_else(|| {
+        let mut text = old.syntax_node().to_string();
+        edit.apply(&mut text);
+        parse(&text, title)
+    })
+}
+
+// Only the item (character definition, stage direction or dialog) containing
+// the edit is reparsed. This is only done when the edited item still parses
+// on its own without errors, since an incomplete item could otherwise have
+// swallowed tokens belonging to the next one in a full parse.
+fn reparse_item(old: &Parse, edit: &Edit) -> Option<Parse> {
+    let root = old.syntax_node();
+    let item = find_item(&root, edit.delete)?;
+    let item_range = item.text_range();
+
+    let mut text = item.to_string();
+    Edit {
+        delete: edit.delete - item_range.start(),
+        insert: edit.insert.clone(),
+    }
+    .apply(&mut text);
+
+    let fragment = parse(&text, false);
+    if !fragment.errors.is_empty() {
+        return None;
+    }
+
+    let new_item = {
+        let fragment_root = fragment.syntax_node();
+        let mut children = fragment_root.children_with_tokens();
+        let new_item = children.next()?.into_node()?;
+        if children.next().is_some() || new_item.kind() != item.kind() {
+            return None;
+        }
+
+        new_item
+    };
+
+    // The next item reports errors at the previous token when it breaks before
+    // using any of its own, which would be this item’s last one. The grammar
+    // always consumes an item’s first token before that can happen, but if it
+    // ever didn’t, such an error couldn’t safely be dropped.
+    let last_token = item
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.kind() != SyntaxKind::Whitespace)
+        .last()?
+        .text_range();
+    if old.errors.iter().any(|error| error.range == last_token) {
+        return None;
+    }
+
+    let green_node = item.replace_with(new_item.green().into_owned());
+
+    let old_len = item_range.len();
+    let new_len = TextSize::of(text.as_str());
+    let errors = old
+        .errors
+        .iter()
+        .filter(|error| !item_range.contains_range(error.range))
+        .map(|error| {
+            let range = if error.range.start() >= item_range.end() {
+                TextRange::at(error.range.start() - old_len + new_len, error.range.len())
+            } else {
+                error.range
+            };
+
+            ParseError {
+                expected: error.expected.clone(),
+                found: error.found,
+                range,
+            }
+        })
+        .collect();
+
+    Some(Parse { green_node, errors })
+}
+
+fn find_item(root: &SyntaxNode, range: TextRange) -> Option<SyntaxNode> {
+    let element = root.covering_element(range);
+    let node = match element {
+        rowan::NodeOrToken::Node(node) => node,
+        rowan::NodeOrToken::Token(token) => token.parent()?,
+    };
+
+    node.ancestors().find(|node| {
+        matches!(
+            node.kind(),
+            SyntaxKind::CharacterDef | SyntaxKind::StageDirection | SyntaxKind::Dialog
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    // Replaces the first occurrence of `delete` in `before` with `insert`.
+    fn check(before: &str, delete: &str, insert: &str, incremental: bool) {
+        let start = TextSize::try_from(before.find(delete).unwrap()).unwrap();
+        let edit = Edit {
+            delete: TextRange::at(start, TextSize::of(delete)),
+            insert: insert.to_string(),
+        };
+
+        let old = parse(before, true);
+        let mut after = before.to_string();
+        edit.apply(&mut after);
+
+        let reparsed = reparse(&old, &edit, true);
+        let full = parse(&after, true);
+
+        assert_eq!(reparsed.debug_tree(), full.debug_tree());
+        assert_eq!(reparsed.errors, full.errors);
+        assert_eq!(reparse_item(&old, &edit).is_some(), incremental);
+    }
+
+    #[test]
+    fn reparse_dialog() {
+        check(
+            "Title.\nRomeo, a man.\n[Enter Romeo and Juliet]\nRomeo: You are a cat.\n",
+            "cat",
+            "fine pig",
+            true,
+        );
+    }
+
+    #[test]
+    fn reparse_stage_direction() {
+        check(
+            "Title.\nRomeo, a man.\n[Enter Romeo and Juliet]\nRomeo: You are a cat.\n",
+            "Juliet",
+            "Puck",
+            true,
+        );
+    }
+
+    #[test]
+    fn reparse_shifts_later_errors() {
+        check(
+            "Title.\nJuliet: Speak your mind.\nRomeo\nJuliet: Open your heart!",
+            "your mind",
+            "thy mind",
+            true,
+        );
+    }
+
+    #[test]
+    fn reparse_drops_fixed_errors() {
+        check(
+            "Title.\nJuliet: You are a fine.\n[Exeunt]",
+            "fine",
+            "fine cat",
+            true,
+        );
+    }
+
+    #[test]
+    fn reparse_before_broken_item() {
+        for (before, delete, insert) in [
+            (
+                "Title.\nRomeo: You are a cat.\nJuliet [Exeunt]",
+                "cat.",
+                "pig.",
+            ),
+            ("Title.\nRomeo: You are a cat.\nJuliet:", "cat.", "cat!"),
+            ("Title.\nRomeo: You are a cat.\n[Enter", "cat.", "cat!"),
+            ("Title.\nRomeo: You are a cat. Juliet", "cat.", "cat!"),
+            ("Title.\nRomeo, a man.\nJuliet", "man.", "man!"),
+            ("Title.\nRomeo: You are a cat.\nScene I", "cat.", "cat!"),
+            ("Title.\n[Exeunt]\nJuliet", "]", "]"),
+        ] {
+            check(before, delete, insert, true);
+        }
+    }
+
+    #[test]
+    fn fall_back_when_item_breaks() {
+        check(
+            "Title.\n[Enter Romeo]\nRomeo: Open your heart!",
+            "]",
+            "",
+            false,
+        );
+    }
+
+    #[test]
+    fn fall_back_when_item_kind_changes() {
+        check(
+            "Title.\nRomeo, a man.\nJuliet: Open your heart!",
+            ",",
+            ":",
+            false,
+        );
+    }
+
+    #[test]
+    fn fall_back_when_editing_title() {
+        check("Title.\nRomeo, a man.", "Title", "A Play", false);
+    }
+}
@@ -0,0 +1,255 @@
+mod completion;
+
+use crate::eval::Evaluator;
+use crate::lexer::Lexer;
+use crate::parser::parse;
+use crate::{ast, hir, validation};
+use completion::PuckHelper;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::Editor;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::{env, fs};
+
+const HELP: &str = "\
+:stage        show who is on stage
+:state        show the value of every character
+:reset        forget all characters and values
+:load <file>  run a play in the current state
+:tokens       show the tokens of the last input
+:tree         show the syntax tree of the last input
+:help         show this message";
+
+pub(crate) struct Repl {
+    editor: Editor<PuckHelper, DefaultHistory>,
+    history: Option<PathBuf>,
+    input: String,
+    last_input: Option<(String, bool)>,
+    db: hir::Database,
+    evaluator: Evaluator,
+}
+
+impl Repl {
+    pub(crate) fn new() -> rustyline::Result<Self> {
+        let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(".puck_history"));
+        Self::with_history(history)
+    }
+
+    fn with_history(history: Option<PathBuf>) -> rustyline::Result<Self> {
+        let mut editor = Editor::new()?;
+        editor.set_helper(Some(PuckHelper::default()));
+
+        if let Some(history) = &history {
+            // There is no history the first time the REPL is used.
+            let _ = editor.load_history(history);
+        }
+
+        Ok(Repl {
+            editor,
+            history,
+            input: String::new(),
+            last_input: None,
+            db: hir::Database::default(),
+            evaluator: Evaluator::default(),
+        })
+    }
+
+    pub(crate) fn run(mut self) -> rustyline::Result<()> {
+        loop {
+            let prompt = if self.input.is_empty() { "→ " } else { "· " };
+            let continuing = !self.input.is_empty();
+
+            match self.editor.readline(prompt) {
+                Ok(line) => {
+                    if !line.trim().is_empty() {
+                        self.editor.add_history_entry(line.as_str())?;
+                        self.save_history();
+                    }
+                    self.input.push_str(&line);
+                    self.input.push('\n');
+                }
+                // Ctrl-C abandons whatever has been typed so far.
+                Err(ReadlineError::Interrupted) => {
+                    self.input.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(error) => return Err(error),
+            }
+
+            if !continuing {
+                if let Some(command) = self.input.trim().strip_prefix(':') {
+                    let command = command.to_string();
+                    self.input.clear();
+                    self.command(&command);
+                    continue;
+                }
+            }
+
+            // A blank line gives up on waiting for the rest of an item.
+            let blank_line = continuing && self.input.ends_with("\n\n");
+
+            let parse = parse(&self.input, false);
+            if parse.is_unfinished() && !blank_line {
+                continue;
+            }
+
+            let input = std::mem::take(&mut self.input);
+            self.run_input(input, false);
+        }
+
+        Ok(())
+    }
+
+    /// Saves the history after every line, so that it survives the session
+    /// ending some other way than at the end of the input.
+    fn save_history(&mut self) {
+        if let Some(history) = &self.history {
+            // Losing the history isn’t worth interrupting the session for.
+            let _ = self.editor.save_history(history);
+        }
+    }
+
+    fn run_input(&mut self, input: String, title: bool) {
+        let parse = parse(&input, title);
+        for error in &parse.errors {
+            println!("{}", error);
+        }
+
+        if parse.errors.is_empty() {
+            let root = ast::Root::cast(parse.syntax_node()).unwrap();
+            let items = hir::lower(&root, &mut self.db);
+
+            // The input carries on from the characters of earlier inputs.
+            let declared: Vec<_> = self
+                .evaluator
+                .values()
+                .map(|(character, _)| character)
+                .collect();
+            let errors = validation::validate_from(
+                &root,
+                &items,
+                &self.db,
+                &declared,
+                self.evaluator.on_stage(),
+            );
+            for error in &errors {
+                println!("{}", error);
+            }
+
+            if errors.is_empty() {
+                if let Err(error) = self.evaluator.eval(&root, &items, &self.db) {
+                    println!("{}", error);
+                }
+            }
+            // Characters are spoken without a newline, which would otherwise
+            // leave them buffered behind the next prompt.
+            let _ = io::stdout().flush();
+        }
+
+        self.last_input = Some((input, title));
+        self.update_completions();
+    }
+
+    /// Forgets every character, which also stops them being completed.
+    fn reset(&mut self) {
+        self.db = hir::Database::default();
+        self.evaluator = Evaluator::default();
+        self.update_completions();
+    }
+
+    /// Offers the characters the session has declared, rather than every one
+    /// that has been mentioned, since the others can’t be used.
+    fn update_completions(&mut self) {
+        let characters = self
+            .evaluator
+            .values()
+            .map(|(character, _)| self.db[character].name.clone())
+            .collect();
+
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.set_characters(characters);
+        }
+    }
+
+    fn command(&mut self, command: &str) {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "stage" => {
+                let names: Vec<_> = self
+                    .evaluator
+                    .on_stage()
+                    .iter()
+                    .map(|character| self.db[*character].name.as_str())
+                    .collect();
+
+                if names.is_empty() {
+                    println!("nobody is on stage");
+                } else {
+                    println!("{}", names.join(", "));
+                }
+            }
+            "state" => {
+                for (character, value) in self.evaluator.values() {
+                    let on_stage = if self.evaluator.on_stage().contains(&character) {
+                        " (on stage)"
+                    } else {
+                        ""
+                    };
+                    println!("{} = {}{}", self.db[character].name, value, on_stage);
+                }
+            }
+            "reset" => self.reset(),
+            "load" if !argument.is_empty() => match fs::read_to_string(argument) {
+                Ok(contents) => self.run_input(contents, true),
+                Err(error) => println!("could not read {}: {}", argument, error),
+            },
+            "tokens" => {
+                if let Some((input, _)) = &self.last_input {
+                    for token in Lexer::new(input) {
+                        println!("{}", token);
+                    }
+                }
+            }
+            "tree" => {
+                if let Some((input, title)) = &self.last_input {
+                    println!("{}", parse(input, *title).debug_tree());
+                }
+            }
+            "help" => println!("{}", HELP),
+            _ => println!("unknown command :{}, try :help", command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completed_characters(repl: &Repl) -> &[String] {
+        repl.editor.helper().unwrap().characters()
+    }
+
+    #[test]
+    fn reset_forgets_completions() {
+        let mut repl = Repl::with_history(None).unwrap();
+        repl.run_input("Romeo, a man. Juliet, a woman.".to_string(), false);
+        assert_eq!(completed_characters(&repl), ["Romeo", "Juliet"]);
+
+        repl.command("reset");
+        assert!(completed_characters(&repl).is_empty());
+    }
+
+    #[test]
+    fn complete_only_declared_characters() {
+        let mut repl = Repl::with_history(None).unwrap();
+        repl.run_input("Romeo, a man.".to_string(), false);
+        repl.run_input("[Enter Romeo and Hamlet]".to_string(), false);
+        assert_eq!(completed_characters(&repl), ["Romeo"]);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::lexer::{self, SyntaxKind};
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+// Some words, such as ‘the ghost’ or ‘summer's day’, span more than one
+// whitespace-separated chunk of the line.
+const MAX_WORDS: usize = 4;
+
+/// Completes the words of the language and the names of the characters that
+/// have been declared so far, offering only what can appear where the cursor
+/// is.
+#[derive(Default)]
+pub(crate) struct PuckHelper {
+    characters: Vec<String>,
+}
+
+impl PuckHelper {
+    pub(crate) fn set_characters(&mut self, characters: Vec<String>) {
+        self.characters = characters;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn characters(&self) -> &[String] {
+        &self.characters
+    }
+
+    fn candidates(&self, position: Position) -> impl Iterator<Item = &str> {
+        let characters = match position {
+            Position::LineStart | Position::StageDirection => &self.characters[..],
+            Position::Sentence => &[],
+        };
+
+        let words = lexer::vocabulary().filter_map(move |(kind, word)| {
+            let offered = match position {
+                // A line can start a declaration, which may name any
+                // character the language knows of.
+                Position::LineStart => true,
+                Position::StageDirection => matches!(
+                    kind,
+                    SyntaxKind::Enter | SyntaxKind::Exit | SyntaxKind::Exeunt | SyntaxKind::And
+                ),
+                Position::Sentence => kind != SyntaxKind::Character,
+            };
+            offered.then_some(word)
+        });
+
+        characters.iter().map(String::as_str).chain(words)
+    }
+
+    fn complete_at(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let line = &line[..pos];
+
+        // Try the longest run of trailing words first, so that typing ‘lady
+        // mac’ offers ‘lady macbeth’ rather than nothing at all.
+        for start in word_starts(line) {
+            let prefix = &line[start..];
+            let lowercase_prefix = prefix.to_lowercase();
+            let position = Position::of(&line[..start]);
+
+            let mut matches: Vec<_> = self
+                .candidates(position)
+                .filter(|candidate| {
+                    candidate.to_lowercase().starts_with(&lowercase_prefix)
+                        && candidate.len() > prefix.len()
+                })
+                .map(|candidate| match_case(prefix, candidate))
+                .collect();
+
+            if !matches.is_empty() {
+                matches.sort();
+                matches.dedup();
+                return (start, matches);
+            }
+        }
+
+        (pos, Vec::new())
+    }
+}
+
+/// Where in the input a word is being typed, which decides what it can be.
+#[derive(Clone, Copy)]
+enum Position {
+    /// The start of a line, which may declare a character, name the one
+    /// speaking, or carry on with what they are saying.
+    LineStart,
+    /// Inside square brackets, where only characters and the words for
+    /// entering and leaving the stage appear.
+    StageDirection,
+    /// Anywhere else in what a character says, where no character is named.
+    Sentence,
+}
+
+impl Position {
+    fn of(before: &str) -> Self {
+        let opened = before.rfind('[');
+        let closed = before.rfind(']');
+        if opened > closed {
+            Position::StageDirection
+        } else if before.trim().is_empty() {
+            Position::LineStart
+        } else {
+            Position::Sentence
+        }
+    }
+}
+
+/// The offsets of the last few words of the line, earliest first.
+fn word_starts(line: &str) -> Vec<usize> {
+    let is_word = |c: char| c.is_alphabetic() || c == '\'' || c == '-';
+
+    let mut starts = Vec::new();
+    let mut previous = None;
+    for (idx, c) in line.char_indices() {
+        if !is_word(c) && !c.is_whitespace() {
+            // Only runs of words separated by whitespace are completed.
+            starts.clear();
+        } else if is_word(c) && !previous.is_some_and(is_word) {
+            starts.push(idx);
+        }
+        previous = Some(c);
+    }
+
+    if !previous.is_some_and(is_word) {
+        return Vec::new();
+    }
+
+    let skip = starts.len().saturating_sub(MAX_WORDS);
+    starts.split_off(skip)
+}
+
+/// Keeps the capitalisation the user typed, so that completing ‘Rom’ gives
+/// ‘Romeo’ while ‘rom’ gives ‘romeo’.
+fn match_case(prefix: &str, candidate: &str) -> String {
+    let typed = candidate
+        .char_indices()
+        .nth(prefix.chars().count())
+        .map_or(candidate.len(), |(idx, _)| idx);
+    let rest = &candidate[typed..];
+
+    let shouting = prefix.chars().filter(|c| c.is_alphabetic()).count() > 1
+        && !prefix.chars().any(char::is_lowercase);
+
+    if shouting {
+        format!("{}{}", prefix, rest.to_uppercase())
+    } else {
+        format!("{}{}", prefix, rest)
+    }
+}
+
+impl Completer for PuckHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.complete_at(line, pos))
+    }
+}
+
+impl Hinter for PuckHelper {
+    type Hint = String;
+}
+
+impl Highlighter for PuckHelper {}
+
+impl Validator for PuckHelper {}
+
+impl Helper for PuckHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(characters: &[&str], line: &str, expected: &[&str]) {
+        let mut helper = PuckHelper::default();
+        helper.set_characters(characters.iter().map(ToString::to_string).collect());
+
+        let (start, candidates) = helper.complete_at(line, line.len());
+        let completed: Vec<_> = candidates
+            .iter()
+            .map(|candidate| format!("{}{}", &line[..start], candidate))
+            .collect();
+
+        assert_eq!(completed, expected);
+    }
+
+    #[test]
+    fn complete_vocabulary() {
+        check(&[], "You are the quot", &["You are the quotient"]);
+    }
+
+    #[test]
+    fn complete_declared_character() {
+        check(&["Yorick"], "[Enter Yor", &["[Enter Yorick"]);
+    }
+
+    #[test]
+    fn complete_multi_word_entry() {
+        check(
+            &["Lady Macbeth", "Lady Macduff"],
+            "[Enter Lady mac",
+            &["[Enter Lady macbeth", "[Enter Lady macduff"],
+        );
+    }
+
+    #[test]
+    fn complete_keeps_case() {
+        check(&[], "You are a BIG", &["You are a BIGGER"]);
+        check(&[], "Rome", &["Romeo"]);
+    }
+
+    #[test]
+    fn complete_characters_only_where_they_appear() {
+        check(&["Romeo"], "Romeo", &[]);
+        check(&["Yorick"], "Yor", &["Yorick"]);
+        check(&["Yorick"], "Juliet: You are as good as Yor", &[]);
+        check(&["Romeo"], "[Exeunt Rom", &["[Exeunt Romeo"]);
+    }
+
+    #[test]
+    fn complete_only_declared_characters_in_stage_directions() {
+        check(&["Juliet"], "[Enter Rom", &[]);
+        check(&["Juliet"], "[Enter Jul", &["[Enter Juliet"]);
+        check(&[], "[Enter Romeo a", &["[Enter Romeo and"]);
+    }
+
+    #[test]
+    fn complete_nothing() {
+        check(&[], "You are a xyz", &[]);
+    }
+}
@@ -0,0 +1,86 @@
+use std::convert::TryFrom;
+
+/// Reads the number of an act or scene, like ‘IV’. The lexer only lets
+/// through numerals of the usual form, but ‘I’ is lexed as a pronoun and
+/// can be written in lowercase.
+pub(crate) fn parse(numeral: &str) -> Option<u32> {
+    let values = numeral
+        .chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            'I' => Some(1),
+            'V' => Some(5),
+            'X' => Some(10),
+            'L' => Some(50),
+            'C' => Some(100),
+            'D' => Some(500),
+            'M' => Some(1000),
+            _ => None,
+        })
+        .collect::<Option<Vec<i64>>>()?;
+
+    // A digit counts against the number when a larger one follows it, as
+    // the I of IV does.
+    let number =
+        values
+            .iter()
+            .enumerate()
+            .fold(0, |number, (idx, value)| match values.get(idx + 1) {
+                Some(next) if next > value => number - value,
+                _ => number + value,
+            });
+
+    u32::try_from(number).ok().filter(|number| *number > 0)
+}
+
+/// Writes a number the way acts and scenes are numbered.
+pub(crate) fn format(mut number: u32) -> String {
+    const DIGITS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut numeral = String::new();
+    for (value, digits) in DIGITS {
+        while number >= value {
+            numeral.push_str(digits);
+            number -= value;
+        }
+    }
+
+    numeral
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_numerals() {
+        assert_eq!(parse("I"), Some(1));
+        assert_eq!(parse("i"), Some(1));
+        assert_eq!(parse("IV"), Some(4));
+        assert_eq!(parse("XIV"), Some(14));
+        assert_eq!(parse("MCMXCIX"), Some(1999));
+        assert_eq!(parse("me"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn format_numbers() {
+        for number in 1..=3999 {
+            assert_eq!(parse(&format(number)), Some(number), "{}", format(number));
+        }
+        assert_eq!(format(1994), "MCMXCIV");
+    }
+}
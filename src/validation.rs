@@ -0,0 +1,617 @@
+use crate::hir::{self, CharacterIdx, ConstError};
+use crate::lexer::SyntaxKind;
+use crate::syntax::{SyntaxNode, SyntaxToken};
+use crate::{ast, roman};
+use arrayvec::ArrayVec;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use text_size::TextRange;
+
+pub(crate) fn validate(
+    root: &ast::Root,
+    items: &[hir::Item],
+    db: &hir::Database,
+) -> Vec<ValidationError> {
+    validate_from(root, items, db, &[], &[])
+}
+
+/// Validates the next part of a play, which carries on with the characters
+/// that are already declared and on stage, as it does in the REPL.
+pub(crate) fn validate_from(
+    root: &ast::Root,
+    items: &[hir::Item],
+    db: &hir::Database,
+    declared: &[CharacterIdx],
+    on_stage: &[CharacterIdx],
+) -> Vec<ValidationError> {
+    let mut errors: Vec<_> = root
+        .items()
+        .zip(items)
+        .flat_map(|(ast, item)| validate_item(&ast, item, db))
+        .map(|error| error.resolve(db))
+        .collect();
+
+    errors.extend(flow(root.items().zip(items), db, declared, on_stage));
+    errors.sort_by_key(|error| error.range.start());
+
+    errors
+}
+
+/// Checks that only need the item itself, so that the results can be reused
+/// for as long as the item’s text doesn’t change.
+pub(crate) fn validate_item(
+    ast: &ast::Item,
+    item: &hir::Item,
+    db: &hir::Database,
+) -> Vec<ItemError> {
+    let mut errors = Vec::new();
+
+    match (ast, item) {
+        (
+            ast::Item::StageDirection(stage_direction),
+            hir::Item::StageDirection { characters, .. },
+        ) => {
+            let mut seen = HashSet::new();
+            for (token, character) in stage_direction.characters().zip(characters) {
+                if !seen.insert(*character) {
+                    errors.push(ItemError {
+                        kind: ItemErrorKind::RepeatedCharacter(*character),
+                        range: token.text_range(),
+                    });
+                }
+            }
+        }
+        (ast::Item::Dialog(dialog), hir::Item::Dialog { sentences, .. }) => {
+            for (ast, sentence) in dialog.sentences().zip(sentences) {
+                for expr in exprs(sentence) {
+                    let kind = match db.constant_value(expr) {
+                        Err(ConstError::DivisionByZero) => ItemErrorKind::DivisionByZero,
+                        Err(ConstError::Overflow) => ItemErrorKind::Overflow,
+                        Ok(_) | Err(ConstError::NotConstant | ConstError::Missing) => continue,
+                    };
+
+                    errors.push(ItemError {
+                        kind,
+                        range: ast.syntax().text_range(),
+                    });
+                }
+            }
+        }
+        _ => {}
+    }
+
+    errors
+}
+
+/// The expressions a sentence works out, including those of a sentence
+/// within a conditional.
+fn exprs(sentence: &hir::Sentence) -> Vec<&hir::Expr> {
+    match sentence {
+        hir::Sentence::Statement { expr } | hir::Sentence::Push { expr } => vec![expr],
+        hir::Sentence::Question { lhs, rhs, .. } => vec![lhs, rhs],
+        hir::Sentence::Conditional {
+            sentence: Some(sentence),
+            ..
+        } => exprs(sentence),
+        _ => Vec::new(),
+    }
+}
+
+/// Follows characters on and off the stage through the whole play, checking
+/// that every dialog has exactly one listener. A goto has to leave the stage
+/// as it is at the start of the scene it goes to, so that the stage can be
+/// followed from top to bottom whichever way the play goes.
+pub(crate) fn validate_flow<'a>(
+    items: impl Iterator<Item = (ast::Item, &'a hir::Item)>,
+    db: &hir::Database,
+) -> Vec<ValidationError> {
+    flow(items, db, &[], &[])
+}
+
+fn flow<'a>(
+    items: impl Iterator<Item = (ast::Item, &'a hir::Item)>,
+    db: &hir::Database,
+    declared: &[CharacterIdx],
+    on_stage: &[CharacterIdx],
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let mut declared: HashSet<_> = declared.iter().copied().collect();
+    let mut on_stage: ArrayVec<CharacterIdx, 2> = on_stage.iter().copied().collect();
+
+    // Scenes are numbered within their act, so each is known by the act
+    // it’s in as well as its number.
+    let mut act = 0;
+    let mut scenes = HashMap::new();
+    let mut gotos = Vec::new();
+
+    let mut error = |kind, range| errors.push(ValidationError { kind, range });
+
+    for (ast, item) in items {
+        match (&ast, item) {
+            (ast::Item::Act(_), hir::Item::Act { .. }) => act += 1,
+            (
+                ast::Item::Scene(heading),
+                hir::Item::Scene {
+                    number: Some(number),
+                },
+            ) => match scenes.entry((act, *number)) {
+                Entry::Occupied(_) => error(
+                    ValidationErrorKind::DuplicateScene(*number),
+                    heading.numeral().unwrap().text_range(),
+                ),
+                Entry::Vacant(entry) => {
+                    entry.insert(on_stage.clone());
+                }
+            },
+            (
+                ast::Item::CharacterDef(character_def),
+                hir::Item::CharacterDef {
+                    character: Some(character),
+                },
+            ) if !declared.insert(*character) => error(
+                ValidationErrorKind::DuplicateDeclaration(db[*character].name.clone()),
+                character_def.character().unwrap().text_range(),
+            ),
+            (
+                ast::Item::StageDirection(stage_direction),
+                hir::Item::StageDirection {
+                    characters,
+                    direction,
+                },
+            ) => {
+                for (idx, (token, character)) in
+                    stage_direction.characters().zip(characters).enumerate()
+                {
+                    let name = || db[*character].name.clone();
+                    let range = token.text_range();
+
+                    // Repeats are already reported by `validate_item`.
+                    if characters[..idx].contains(character) {
+                        continue;
+                    }
+
+                    if !declared.contains(character) {
+                        error(ValidationErrorKind::UndeclaredCharacter(name()), range);
+                        continue;
+                    }
+
+                    let position = on_stage.iter().position(|c| c == character);
+                    match (direction, position) {
+                        (hir::Direction::Enter, Some(_)) => {
+                            error(ValidationErrorKind::AlreadyOnStage(name()), range)
+                        }
+                        (hir::Direction::Enter, None) => {
+                            if on_stage.try_push(*character).is_err() {
+                                error(ValidationErrorKind::CrowdedStage(name()), range);
+                            }
+                        }
+                        (hir::Direction::Exit, Some(idx)) => {
+                            on_stage.remove(idx);
+                        }
+                        (hir::Direction::Exit, None) => {
+                            error(ValidationErrorKind::NotOnStage(name()), range)
+                        }
+                        (hir::Direction::Exeunt | hir::Direction::Missing, _) => {}
+                    }
+                }
+
+                if let hir::Direction::Exeunt = direction {
+                    on_stage.clear();
+                }
+            }
+            (
+                ast::Item::Dialog(dialog),
+                hir::Item::Dialog {
+                    character: Some(character),
+                    ..
+                },
+            ) => {
+                let name = || db[*character].name.clone();
+                let range = dialog.character().unwrap().text_range();
+
+                if !declared.contains(character) {
+                    error(ValidationErrorKind::UndeclaredCharacter(name()), range);
+                } else if !on_stage.contains(character) {
+                    error(ValidationErrorKind::NotOnStage(name()), range);
+                } else if on_stage.len() < 2 {
+                    error(ValidationErrorKind::NoListener(name()), range);
+                }
+
+                let listener = crate::analysis::listener(&on_stage, *character);
+                for sentence in dialog.sentences() {
+                    if let Some(goto) = goto(&sentence) {
+                        gotos.push((act, goto, on_stage.clone()));
+                    }
+
+                    for token in characters(sentence.syntax()) {
+                        let referenced = match db.find_character(token.text()) {
+                            Some(referenced) => referenced,
+                            None => continue,
+                        };
+                        let name = || db[referenced].name.clone();
+
+                        if !declared.contains(&referenced) {
+                            error(
+                                ValidationErrorKind::UndeclaredCharacter(name()),
+                                token.text_range(),
+                            );
+                        } else if matches!(sentence, ast::Sentence::Vocative(_))
+                            && listener.is_some_and(|listener| listener != referenced)
+                        {
+                            error(
+                                ValidationErrorKind::NotListening(name()),
+                                token.text_range(),
+                            );
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (act, goto, stage) in gotos {
+        let (scene, numeral) = match goto.scene().zip(goto.numeral()) {
+            Some(scene) => scene,
+            None => continue,
+        };
+
+        match scenes.get(&(act, scene)) {
+            None => error(
+                ValidationErrorKind::UnknownScene(scene),
+                numeral.text_range(),
+            ),
+            Some(target)
+                if target.len() != stage.len()
+                    || !target.iter().all(|character| stage.contains(character)) =>
+            {
+                error(
+                    ValidationErrorKind::DifferentStage(scene),
+                    numeral.text_range(),
+                )
+            }
+            Some(_) => {}
+        }
+    }
+
+    errors
+}
+
+/// The goto a sentence makes, if it makes one, perhaps only on some answer.
+fn goto(sentence: &ast::Sentence) -> Option<ast::Goto> {
+    match sentence {
+        ast::Sentence::Goto(goto) => Some(goto.clone()),
+        ast::Sentence::Conditional(conditional) => goto(&conditional.sentence()?),
+        _ => None,
+    }
+}
+
+/// The characters named within a sentence.
+fn characters(sentence: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    sentence
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.kind() == SyntaxKind::Character)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ValidationError {
+    pub(crate) kind: ValidationErrorKind,
+    pub(crate) range: TextRange,
+}
+
+/// An error found by `validate_item`. Characters are kept as indices rather
+/// than names, as a cached error can outlive the spelling of a declaration
+/// elsewhere in the play.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ItemError {
+    kind: ItemErrorKind,
+    pub(crate) range: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ItemErrorKind {
+    RepeatedCharacter(CharacterIdx),
+    DivisionByZero,
+    Overflow,
+}
+
+impl ItemError {
+    /// Names the characters in the error as they are currently declared.
+    pub(crate) fn resolve(&self, db: &hir::Database) -> ValidationError {
+        let kind = match self.kind {
+            ItemErrorKind::RepeatedCharacter(character) => {
+                ValidationErrorKind::RepeatedCharacter(db[character].name.clone())
+            }
+            ItemErrorKind::DivisionByZero => ValidationErrorKind::DivisionByZero,
+            ItemErrorKind::Overflow => ValidationErrorKind::Overflow,
+        };
+
+        ValidationError {
+            kind,
+            range: self.range,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ValidationErrorKind {
+    UndeclaredCharacter(String),
+    DuplicateDeclaration(String),
+    RepeatedCharacter(String),
+    AlreadyOnStage(String),
+    NotOnStage(String),
+    CrowdedStage(String),
+    NoListener(String),
+    NotListening(String),
+    DivisionByZero,
+    Overflow,
+    UnknownScene(u32),
+    DuplicateScene(u32),
+    DifferentStage(u32),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error at {}..{}: {}",
+            u32::from(self.range.start()),
+            u32::from(self.range.end()),
+            self.kind,
+        )
+    }
+}
+
+impl ValidationErrorKind {
+    /// A stable name for the kind of error, for tools that consume
+    /// diagnostics.
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            ValidationErrorKind::UndeclaredCharacter(_) => "undeclared-character",
+            ValidationErrorKind::DuplicateDeclaration(_) => "duplicate-declaration",
+            ValidationErrorKind::RepeatedCharacter(_) => "repeated-character",
+            ValidationErrorKind::AlreadyOnStage(_) => "already-on-stage",
+            ValidationErrorKind::NotOnStage(_) => "not-on-stage",
+            ValidationErrorKind::CrowdedStage(_) => "crowded-stage",
+            ValidationErrorKind::NoListener(_) => "no-listener",
+            ValidationErrorKind::NotListening(_) => "not-listening",
+            ValidationErrorKind::DivisionByZero => "division-by-zero",
+            ValidationErrorKind::Overflow => "overflow",
+            ValidationErrorKind::UnknownScene(_) => "unknown-scene",
+            ValidationErrorKind::DuplicateScene(_) => "duplicate-scene",
+            ValidationErrorKind::DifferentStage(_) => "different-stage",
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationErrorKind::UndeclaredCharacter(name) => {
+                write!(f, "{} is not in the dramatis personae", name)
+            }
+            ValidationErrorKind::DuplicateDeclaration(name) => {
+                write!(f, "{} is already in the dramatis personae", name)
+            }
+            ValidationErrorKind::RepeatedCharacter(name) => {
+                write!(f, "{} is named more than once", name)
+            }
+            ValidationErrorKind::AlreadyOnStage(name) => write!(f, "{} is already on stage", name),
+            ValidationErrorKind::NotOnStage(name) => write!(f, "{} is not on stage", name),
+            ValidationErrorKind::CrowdedStage(name) => {
+                write!(
+                    f,
+                    "{} cannot enter as two characters are already on stage",
+                    name
+                )
+            }
+            ValidationErrorKind::NoListener(name) => {
+                write!(f, "{} has no one on stage to talk to", name)
+            }
+            ValidationErrorKind::NotListening(name) => {
+                write!(f, "{} is not the one being spoken to", name)
+            }
+            ValidationErrorKind::DivisionByZero => write!(f, "division by zero"),
+            ValidationErrorKind::Overflow => write!(f, "value is too large"),
+            ValidationErrorKind::UnknownScene(scene) => {
+                write!(f, "there is no scene {} in this act", roman::format(*scene))
+            }
+            ValidationErrorKind::DuplicateScene(scene) => {
+                write!(f, "scene {} is already in this act", roman::format(*scene))
+            }
+            ValidationErrorKind::DifferentStage(scene) => write!(
+                f,
+                "the stage is not as it is at the start of scene {}",
+                roman::format(*scene)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn check(input: &str, expected: &[&str]) {
+        let root = ast::Root::cast(parse(input, false).syntax_node()).unwrap();
+        let mut db = hir::Database::default();
+        let items = hir::lower(&root, &mut db);
+
+        let errors: Vec<_> = validate(&root, &items, &db)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(errors, expected);
+    }
+
+    #[test]
+    fn validate_valid_play() {
+        check(
+            "Romeo, a man. Juliet, a woman. [Enter Romeo and Juliet] Romeo: You are a cat. [Exeunt]",
+            &[],
+        );
+    }
+
+    #[test]
+    fn validate_undeclared_character() {
+        check(
+            "Romeo, a man. [Enter Romeo and Juliet]",
+            &["error at 31..37: Juliet is not in the dramatis personae"],
+        );
+    }
+
+    #[test]
+    fn validate_duplicate_declaration() {
+        check(
+            "Romeo, a man. ROMEO, a man.",
+            &["error at 14..19: ROMEO is already in the dramatis personae"],
+        );
+    }
+
+    #[test]
+    fn validate_stage() {
+        check(
+            "Romeo, a man. Juliet, a woman. Puck, a sprite.
+[Enter Romeo and Romeo]
+[Enter Juliet and Puck]
+[Exit Juliet]
+[Exit Juliet]
+Romeo: You are a cat.",
+            &[
+                "error at 64..69: Romeo is named more than once",
+                "error at 89..93: Puck cannot enter as two characters are already on stage",
+                "error at 115..121: Juliet is not on stage",
+                "error at 123..128: Romeo has no one on stage to talk to",
+            ],
+        );
+    }
+
+    #[test]
+    fn validate_exeunt_clears_stage() {
+        check(
+            "Romeo, a man. Juliet, a woman. [Enter Romeo and Juliet] [Exeunt] Romeo: You are a cat.",
+            &["error at 65..70: Romeo is not on stage"],
+        );
+    }
+
+    #[test]
+    fn validate_characters_in_sentences() {
+        check(
+            "Romeo, a man. Juliet, a woman. Puck, a sprite. [Enter Romeo and Juliet]
+Romeo: Puck, you are Tybalt. Juliet, you are the sum of Puck and myself.",
+            &[
+                "error at 79..83: Puck is not the one being spoken to",
+                "error at 93..99: Tybalt is not in the dramatis personae",
+            ],
+        );
+    }
+
+    #[test]
+    fn validate_division_by_nothing_with_characters() {
+        check(
+            "Romeo, a man. Juliet, a woman. [Enter Romeo and Juliet]
+Romeo: You are the quotient between Juliet and nothing. You are the quotient between nothing and Juliet.",
+            &["error at 63..112: division by zero"],
+        );
+    }
+
+    #[test]
+    fn validate_scenes() {
+        check(
+            "Romeo, a man. Juliet, a woman.
+Act I: Gotos.
+Scene I: The start. [Enter Romeo and Juliet]
+Romeo: Let us proceed to scene III. If so, let us return to scene I.
+Scene II: More.
+Scene II: Again.
+[Exit Juliet]
+Act II: Elsewhere.
+Scene III: Here.",
+            &[
+                "error at 121..124: there is no scene III in this act",
+                "error at 156..157: the stage is not as it is at the start of scene I",
+                "error at 181..183: scene II is already in this act",
+            ],
+        );
+        check(
+            "Romeo, a man. Juliet, a woman.
+Act I: A loop. Scene I: The setup. [Enter Romeo and Juliet]
+Scene II: The loop.
+Romeo: Are you better than nothing? If so, let us return to scene II.
+Juliet: Let us proceed to scene III.
+Scene III: The end.",
+            &[],
+        );
+    }
+
+    #[test]
+    fn validate_arithmetic_in_questions_and_stacks() {
+        check(
+            "Romeo, a man. Juliet, a woman. [Enter Romeo and Juliet]
+Romeo: Are you better than the quotient between a cat and nothing? Remember the quotient between a cat and nothing. If so, you are the quotient between a cat and nothing.",
+            &[
+                "error at 63..123: division by zero",
+                "error at 123..172: division by zero",
+                "error at 172..226: division by zero",
+            ],
+        );
+    }
+
+    #[test]
+    fn validate_constant_arithmetic() {
+        check(
+            "Romeo: You are the quotient between a cat and nothing. You are a big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big big cat.",
+            &[
+                "error at 0..5: Romeo is not in the dramatis personae",
+                "error at 7..55: division by zero",
+                "error at 55..325: value is too large",
+            ],
+        );
+    }
+
+    #[test]
+    fn validate_unary_overflow() {
+        check(
+            "Romeo, a test. Juliet, a test. [Enter Romeo and Juliet]
+Romeo: You are the cube of a big big big big big big big big big big big big big big big big big big big big big cat.
+You are twice the cube of a big big big big big big big big big big big big big big big big big big big big cat.",
+            &["error at 63..174: value is too large"],
+        );
+    }
+
+    #[test]
+    fn validate_from_earlier_input() {
+        let mut db = hir::Database::default();
+        let root = ast::Root::cast(parse("Romeo, a test. Juliet, a test.", false).syntax_node());
+        hir::lower(&root.unwrap(), &mut db);
+        let romeo = db.find_character("romeo").unwrap();
+        let juliet = db.find_character("juliet").unwrap();
+
+        let input = "[Enter Romeo and Juliet] Juliet: You are nothing.";
+        let root = ast::Root::cast(parse(input, false).syntax_node()).unwrap();
+        let items = hir::lower(&root, &mut db);
+
+        let errors: Vec<_> = validate_from(&root, &items, &db, &[romeo, juliet], &[romeo])
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(errors, ["error at 7..12: Romeo is already on stage"]);
+
+        let errors: Vec<_> = validate(&root, &items, &db)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "error at 7..12: Romeo is not in the dramatis personae",
+                "error at 17..23: Juliet is not in the dramatis personae",
+                "error at 25..31: Juliet is not in the dramatis personae",
+            ]
+        );
+    }
+}